@@ -0,0 +1,67 @@
+//! Compares verifying signatures with a key that is parsed for each request, against a key that
+//! is parsed once up front (as the webhooks do).
+//!
+//! Run with `cargo bench --all-features --bench public_key`
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use rocket_webhook::webhooks::interface::public_key::{
+    PublicKey, WebhookPublicKeyAlgorithm,
+    algorithms::{ed25519::Ed25519, p256::EcdsaP256Asn1},
+};
+
+const P256_KEY: &str = "033db96d330fa33bc0a3f57c7f3b702a9bf52f0fa77f44d1c6fe42cd4f924d863d";
+const P256_MESSAGE: &[u8] = b"Hello ECDSA";
+const P256_SIGNATURE: &str = "3044022078a0a351ff240ab66dd1bd493de5721fcb1b9a0c6d02d7c9f050533971\
+    ea019d02202b2106c8d6c336e8b7ff59c0a1caa7bd3946bf42d95d3403091fab2b12dbe686";
+
+const ED25519_KEY: &str = "03a107bff3ce10be1d70dd18e74bc09967e4d6309ba50d5f1ddc8664125531b8";
+const ED25519_MESSAGE: &[u8] = b"1760414077|Hello Ed25519";
+const ED25519_SIGNATURE: &str = "f6daa437e7a8e82aa899652261095530c52f954d7676f453664e376dd59bfc88\
+    56175de26712203999a664ca1a5ea40bf8d951c37e8de924b749b547988dd507";
+
+/// Benchmark parsing the key for each verification, against reusing the parsed key. The cost of
+/// parsing alone is also measured.
+fn bench_algorithm<A: WebhookPublicKeyAlgorithm>(
+    c: &mut Criterion,
+    name: &str,
+    key: &str,
+    message: &[u8],
+    signature: &str,
+) {
+    let key_bytes = hex::decode(key).unwrap();
+    let signature = hex::decode(signature).unwrap();
+    let parsed_key = PublicKey::<A>::parse(&key_bytes).unwrap();
+
+    let mut group = c.benchmark_group(name);
+    group.bench_function("parse only", |b| {
+        b.iter(|| PublicKey::<A>::parse(black_box(&key_bytes)).unwrap())
+    });
+    group.bench_function("parse per request", |b| {
+        b.iter(|| {
+            let key = PublicKey::<A>::parse(black_box(&key_bytes)).unwrap();
+            A::verify(key.key(), black_box(message), black_box(&signature)).unwrap();
+        })
+    });
+    group.bench_function("pre-parsed", |b| {
+        b.iter(|| {
+            A::verify(parsed_key.key(), black_box(message), black_box(&signature)).unwrap();
+        })
+    });
+    group.finish();
+}
+
+fn public_key(c: &mut Criterion) {
+    bench_algorithm::<EcdsaP256Asn1>(c, "ecdsa_p256", P256_KEY, P256_MESSAGE, P256_SIGNATURE);
+    bench_algorithm::<Ed25519>(
+        c,
+        "ed25519",
+        ED25519_KEY,
+        ED25519_MESSAGE,
+        ED25519_SIGNATURE,
+    );
+}
+
+criterion_group!(benches, public_key);
+criterion_main!(benches);
@@ -0,0 +1,99 @@
+//! Automatic responses to provider verification challenges
+
+use std::io::Cursor;
+
+use rocket::{
+    Build, Request, Response, Rocket, async_trait,
+    fairing::{self, Fairing, Info, Kind},
+    http::{ContentType, Status},
+    serde::{Serialize, json::serde_json},
+};
+
+/// Response to a verification challenge from the provider, sent with 200 OK
+#[derive(Debug, Clone)]
+pub struct ChallengeResponse {
+    /// Content type of the response
+    pub content_type: ContentType,
+    /// Body of the response
+    pub body: String,
+}
+
+impl ChallengeResponse {
+    /// Respond with plain text
+    pub fn text(body: impl Into<String>) -> Self {
+        Self {
+            content_type: ContentType::Plain,
+            body: body.into(),
+        }
+    }
+
+    /// Respond with JSON
+    pub fn json(body: &impl Serialize) -> Self {
+        Self {
+            content_type: ContentType::JSON,
+            body: serde_json::to_string(body).expect("challenge response should serialize"),
+        }
+    }
+}
+
+/**
+Fairing that automatically answers verification challenges from webhook providers, such as
+Slack's `url_verification` event or Discord's PING interaction. Challenges are only answered
+after the webhook signature is validated, and the route handler will only receive real events.
+
+Without this fairing, challenges are passed to the route handler like any other event.
+
+# Example
+```
+use rocket::{post, routes};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw, challenge::WebhookChallenges,
+    webhooks::built_in::SlackWebhook,
+};
+
+#[post("/slack/events", data = "<payload>")]
+async fn slack_events(payload: WebhookPayloadRaw<'_, SlackWebhook>) {
+    // only real events get here
+}
+
+let slack_webhook = RocketWebhook::builder()
+    .webhook(SlackWebhook::with_secret("my-slack-secret"))
+    .build();
+let rocket = rocket::build()
+    .manage(slack_webhook)
+    .attach(WebhookChallenges)
+    .mount("/", routes![slack_events]);
+```
+
+Meta's challenge is a separate GET request, see
+[MetaWebhook::verification_route](crate::webhooks::built_in::MetaWebhook::verification_route).
+*/
+pub struct WebhookChallenges;
+
+/// Marker in Rocket state, set when the fairing is attached
+pub(crate) struct ChallengesEnabled;
+
+/// The answered challenge, stored in the request-local cache
+pub(crate) struct AnsweredChallenge(pub(crate) Option<ChallengeResponse>);
+
+#[async_trait]
+impl Fairing for WebhookChallenges {
+    fn info(&self) -> Info {
+        Info {
+            name: "Webhook challenge responder",
+            kind: Kind::Ignite | Kind::Response,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket.manage(ChallengesEnabled))
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        if let Some(challenge) = &req.local_cache(|| AnsweredChallenge(None)).0 {
+            res.set_status(Status::Ok);
+            res.set_header(challenge.content_type.clone());
+            res.set_sized_body(challenge.body.len(), Cursor::new(challenge.body.clone()));
+        }
+    }
+}
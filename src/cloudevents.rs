@@ -0,0 +1,483 @@
+//! CloudEvents data guards
+
+use std::marker::PhantomData;
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use rocket::{
+    Data, Request, async_trait,
+    data::{FromData, Outcome},
+    http::{HeaderMap, RawStr},
+    outcome::try_outcome,
+    serde::{
+        DeserializeOwned,
+        json::{
+            Value,
+            serde_json::{self, Map},
+        },
+    },
+};
+
+use crate::{
+    WebhookError,
+    guard::validate_request,
+    webhooks::{ValidatedBody, Webhook},
+};
+
+const SPEC_VERSION: &str = "1.0";
+const HEADER_PREFIX: &str = "ce-";
+const STRUCTURED_CONTENT_TYPE: &str = "application/cloudevents+json";
+const BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
+
+/**
+Data guard to validate a webhook of type `W`, and parse the body as a
+[CloudEvent](https://cloudevents.io) with data of the `T` type. Supports the binary mode (attributes
+in `ce-*` headers, and the data in the body) and the structured mode (the event as JSON with the
+`application/cloudevents+json` content type) of the HTTP binding. Use [CloudEventBatch] to also
+receive batches of events.
+
+The data is deserialized from JSON, or from a string if the content type of the data isn't JSON
+(e.g. `text/plain` data into a `String`). Use an `Option` for events that may not have data.
+The `W` webhook configuration must be in Rocket state using [RocketWebhook](crate::RocketWebhook).
+
+Events that aren't valid CloudEvents are rejected with a [WebhookError::InvalidEvent] error (or
+[WebhookError::MissingHeader] for a missing required header in binary mode), and data that doesn't
+match the `T` type with a [WebhookError::Deserialize] error.
+
+```
+use rocket::{post, serde::Deserialize};
+use rocket_webhook::{CloudEvent, webhooks::generic::TokenWebhook};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct BlobCreated {
+    url: String,
+}
+
+#[post("/api/webhooks/events", data = "<event>")]
+async fn events_route(event: CloudEvent<'_, BlobCreated, TokenWebhook>) -> String {
+    format!("{} from {}: {}", event.event_type, event.source, event.data.url)
+}
+```
+*/
+pub struct CloudEvent<'r, T, W, M = W> {
+    /// Unique ID of the event, for its source (`id`)
+    pub id: String,
+    /// Context in which the event happened, as a URI reference (`source`)
+    pub source: String,
+    /// Type of the event, e.g. `com.example.object.deleted.v2` (`type`)
+    pub event_type: String,
+    /// CloudEvents specification version (`specversion`)
+    pub spec_version: String,
+    /// Subject of the event, in the context of its source (`subject`)
+    pub subject: Option<String>,
+    /// Time the event happened, in RFC 3339 format (`time`)
+    pub time: Option<String>,
+    /// Content type of the data (`datacontenttype`)
+    pub data_content_type: Option<String>,
+    /// URI of the schema of the data (`dataschema`)
+    pub data_schema: Option<String>,
+    /// Extension attributes. In binary mode, the values are always strings.
+    pub extensions: Map<String, Value>,
+    /// The deserialized data
+    pub data: T,
+    /// The headers sent with the webhook request
+    pub headers: &'r HeaderMap<'r>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
+    _webhook: PhantomData<W>,
+    _marker: PhantomData<M>,
+}
+
+#[async_trait]
+impl<'r, T, W, M> FromData<'r> for CloudEvent<'r, T, W, M>
+where
+    T: DeserializeOwned,
+    W: Webhook + Send + Sync + 'static,
+    M: Send + Sync + 'static,
+{
+    type Error = WebhookError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        let (_, validated_body) = try_outcome!(validate_request::<W, M>(req, data).await);
+
+        let event = match content_mode(req.headers()) {
+            ContentMode::Batch => Err(WebhookError::InvalidEvent(
+                "Received a batch of events, use the CloudEventBatch guard to receive batches"
+                    .into(),
+            )),
+            ContentMode::Binary => parse_binary(req.headers(), &validated_body),
+            ContentMode::Structured => serde_json::from_slice(&validated_body.data)
+                .map_err(WebhookError::Deserialize)
+                .and_then(|event| parse_structured(event, req.headers(), &validated_body)),
+            ContentMode::Unknown => Err(not_cloud_event()),
+        };
+        match event {
+            Ok(event) => Outcome::Success(event),
+            Err(err) => Outcome::Error((err.status(), err)),
+        }
+    }
+}
+
+/**
+Data guard to validate a webhook of type `W`, and parse a batch of [CloudEvents](CloudEvent) with
+data of the `T` type (sent with the `application/cloudevents-batch+json` content type). Single
+events in binary or structured mode are also accepted, as a batch of one event.
+The `W` webhook configuration must be in Rocket state using [RocketWebhook](crate::RocketWebhook).
+
+```
+use rocket::post;
+use rocket_webhook::{CloudEventBatch, webhooks::generic::TokenWebhook};
+
+#[post("/api/webhooks/events", data = "<batch>")]
+async fn events_route(batch: CloudEventBatch<'_, Option<String>, TokenWebhook>) -> String {
+    let ids: Vec<_> = batch.events.into_iter().map(|event| event.id).collect();
+    ids.join(",")
+}
+```
+*/
+pub struct CloudEventBatch<'r, T, W, M = W> {
+    /// The events of the batch
+    pub events: Vec<CloudEvent<'r, T, W, M>>,
+    /// The headers sent with the webhook request
+    pub headers: &'r HeaderMap<'r>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
+}
+
+#[async_trait]
+impl<'r, T, W, M> FromData<'r> for CloudEventBatch<'r, T, W, M>
+where
+    T: DeserializeOwned,
+    W: Webhook + Send + Sync + 'static,
+    M: Send + Sync + 'static,
+{
+    type Error = WebhookError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> Outcome<'r, Self, Self::Error> {
+        let (_, validated_body) = try_outcome!(validate_request::<W, M>(req, data).await);
+
+        let headers = req.headers();
+        let events = match content_mode(headers) {
+            ContentMode::Batch => parse_batch(headers, &validated_body),
+            ContentMode::Binary => parse_binary(headers, &validated_body).map(|event| vec![event]),
+            ContentMode::Structured => serde_json::from_slice(&validated_body.data)
+                .map_err(WebhookError::Deserialize)
+                .and_then(|event| parse_structured(event, headers, &validated_body))
+                .map(|event| vec![event]),
+            ContentMode::Unknown => Err(not_cloud_event()),
+        };
+        match events {
+            Ok(events) => Outcome::Success(Self {
+                events,
+                headers,
+                secret_index: validated_body.secret_index,
+            }),
+            Err(err) => Outcome::Error((err.status(), err)),
+        }
+    }
+}
+
+/// How the event(s) are sent in the request
+enum ContentMode {
+    /// Attributes in `ce-*` headers, and the data in the body
+    Binary,
+    /// The event as JSON
+    Structured,
+    /// Multiple events as a JSON array
+    Batch,
+    /// Not a CloudEvent
+    Unknown,
+}
+
+fn content_mode(headers: &HeaderMap<'_>) -> ContentMode {
+    // the CloudEvents content types mean structured mode, even if `ce-` headers are also present
+    match media_type(headers.get_one("Content-Type")).as_deref() {
+        Some(STRUCTURED_CONTENT_TYPE) => ContentMode::Structured,
+        Some(BATCH_CONTENT_TYPE) => ContentMode::Batch,
+        _ if headers.contains("ce-specversion") => ContentMode::Binary,
+        _ => ContentMode::Unknown,
+    }
+}
+
+fn not_cloud_event() -> WebhookError {
+    WebhookError::InvalidEvent(format!(
+        "Request has no ce-specversion header, and its content type is not \
+        {STRUCTURED_CONTENT_TYPE} or {BATCH_CONTENT_TYPE}"
+    ))
+}
+
+/// The media type of a content type, without parameters (lowercase)
+fn media_type(content_type: Option<&str>) -> Option<String> {
+    content_type.map(|content_type| {
+        let media_type = content_type.split(';').next().unwrap_or_default();
+        media_type.trim().to_ascii_lowercase()
+    })
+}
+
+/// The data of an event
+enum EventData {
+    /// Raw data (binary mode, or `data_base64` in structured mode)
+    Raw(Vec<u8>),
+    /// JSON data (`data` in structured mode)
+    Json(Value),
+    /// The event has no data
+    None,
+}
+
+/// Context attributes of an event
+struct Attributes {
+    id: String,
+    source: String,
+    event_type: String,
+    spec_version: String,
+    subject: Option<String>,
+    time: Option<String>,
+    data_content_type: Option<String>,
+    data_schema: Option<String>,
+    extensions: Map<String, Value>,
+}
+
+impl Attributes {
+    /// Build the event with the deserialized data
+    fn into_event<'r, T, W, M>(
+        self,
+        data: EventData,
+        headers: &'r HeaderMap<'r>,
+        validated_body: &ValidatedBody,
+    ) -> Result<CloudEvent<'r, T, W, M>, WebhookError>
+    where
+        T: DeserializeOwned,
+    {
+        let data = match data {
+            EventData::Json(value) => serde_json::from_value(value),
+            EventData::None => serde_json::from_value(Value::Null),
+            EventData::Raw(raw) if is_json(self.data_content_type.as_deref()) => {
+                serde_json::from_slice(&raw)
+            }
+            EventData::Raw(raw) => {
+                let text = String::from_utf8(raw).map_err(|_| {
+                    WebhookError::InvalidEvent(format!(
+                        "Data with content type '{}' is not JSON or text",
+                        self.data_content_type.as_deref().unwrap_or_default()
+                    ))
+                })?;
+                serde_json::from_value(Value::String(text))
+            }
+        }
+        .map_err(WebhookError::Deserialize)?;
+
+        Ok(CloudEvent {
+            id: self.id,
+            source: self.source,
+            event_type: self.event_type,
+            spec_version: self.spec_version,
+            subject: self.subject,
+            time: self.time,
+            data_content_type: self.data_content_type,
+            data_schema: self.data_schema,
+            extensions: self.extensions,
+            data,
+            headers,
+            secret_index: validated_body.secret_index,
+            _webhook: PhantomData,
+            _marker: PhantomData,
+        })
+    }
+}
+
+/// Whether data with the content type is JSON. Data without a content type is assumed to be JSON.
+fn is_json(content_type: Option<&str>) -> bool {
+    match media_type(content_type).as_deref() {
+        None => true,
+        Some(media_type) => {
+            media_type == "application/json"
+                || media_type == "text/json"
+                || media_type.ends_with("+json")
+        }
+    }
+}
+
+/// Parse an event in binary mode, from the `ce-*` headers and the body
+fn parse_binary<'r, T: DeserializeOwned, W, M>(
+    headers: &'r HeaderMap<'r>,
+    validated_body: &ValidatedBody,
+) -> Result<CloudEvent<'r, T, W, M>, WebhookError> {
+    let header = |name: &str| {
+        headers
+            .get_one(&format!("{HEADER_PREFIX}{name}"))
+            .map(|value| RawStr::new(value).percent_decode_lossy().into_owned())
+    };
+    let required = |name: &str| match header(name) {
+        Some(value) if !value.is_empty() => Ok(value),
+        Some(_) => Err(WebhookError::InvalidHeader(format!(
+            "{HEADER_PREFIX}{name} header is empty"
+        ))),
+        None => Err(WebhookError::MissingHeader(format!(
+            "{HEADER_PREFIX}{name}"
+        ))),
+    };
+
+    let spec_version = required("specversion")?;
+    if spec_version != SPEC_VERSION {
+        return Err(WebhookError::InvalidHeader(format!(
+            "Unsupported CloudEvents version '{spec_version}'"
+        )));
+    }
+    if headers.contains("ce-datacontenttype") {
+        return Err(WebhookError::InvalidEvent(
+            "The data content type must be sent in the Content-Type header, not ce-datacontenttype"
+                .into(),
+        ));
+    }
+    let mut extensions = Map::new();
+    for header in headers.iter() {
+        let name = header.name().as_str().to_ascii_lowercase();
+        if let Some(attribute) = name.strip_prefix(HEADER_PREFIX)
+            && !is_context_attribute(attribute)
+        {
+            check_attribute_name(attribute)?;
+            let value = RawStr::new(header.value()).percent_decode_lossy();
+            extensions.insert(attribute.to_owned(), Value::String(value.into_owned()));
+        }
+    }
+
+    let attributes = Attributes {
+        id: required("id")?,
+        source: required("source")?,
+        event_type: required("type")?,
+        spec_version,
+        subject: header("subject"),
+        time: header("time"),
+        data_content_type: headers.get_one("Content-Type").map(str::to_owned),
+        data_schema: header("dataschema"),
+        extensions,
+    };
+    let data = if validated_body.data.is_empty() {
+        EventData::None
+    } else {
+        EventData::Raw(validated_body.data.clone())
+    };
+    attributes.into_event(data, headers, validated_body)
+}
+
+/// Parse an event in structured mode, from its JSON representation
+fn parse_structured<'r, T: DeserializeOwned, W, M>(
+    event: Value,
+    headers: &'r HeaderMap<'r>,
+    validated_body: &ValidatedBody,
+) -> Result<CloudEvent<'r, T, W, M>, WebhookError> {
+    let Value::Object(mut event) = event else {
+        return Err(WebhookError::InvalidEvent(
+            "Event is not a JSON object".into(),
+        ));
+    };
+    let mut optional = |name: &str| match event.remove(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(value)) => Ok(Some(value)),
+        Some(_) => Err(WebhookError::InvalidEvent(format!(
+            "Attribute '{name}' is not a string"
+        ))),
+    };
+    let mut required = |name: &str| match optional(name)? {
+        Some(value) if !value.is_empty() => Ok(value),
+        Some(_) => Err(WebhookError::InvalidEvent(format!(
+            "Attribute '{name}' is empty"
+        ))),
+        None => Err(WebhookError::InvalidEvent(format!(
+            "Missing required attribute '{name}'"
+        ))),
+    };
+
+    let spec_version = required("specversion")?;
+    if spec_version != SPEC_VERSION {
+        return Err(WebhookError::InvalidEvent(format!(
+            "Unsupported CloudEvents version '{spec_version}'"
+        )));
+    }
+    let id = required("id")?;
+    let source = required("source")?;
+    let event_type = required("type")?;
+    let subject = optional("subject")?;
+    let time = optional("time")?;
+    let data_content_type = optional("datacontenttype")?;
+    let data_schema = optional("dataschema")?;
+    let data_base64 = optional("data_base64")?;
+
+    let data = match (event.remove("data"), data_base64) {
+        (Some(_), Some(_)) => {
+            return Err(WebhookError::InvalidEvent(
+                "Event has both 'data' and 'data_base64'".into(),
+            ));
+        }
+        (Some(data), None) => EventData::Json(data),
+        (None, Some(data)) => EventData::Raw(BASE64_STANDARD.decode(data).map_err(|e| {
+            WebhookError::InvalidEvent(format!("Attribute 'data_base64' is not base64: {e}"))
+        })?),
+        (None, None) => EventData::None,
+    };
+    for name in event.keys() {
+        check_attribute_name(name)?;
+    }
+
+    let attributes = Attributes {
+        id,
+        source,
+        event_type,
+        spec_version,
+        subject,
+        time,
+        data_content_type,
+        data_schema,
+        extensions: event,
+    };
+    attributes.into_event(data, headers, validated_body)
+}
+
+/// Parse a batch of events in structured mode
+fn parse_batch<'r, T: DeserializeOwned, W, M>(
+    headers: &'r HeaderMap<'r>,
+    validated_body: &ValidatedBody,
+) -> Result<Vec<CloudEvent<'r, T, W, M>>, WebhookError> {
+    let batch: Value =
+        serde_json::from_slice(&validated_body.data).map_err(WebhookError::Deserialize)?;
+    let Value::Array(events) = batch else {
+        return Err(WebhookError::InvalidEvent(
+            "Batch is not a JSON array".into(),
+        ));
+    };
+    events
+        .into_iter()
+        .map(|event| parse_structured(event, headers, validated_body))
+        .collect()
+}
+
+/// Whether the attribute is a context attribute (not an extension)
+fn is_context_attribute(name: &str) -> bool {
+    matches!(
+        name,
+        "id" | "source"
+            | "type"
+            | "specversion"
+            | "datacontenttype"
+            | "subject"
+            | "time"
+            | "dataschema"
+    )
+}
+
+/// Check that the attribute name only has lowercase letters and digits
+fn check_attribute_name(name: &str) -> Result<(), WebhookError> {
+    let is_valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit());
+    if is_valid {
+        Ok(())
+    } else {
+        Err(WebhookError::InvalidEvent(format!(
+            "Invalid attribute name '{name}'"
+        )))
+    }
+}
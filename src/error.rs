@@ -1,5 +1,7 @@
 use std::{error::Error, fmt::Display};
 
+use rocket::http::Status;
+
 /// Possible errors when receiving a webhook
 #[derive(Debug)]
 pub enum WebhookError {
@@ -11,12 +13,47 @@ pub enum WebhookError {
     InvalidHeader(String),
     /// Timestamp was invalid and/or not within expected bounds
     Timestamp(String),
+    /// The webhook payload is not a valid event (e.g. a CloudEvent without a required attribute)
+    InvalidEvent(String),
     /// Error deserializing webhook payload
     Deserialize(rocket::serde::json::serde_json::Error),
+    /// Error deserializing a form (`application/x-www-form-urlencoded`) webhook payload
+    #[cfg(feature = "form")]
+    Form(serde_urlencoded::de::Error),
     /// Error while reading the body of the webhook
     Read(std::io::Error),
+    /// The body of the webhook exceeded the maximum size (in bytes)
+    PayloadTooLarge(u32),
+    /// The webhook delivery with this ID was already received
+    Duplicate(String),
+    /// Failed to get the secret key(s) for the webhook
+    Secret(String),
     /// The webhook was not setup properly on the Rocket instance
     NotAttached,
+    /// The webhook was a verification challenge from the provider, which was answered
+    /// automatically (see [WebhookChallenges](crate::challenge::WebhookChallenges))
+    Challenge,
+}
+
+impl WebhookError {
+    /// The default HTTP status to respond with for this error
+    pub fn status(&self) -> Status {
+        match self {
+            WebhookError::Signature(_) => Status::Unauthorized,
+            WebhookError::MissingHeader(_)
+            | WebhookError::InvalidHeader(_)
+            | WebhookError::Timestamp(_)
+            | WebhookError::InvalidEvent(_)
+            | WebhookError::Deserialize(_)
+            | WebhookError::Read(_) => Status::BadRequest,
+            #[cfg(feature = "form")]
+            WebhookError::Form(_) => Status::BadRequest,
+            WebhookError::PayloadTooLarge(_) => Status::PayloadTooLarge,
+            WebhookError::Duplicate(_) => Status::Conflict,
+            WebhookError::Secret(_) | WebhookError::NotAttached => Status::InternalServerError,
+            WebhookError::Challenge => Status::Ok,
+        }
+    }
 }
 
 impl Display for WebhookError {
@@ -26,13 +63,22 @@ impl Display for WebhookError {
             WebhookError::MissingHeader(name) => write!(f, "Missing header '{name}'"),
             WebhookError::InvalidHeader(err) => write!(f, "Header has invalid format: {err}"),
             WebhookError::Timestamp(time) => write!(f, "Invalid timestamp: {time}"),
+            WebhookError::InvalidEvent(err) => write!(f, "Invalid event: {err}"),
             WebhookError::Deserialize(err) => {
                 write!(f, "Failed to deserialize webhook payload: {err}")
             }
+            #[cfg(feature = "form")]
+            WebhookError::Form(err) => write!(f, "Failed to deserialize webhook form: {err}"),
             WebhookError::Read(err) => write!(f, "Failed to read webhook body: {err}"),
+            WebhookError::PayloadTooLarge(limit) => {
+                write!(f, "Webhook body exceeds the maximum size of {limit} bytes")
+            }
+            WebhookError::Duplicate(id) => write!(f, "Duplicate webhook delivery: {id}"),
+            WebhookError::Secret(err) => write!(f, "Failed to get secret key: {err}"),
             WebhookError::NotAttached => {
                 write!(f, "Webhook of this type is not attached to Rocket")
             }
+            WebhookError::Challenge => write!(f, "Verification challenge was answered"),
         }
     }
 }
@@ -41,6 +87,8 @@ impl Error for WebhookError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
             WebhookError::Deserialize(err) => Some(err),
+            #[cfg(feature = "form")]
+            WebhookError::Form(err) => Some(err),
             WebhookError::Read(err) => Some(err),
             _ => None,
         }
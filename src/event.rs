@@ -0,0 +1,97 @@
+//! Typed webhook events
+
+#[doc(hidden)]
+pub use rocket::serde::json::serde_json;
+
+/**
+Trait for event types that are deserialized using both the provider's event type (e.g. from the
+`X-GitHub-Event` header) and the body. Used by the [WebhookEvent](crate::WebhookEvent) data guard.
+
+The easiest way to implement this is with the [webhook_event!](crate::webhook_event) macro.
+*/
+pub trait FromWebhookEvent: Sized {
+    /// Deserialize the event from the event type (if the webhook provides one) and the raw body
+    fn from_event(event_type: Option<&str>, body: &[u8]) -> Result<Self, serde_json::Error>;
+}
+
+/// An event that wasn't recognized, with its raw body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEvent {
+    /// The event type, if the webhook provides one
+    pub event_type: Option<String>,
+    /// The raw body
+    pub data: Vec<u8>,
+}
+
+/**
+Declare an enum of webhook events, with one variant per event type and a fallback variant for
+unknown events. Each event's body is deserialized from JSON into the variant's type.
+
+# Example
+```
+use rocket::serde::Deserialize;
+use rocket_webhook::{event::RawEvent, webhook_event};
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct PushEvent {
+    r#ref: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct PullRequestEvent {
+    action: String,
+    number: u64,
+}
+
+webhook_event! {
+    /// Events received from GitHub
+    #[derive(Debug)]
+    pub enum GitHubEvent {
+        "push" => Push(PushEvent),
+        "pull_request" => PullRequest(PullRequestEvent),
+        _ => Other(RawEvent),
+    }
+}
+```
+*/
+#[macro_export]
+macro_rules! webhook_event {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($event_type:literal => $variant:ident($data:ty)),+ ,
+            _ => $fallback:ident(RawEvent) $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $(
+                #[doc = concat!("The `", $event_type, "` event")]
+                $variant($data),
+            )+
+            /// An event that wasn't recognized
+            $fallback($crate::event::RawEvent),
+        }
+
+        impl $crate::event::FromWebhookEvent for $name {
+            fn from_event(
+                event_type: ::std::option::Option<&str>,
+                body: &[u8],
+            ) -> ::std::result::Result<Self, $crate::event::serde_json::Error> {
+                match event_type {
+                    $(
+                        ::std::option::Option::Some($event_type) => {
+                            $crate::event::serde_json::from_slice(body).map(Self::$variant)
+                        }
+                    )+
+                    _ => ::std::result::Result::Ok(Self::$fallback($crate::event::RawEvent {
+                        event_type: event_type.map(::std::borrow::ToOwned::to_owned),
+                        data: body.to_vec(),
+                    })),
+                }
+            }
+        }
+    };
+}
@@ -1,17 +1,21 @@
-use std::{
-    marker::PhantomData,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::marker::PhantomData;
 
+#[cfg(feature = "form")]
+use rocket::serde::{Deserialize, Deserializer, de::Error};
 use rocket::{
-    Request, async_trait,
+    Data, Request, async_trait,
     data::{FromData, Outcome, ToByteUnit},
     http::{HeaderMap, Status},
     outcome::try_outcome,
     serde::{DeserializeOwned, json::serde_json},
 };
 
-use crate::{RocketWebhook, WebhookError, webhooks::Webhook};
+use crate::{
+    RocketWebhook, WebhookError,
+    challenge::{AnsweredChallenge, ChallengesEnabled},
+    event::FromWebhookEvent,
+    webhooks::{ValidatedBody, Webhook},
+};
 
 /**
  Data guard to validate and deserialize the JSON body of webhook type `W` into the `T` type.
@@ -43,6 +47,9 @@ pub struct WebhookPayload<'r, T, W, M = W> {
     pub data: T,
     /// The headers sent with the webhook request
     pub headers: &'r HeaderMap<'r>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
     _webhook: PhantomData<W>,
     _marker: PhantomData<M>,
 }
@@ -60,16 +67,13 @@ where
         req: &'r Request<'_>,
         data: rocket::Data<'r>,
     ) -> Outcome<'r, Self, Self::Error> {
-        let config: &RocketWebhook<W, M> = try_outcome!(get_webhook_from_state(req));
-        let body = data.open(config.max_body_size.bytes());
-        let time_bounds = get_timestamp_bounds(config.timestamp_tolerance);
-        let validated_body =
-            try_outcome!(config.webhook.validate_body(req, body, time_bounds).await);
+        let (_, validated_body) = try_outcome!(validate_request::<W, M>(req, data).await);
 
-        match serde_json::from_slice(&validated_body) {
+        match serde_json::from_slice(&validated_body.data) {
             Ok(data) => Outcome::Success(Self {
                 data,
                 headers: req.headers(),
+                secret_index: validated_body.secret_index,
                 _webhook: PhantomData,
                 _marker: PhantomData,
             }),
@@ -103,6 +107,9 @@ pub struct WebhookPayloadRaw<'r, W, M = W> {
     pub data: Vec<u8>,
     /// The headers sent with the webhook request
     pub headers: &'r HeaderMap<'r>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
     _webhook: PhantomData<W>,
     _marker: PhantomData<M>,
 }
@@ -119,50 +126,230 @@ where
         req: &'r Request<'_>,
         data: rocket::Data<'r>,
     ) -> Outcome<'r, Self, Self::Error> {
-        let config: &RocketWebhook<W, M> = try_outcome!(get_webhook_from_state(req));
-        let body = data.open(config.max_body_size.bytes());
-        let time_bounds = get_timestamp_bounds(config.timestamp_tolerance);
-        let validated_body =
-            try_outcome!(config.webhook.validate_body(req, body, time_bounds).await);
+        let (_, validated_body) = try_outcome!(validate_request::<W, M>(req, data).await);
 
         Outcome::Success(Self {
-            data: validated_body,
+            data: validated_body.data,
             headers: req.headers(),
+            secret_index: validated_body.secret_index,
             _webhook: PhantomData,
             _marker: PhantomData,
         })
     }
 }
 
-fn get_webhook_from_state<'r, W, M>(
-    req: &'r Request,
-) -> Outcome<'r, &'r RocketWebhook<W, M>, WebhookError>
+/**
+Data guard to validate a webhook and deserialize its form body
+(`application/x-www-form-urlencoded`) into the `T` type, e.g. for Twilio webhooks or Slack slash
+commands. Use [FormJson] for a field that contains a JSON document, such as the `payload` field
+of Slack interactions. The `W` webhook configuration must be in Rocket state using [RocketWebhook].
+Bodies that don't match the `T` type are rejected with a [WebhookError::Form] error.
+```
+use rocket::{post, serde::Deserialize};
+use rocket_webhook::{FormJson, WebhookForm, webhooks::built_in::SlackWebhook};
+
+/// Slash command
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct SlashCommand {
+    command: String,
+    text: String,
+}
+
+/// Interaction, with the JSON payload in the `payload` field
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Interaction {
+    payload: FormJson<InteractionPayload>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct InteractionPayload {
+    r#type: String,
+}
+
+#[post("/api/webhooks/slack/command", data = "<payload>")]
+async fn command_route(payload: WebhookForm<'_, SlashCommand, SlackWebhook>) -> String {
+    format!("{} {}", payload.data.command, payload.data.text)
+}
+
+#[post("/api/webhooks/slack/interaction", data = "<payload>")]
+async fn interaction_route(payload: WebhookForm<'_, Interaction, SlackWebhook>) -> String {
+    payload.data.payload.0.r#type
+}
+```
+*/
+#[cfg(feature = "form")]
+pub struct WebhookForm<'r, T, W, M = W> {
+    /// The deserialized form data
+    pub data: T,
+    /// The headers sent with the webhook request
+    pub headers: &'r HeaderMap<'r>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
+    _webhook: PhantomData<W>,
+    _marker: PhantomData<M>,
+}
+
+#[cfg(feature = "form")]
+#[async_trait]
+impl<'r, T, W, M> FromData<'r> for WebhookForm<'r, T, W, M>
 where
+    T: DeserializeOwned,
     W: Webhook + Send + Sync + 'static,
     M: Send + Sync + 'static,
 {
-    match req.rocket().state::<RocketWebhook<W, M>>() {
-        Some(config) => Outcome::Success(config),
-        None => {
-            return Outcome::Error((Status::InternalServerError, WebhookError::NotAttached));
+    type Error = WebhookError;
+
+    async fn from_data(
+        req: &'r Request<'_>,
+        data: rocket::Data<'r>,
+    ) -> Outcome<'r, Self, Self::Error> {
+        let (_, validated_body) = try_outcome!(validate_request::<W, M>(req, data).await);
+
+        match serde_urlencoded::from_bytes(&validated_body.data) {
+            Ok(data) => Outcome::Success(Self {
+                data,
+                headers: req.headers(),
+                secret_index: validated_body.secret_index,
+                _webhook: PhantomData,
+                _marker: PhantomData,
+            }),
+            Err(e) => Outcome::Error((Status::BadRequest, WebhookError::Form(e))),
         }
     }
 }
 
-/// Get the timestamp bounds based on the current unix epoch time in seconds
-fn get_timestamp_bounds((past_secs, future_secs): (u32, u32)) -> (u32, u32) {
-    let unix_time = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as u32; // Safe to use u32 until 2106
-    let lower_bound = {
-        if past_secs > unix_time {
-            0
-        } else {
-            unix_time - past_secs
+/// A form field containing a JSON document, deserialized into the `T` type (see [WebhookForm])
+#[cfg(feature = "form")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormJson<T>(pub T);
+
+#[cfg(feature = "form")]
+impl<'de, T: DeserializeOwned> Deserialize<'de> for FormJson<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let json = String::deserialize(deserializer)?;
+        serde_json::from_str(&json)
+            .map(FormJson)
+            .map_err(D::Error::custom)
+    }
+}
+
+/**
+Data guard to validate a webhook and deserialize it into a typed event `E`, using the event
+type sent by the provider (e.g. the `X-GitHub-Event` header). Events are declared using the
+[webhook_event!](crate::webhook_event) macro, or by implementing [FromWebhookEvent].
+The `W` webhook configuration must be in Rocket state using [RocketWebhook].
+```
+use rocket::{post, serde::Deserialize};
+use rocket_webhook::{
+    WebhookEvent, event::RawEvent, webhook_event, webhooks::built_in::GitHubWebhook,
+};
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct PushEvent {
+    r#ref: String,
+}
+
+webhook_event! {
+    pub enum GitHubEvent {
+        "push" => Push(PushEvent),
+        _ => Other(RawEvent),
+    }
+}
+
+#[post("/api/webhooks/github", data = "<payload>")]
+async fn github_route(payload: WebhookEvent<'_, GitHubEvent, GitHubWebhook>) -> String {
+    match payload.event {
+        GitHubEvent::Push(push) => format!("Pushed to {}", push.r#ref),
+        GitHubEvent::Other(event) => format!("Ignored {:?} event", event.event_type),
+    }
+}
+```
+*/
+pub struct WebhookEvent<'r, E, W, M = W> {
+    /// The deserialized event
+    pub event: E,
+    /// The headers sent with the webhook request
+    pub headers: &'r HeaderMap<'r>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
+    _webhook: PhantomData<W>,
+    _marker: PhantomData<M>,
+}
+
+#[async_trait]
+impl<'r, E, W, M> FromData<'r> for WebhookEvent<'r, E, W, M>
+where
+    E: FromWebhookEvent,
+    W: Webhook + Send + Sync + 'static,
+    M: Send + Sync + 'static,
+{
+    type Error = WebhookError;
+
+    async fn from_data(
+        req: &'r Request<'_>,
+        data: rocket::Data<'r>,
+    ) -> Outcome<'r, Self, Self::Error> {
+        let (config, validated_body) = try_outcome!(validate_request::<W, M>(req, data).await);
+
+        match config.parse_event(req, &validated_body) {
+            Ok(event) => Outcome::Success(Self {
+                event,
+                headers: req.headers(),
+                secret_index: validated_body.secret_index,
+                _webhook: PhantomData,
+                _marker: PhantomData,
+            }),
+            Err(err) => Outcome::Error((err.status(), err)),
         }
+    }
+}
+
+/// Validate the webhook request using the configuration in Rocket state
+pub(crate) async fn validate_request<'r, W, M>(
+    req: &'r Request<'_>,
+    data: Data<'r>,
+) -> Outcome<'r, (&'r RocketWebhook<W, M>, ValidatedBody), WebhookError>
+where
+    W: Webhook + Send + Sync + 'static,
+    M: Send + Sync + 'static,
+{
+    let config: &RocketWebhook<W, M> = try_outcome!(get_webhook_from_state(req));
+    let body = data.open((u64::from(config.max_body_size) + 1).bytes());
+    let validated_body = match config.verify_stream(req, body).await {
+        Ok(validated_body) => validated_body,
+        Err(err) => return Outcome::Error((config.error_status(&err), err)),
     };
-    let upper_bound = unix_time + future_secs;
 
-    (lower_bound, upper_bound)
+    // Answer verification challenges if enabled, so only real events reach the route
+    if req.rocket().state::<ChallengesEnabled>().is_some()
+        && let Some(challenge) = config
+            .webhook
+            .challenge_response(req, &validated_body)
+            .await
+    {
+        req.local_cache(|| AnsweredChallenge(Some(challenge)));
+        let err = WebhookError::Challenge;
+        return Outcome::Error((err.status(), err));
+    }
+
+    Outcome::Success((config, validated_body))
+}
+
+fn get_webhook_from_state<'r, W, M>(
+    req: &'r Request,
+) -> Outcome<'r, &'r RocketWebhook<W, M>, WebhookError>
+where
+    W: Webhook + Send + Sync + 'static,
+    M: Send + Sync + 'static,
+{
+    match req.rocket().state::<RocketWebhook<W, M>>() {
+        Some(config) => Outcome::Success(config),
+        None => Outcome::Error((Status::InternalServerError, WebhookError::NotAttached)),
+    }
 }
@@ -8,9 +8,15 @@
 Streamlined webhook validation in Rocket applications.
 
 - Automatically validate and deserialize webhook JSON payloads using the [WebhookPayload] data guard. You can also
-get the raw body using [WebhookPayloadRaw].
-- [Common webhooks](webhooks::built_in) included (GitHub, Slack, Stripe, Standard)
+  get the raw body using [WebhookPayloadRaw], or receive [typed events](event) using [WebhookEvent].
+- Deserialize form payloads (e.g. Twilio, Slack slash commands) with the `WebhookForm` data guard
+  (requires the `form` feature)
+- Receive [CloudEvents](https://cloudevents.io) in binary, structured or batch mode with the `CloudEvent`
+  and `CloudEventBatch` data guards (requires the `cloudevents` feature)
+- [Common webhooks](webhooks::built_in) included (GitHub, Slack, Stripe, Standard, and more)
+- Automatically answer verification challenges from providers with the [WebhookChallenges](challenge::WebhookChallenges) fairing
 - Easily validate custom webhooks with one of the generic builders
+- Sign and send your own webhooks with retries using the [outbound] module (requires the `outbound` feature)
 
 # Usage
 
@@ -74,8 +80,7 @@ use rocket_webhook::{WebhookError, webhooks::generic::Hmac256Webhook};
 let my_webhook = Hmac256Webhook::builder()
     .secret("my-secret")
     .expected_signatures(|req| {
-        req.headers()
-            .get_one("Foo-Signature-256")
+        req.header("Foo-Signature-256")
             .and_then(|header| hex::decode(header).ok())
             .map(|header| vec![header])
     })
@@ -88,7 +93,8 @@ along with the [Webhook](src/webhooks.rs) trait. See the implementations in [web
 
 # Handling errors
 By default, the webhook data guards will return Bad Request (400) for invalid requests (e.g. missing headers) and
-Unauthorized (401) for signature validation failures. Rocket's error responses can be overridden using
+Unauthorized (401) for signature validation failures. Bodies larger than the configured `max_body_size` are
+rejected with Payload Too Large (413). Rocket's error responses can be overridden using
 [catchers](https://rocket.rs/guide/v0.5/requests/#error-catchers) scoped to a specific path.
 
 If you need more control over how to
@@ -123,11 +129,34 @@ struct GithubPayload {
 }
 ```
 
+# Verifying outside of Rocket
+Webhooks can also be validated without going through a Rocket route, e.g. in a background job that processes
+stored deliveries, or in another web framework. Use [RocketWebhook::verify] (or [RocketWebhook::verify_stream] in
+async code) with anything implementing [WebhookRequest], such as a list or map of headers. Size limits, timestamp
+tolerance, and the replay store are applied the same way as in the data guards.
+
+```
+use rocket_webhook::{RocketWebhook, WebhookError, webhooks::built_in::GitHubWebhook};
+
+let webhook = RocketWebhook::builder()
+    .webhook(GitHubWebhook::with_secret("my-github-secret"))
+    .build();
+
+let headers = vec![("X-Hub-Signature-256".to_string(), "sha256=abc".to_string())];
+match webhook.verify(&headers, br#"{"action":"opened"}"#) {
+    Ok(validated) => println!("Got {} bytes", validated.data.len()),
+    Err(err) => println!("Rejected with {}: {err}", webhook.error_status(&err)),
+}
+```
+
 # Multiple with same type
 If you want to receive webhooks using multiple accounts/keys from the same built-in or generic webhook, you'll need to pass
 in a marker struct when building the webhooks and using the data guards. This is needed to distinguish
 between the two webhooks in Rocket's internal state.
 
+If you receive webhooks for many accounts that each have their own secret (e.g. multi-tenant applications), you can
+instead use a [SecretResolver](webhooks::interface::hmac::resolver::SecretResolver) to get the secret for each request.
+
 ```
 use rocket::{get, routes};
 use rocket_webhook::{
@@ -169,11 +198,26 @@ async fn slack2_route(payload: WebhookPayloadRaw<'_, SlackWebhook, SlackAccount2
 ```
 */
 
+pub mod challenge;
+#[cfg(feature = "cloudevents")]
+mod cloudevents;
 mod error;
+pub mod event;
 mod guard;
+mod request;
 mod state;
 
+#[cfg(feature = "outbound")]
+pub mod outbound;
+pub mod replay;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod webhooks;
+#[cfg(feature = "cloudevents")]
+pub use cloudevents::{CloudEvent, CloudEventBatch};
 pub use error::WebhookError;
-pub use guard::{WebhookPayload, WebhookPayloadRaw};
+#[cfg(feature = "form")]
+pub use guard::{FormJson, WebhookForm};
+pub use guard::{WebhookEvent, WebhookPayload, WebhookPayloadRaw};
+pub use request::WebhookRequest;
 pub use state::RocketWebhook;
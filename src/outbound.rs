@@ -0,0 +1,43 @@
+//! Sending webhooks
+
+/**
+Sign and send webhooks to your own customers' endpoints, using the same signature formats that
+this crate validates. Outbound webhooks are signed using a [WebhookSigner](signer::WebhookSigner),
+and delivered with retries using the [WebhookDispatcher](dispatcher::WebhookDispatcher).
+
+# Example
+```
+use std::time::Duration;
+use rocket_webhook::outbound::{
+    dispatcher::{WebhookDispatcher, WebhookEndpoint, WebhookTransport},
+    signer::StandardSigner,
+};
+
+# struct MyHttpClient;
+# #[rocket::async_trait]
+# impl WebhookTransport for MyHttpClient {
+#     async fn send(&self, url: &str, headers: &[(String, String)], body: &[u8]) -> Result<u16, String> {
+#         Ok(200)
+#     }
+# }
+# rocket::execute(async {
+let dispatcher = WebhookDispatcher::builder()
+    .transport(MyHttpClient)
+    .retry_delays([Duration::from_secs(5), Duration::from_secs(5 * 60)])
+    .build();
+
+// Each endpoint is signed with its own secret
+let endpoint = WebhookEndpoint::new(
+    "https://example.com/webhooks",
+    StandardSigner::with_secret("whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw").expect("valid base64"),
+);
+let delivery = dispatcher
+    .dispatch(&endpoint, "msg_2KWPBgLlAfxdpx2AI54pPJ85f4W", br#"{"type":"user.created"}"#)
+    .await;
+assert!(delivery.is_success());
+assert_eq!(delivery.attempts.len(), 1);
+# });
+```
+*/
+pub mod dispatcher;
+pub mod signer;
@@ -0,0 +1,193 @@
+use std::time::{Duration, Instant};
+
+use bon::Builder;
+use rocket::{async_trait, tokio::time::sleep};
+
+use crate::webhooks::utils::unix_time;
+
+use super::signer::WebhookSigner;
+
+/// Trait for the HTTP client used to send webhooks. This is implemented for [reqwest::Client]
+/// when the `reqwest` feature is enabled.
+#[async_trait]
+pub trait WebhookTransport: Send + Sync {
+    /// Send a POST request with the headers and body, and return the response status code. Return
+    /// an error if no response was received (e.g. connection errors or timeouts).
+    async fn send(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &[u8],
+    ) -> Result<u16, String>;
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl WebhookTransport for reqwest::Client {
+    async fn send(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &[u8],
+    ) -> Result<u16, String> {
+        let mut request = self.post(url).body(body.to_vec());
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        match request.send().await {
+            Ok(response) => Ok(response.status().as_u16()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+/// A customer endpoint that receives webhooks, with its own signer (and secret)
+pub struct WebhookEndpoint {
+    url: String,
+    signer: Box<dyn WebhookSigner>,
+}
+
+impl WebhookEndpoint {
+    /// Create an endpoint with the URL and the signer used for its webhooks
+    pub fn new(url: impl Into<String>, signer: impl WebhookSigner + 'static) -> Self {
+        Self {
+            url: url.into(),
+            signer: Box::new(signer),
+        }
+    }
+
+    /// The URL of the endpoint
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+/// Record of a single attempt to deliver a webhook
+#[derive(Debug, Clone)]
+pub struct DeliveryAttempt {
+    /// The attempt number, starting from 1
+    pub attempt: u32,
+    /// Time the attempt was signed and sent (Unix epoch seconds)
+    pub timestamp: u32,
+    /// The response status code, if a response was received
+    pub status: Option<u16>,
+    /// The transport error, if no response was received
+    pub error: Option<String>,
+    /// How long the request took
+    pub duration: Duration,
+}
+
+impl DeliveryAttempt {
+    /// Whether the endpoint responded with a successful (2xx) status code
+    pub fn is_success(&self) -> bool {
+        self.status
+            .is_some_and(|status| (200..300).contains(&status))
+    }
+}
+
+/// Record of a webhook delivery, including all attempts
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    /// The delivery ID
+    pub id: String,
+    /// The endpoint URL
+    pub url: String,
+    /// All attempts, in order
+    pub attempts: Vec<DeliveryAttempt>,
+}
+
+impl Delivery {
+    /// Whether the webhook was successfully delivered
+    pub fn is_success(&self) -> bool {
+        self.attempts
+            .last()
+            .is_some_and(DeliveryAttempt::is_success)
+    }
+}
+
+/// Sends signed webhooks to endpoints, retrying failed attempts according to the retry schedule.
+/// An attempt fails if there is no response, or the response status code is not 2xx.
+#[derive(Builder)]
+pub struct WebhookDispatcher<T: WebhookTransport> {
+    /// The HTTP client used to send webhooks
+    transport: T,
+    /// Delays before each retry. Defaults to the schedule recommended by Standard Webhooks:
+    /// 5 seconds, 5 minutes, 30 minutes, 2 hours, 5 hours, 10 hours, and 10 hours.
+    #[builder(
+        with = |delays: impl IntoIterator<Item = Duration>| delays.into_iter().collect(),
+        default = default_retry_delays()
+    )]
+    retry_delays: Vec<Duration>,
+    /// Content type of the webhook body. Defaults to `application/json`.
+    #[builder(into, default = "application/json")]
+    content_type: String,
+}
+
+impl<T: WebhookTransport> WebhookDispatcher<T> {
+    /// Sign and send the webhook body to the endpoint, retrying until it succeeds or all retries
+    /// are exhausted. The ID should uniquely identify the message, so that the endpoint can
+    /// reject duplicate deliveries.
+    pub async fn dispatch(
+        &self,
+        endpoint: &WebhookEndpoint,
+        id: impl Into<String>,
+        body: &[u8],
+    ) -> Delivery {
+        let mut delivery = Delivery {
+            id: id.into(),
+            url: endpoint.url.clone(),
+            attempts: Vec::new(),
+        };
+        let delays = std::iter::once(Duration::ZERO).chain(self.retry_delays.iter().copied());
+        for (attempt, delay) in (1..).zip(delays) {
+            if !delay.is_zero() {
+                sleep(delay).await;
+            }
+            let attempt = self.attempt(endpoint, &delivery.id, body, attempt).await;
+            let is_success = attempt.is_success();
+            delivery.attempts.push(attempt);
+            if is_success {
+                break;
+            }
+        }
+
+        delivery
+    }
+
+    /// Sign and send a single attempt. The webhook is signed again for each attempt, as
+    /// the timestamp changes.
+    async fn attempt(
+        &self,
+        endpoint: &WebhookEndpoint,
+        id: &str,
+        body: &[u8],
+        attempt: u32,
+    ) -> DeliveryAttempt {
+        let timestamp = unix_time();
+        let mut headers = endpoint.signer.sign(id, timestamp, body);
+        headers.push(("Content-Type".into(), self.content_type.clone()));
+
+        let start = Instant::now();
+        let response = self.transport.send(&endpoint.url, &headers, body).await;
+        let duration = start.elapsed();
+        let (status, error) = match response {
+            Ok(status) => (Some(status), None),
+            Err(err) => (None, Some(err)),
+        };
+
+        DeliveryAttempt {
+            attempt,
+            timestamp,
+            status,
+            error,
+            duration,
+        }
+    }
+}
+
+fn default_retry_delays() -> Vec<Duration> {
+    [5, 5 * 60, 30 * 60, 2 * 3600, 5 * 3600, 10 * 3600, 10 * 3600]
+        .into_iter()
+        .map(Duration::from_secs)
+        .collect()
+}
@@ -0,0 +1,137 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bon::Builder;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+/// Trait for signing outbound webhooks
+pub trait WebhookSigner: Send + Sync {
+    /// Sign the webhook body and return the headers to send with the request. The delivery ID
+    /// stays the same across retries, while the timestamp (in Unix epoch seconds) is the time of
+    /// each attempt.
+    fn sign(&self, id: &str, timestamp: u32, body: &[u8]) -> Vec<(String, String)>;
+}
+
+/// # Standard Webhook signer
+/// Signs webhooks following the **Standard Webhooks** spec, which can be validated by the
+/// [StandardWebhook](crate::webhooks::built_in::StandardWebhook).
+///
+/// Sends headers `webhook-id`, `webhook-timestamp`, `webhook-signature`. (`webhook-` prefix can
+/// be configured)
+///
+/// Signature is base64 HMAC of `<id>.<timestamp>.<body>`, prefixed by `v1,`
+pub struct StandardSigner {
+    secret_key: Zeroizing<Vec<u8>>,
+    header_prefix: String,
+}
+
+impl StandardSigner {
+    /// Instantiate using the secret key starting with `whsec_`. Headers will have a prefix of
+    /// `webhook-`.
+    pub fn with_secret(secret_key: impl AsRef<str>) -> Result<Self, base64::DecodeError> {
+        Self::with_secret_and_prefix(secret_key, "webhook-")
+    }
+
+    /// Instantiate using the secret key starting with `whsec_` and a header prefix (include the
+    /// dash when providing the header prefix, e.g. `svix-`).
+    pub fn with_secret_and_prefix(
+        secret_key: impl AsRef<str>,
+        header_prefix: impl Into<String>,
+    ) -> Result<Self, base64::DecodeError> {
+        let secret_key = secret_key.as_ref();
+        let stripped_key = secret_key.strip_prefix("whsec_").unwrap_or(secret_key);
+        Ok(Self {
+            secret_key: Zeroizing::new(BASE64_STANDARD.decode(stripped_key)?),
+            header_prefix: header_prefix.into(),
+        })
+    }
+}
+
+impl WebhookSigner for StandardSigner {
+    fn sign(&self, id: &str, timestamp: u32, body: &[u8]) -> Vec<(String, String)> {
+        let timestamp = timestamp.to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret_key)
+            .expect("HMAC should take any key length");
+        mac.update(id.as_bytes());
+        mac.update(b".");
+        mac.update(timestamp.as_bytes());
+        mac.update(b".");
+        mac.update(body);
+        let signature = BASE64_STANDARD.encode(mac.finalize().into_bytes());
+
+        let prefix = &self.header_prefix;
+        vec![
+            (format!("{prefix}id"), id.to_owned()),
+            (format!("{prefix}timestamp"), timestamp),
+            (format!("{prefix}signature"), format!("v1,{signature}")),
+        ]
+    }
+}
+
+/// Function to get a prefix or suffix for the body, given the delivery ID and timestamp
+type BodyFn = Box<dyn Fn(&str, u32) -> Vec<u8> + Send + Sync>;
+
+/// Function to get the headers, given the delivery ID, timestamp, and calculated signature
+type HeadersFn = Box<dyn Fn(&str, u32, &[u8]) -> Vec<(String, String)> + Send + Sync>;
+
+/**
+A custom webhook signer using HMAC SHA256, the sending side of the
+[Hmac256Webhook](crate::webhooks::generic::Hmac256Webhook).
+
+# Example
+This signs webhooks with a hex-encoded signature in the `Signature-SHA256` header (using the
+endpoint's header name), and a Unix epoch timestamp in the `Timestamp` header that is attached
+as a suffix to the body when calculating the signature:
+
+```
+use rocket_webhook::outbound::signer::{Hmac256Signer, WebhookSigner};
+
+let signature_header = String::from("Signature-SHA256");
+let signer = Hmac256Signer::builder()
+    .secret("my-secret")
+    .headers(move |_id, timestamp, signature| {
+        vec![
+            (signature_header.clone(), hex::encode(signature)),
+            ("Timestamp".into(), timestamp.to_string()),
+        ]
+    })
+    .body_suffix(|_id, timestamp| timestamp.to_string().into_bytes())
+    .build();
+
+let headers = signer.sign("msg_1", 1760414077, b"Hello custom HMAC");
+assert_eq!(headers[1], ("Timestamp".into(), "1760414077".into()));
+```
+*/
+#[derive(Builder)]
+pub struct Hmac256Signer {
+    /// The secret used to sign the webhook. If the key is encoded in hex or base64, etc., it
+    /// must be decoded to bytes first
+    #[builder(with = |secret: impl Into<Vec<u8>>| Zeroizing::new(secret.into()))]
+    secret: Zeroizing<Vec<u8>>,
+    /// Function to get the headers to send, given the delivery ID, timestamp, and the
+    /// calculated signature
+    #[builder(with = |f: impl Fn(&str, u32, &[u8]) -> Vec<(String, String)> + Send + Sync + 'static| Box::new(f) as HeadersFn)]
+    headers: HeadersFn,
+    /// Function to get the prefix to attach to the body when calculating the signature
+    #[builder(with = |f: impl Fn(&str, u32) -> Vec<u8> + Send + Sync + 'static| Box::new(f) as BodyFn)]
+    body_prefix: Option<BodyFn>,
+    /// Function to get the suffix to attach to the body when calculating the signature
+    #[builder(with = |f: impl Fn(&str, u32) -> Vec<u8> + Send + Sync + 'static| Box::new(f) as BodyFn)]
+    body_suffix: Option<BodyFn>,
+}
+
+impl WebhookSigner for Hmac256Signer {
+    fn sign(&self, id: &str, timestamp: u32, body: &[u8]) -> Vec<(String, String)> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC should take any key length");
+        if let Some(body_prefix) = &self.body_prefix {
+            mac.update(&body_prefix(id, timestamp));
+        }
+        mac.update(body);
+        if let Some(body_suffix) = &self.body_suffix {
+            mac.update(&body_suffix(id, timestamp));
+        }
+
+        (self.headers)(id, timestamp, &mac.finalize().into_bytes())
+    }
+}
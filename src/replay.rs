@@ -0,0 +1,119 @@
+//! Replay protection for validated webhook deliveries
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use rocket::async_trait;
+
+use crate::webhooks::utils::unix_time;
+
+/**
+Store used to detect duplicate webhook deliveries. Once a webhook has been validated, its
+delivery ID (e.g. `webhook-id`, `X-GitHub-Delivery`, etc.) is recorded in the store.
+If the same delivery ID is seen again before it expires, the delivery is rejected as a replay.
+
+This only protects against replays for webhooks whose delivery ID is signed (e.g. Standard
+Webhooks, Stripe, Slack, Twitch). GitHub and Shopify don't sign their delivery ID or a timestamp,
+so for them the store only rejects the provider's retries of a delivery: a captured request can
+still be replayed with a different delivery ID header.
+
+You can implement this trait to use an external store (e.g. Redis) that is shared between
+multiple instances of your application. For a single instance, use the [MemoryReplayStore].
+*/
+#[async_trait]
+pub trait ReplayStore: Send + Sync {
+    /// Record the delivery ID, which should be remembered until the given expiration time (in Unix
+    /// epoch seconds). Returns `true` if the delivery ID is new, or `false` if it was already seen
+    /// and hasn't expired yet.
+    async fn insert(&self, delivery_id: &str, expires_at: u32) -> bool;
+}
+
+#[async_trait]
+impl<S: ReplayStore + ?Sized> ReplayStore for Arc<S> {
+    async fn insert(&self, delivery_id: &str, expires_at: u32) -> bool {
+        (**self).insert(delivery_id, expires_at).await
+    }
+}
+
+/**
+In-memory replay store. Delivery IDs are evicted once they expire, i.e. once they fall
+outside of the webhook's timestamp tolerance window.
+
+# Example
+```
+use rocket_webhook::{
+    RocketWebhook, replay::MemoryReplayStore, webhooks::built_in::StripeWebhook,
+};
+
+let stripe_webhook = RocketWebhook::builder()
+    .webhook(StripeWebhook::with_secret("my-stripe-secret"))
+    .replay_store(MemoryReplayStore::new())
+    .build();
+```
+*/
+#[derive(Default)]
+pub struct MemoryReplayStore {
+    entries: Mutex<MemoryEntries>,
+}
+
+#[derive(Default)]
+struct MemoryEntries {
+    /// Delivery IDs and their expiration time
+    ids: HashMap<String, u32>,
+    /// Delivery IDs in insertion order, used for eviction
+    queue: VecDeque<(u32, String)>,
+}
+
+impl MemoryReplayStore {
+    /// Create an empty in-memory replay store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of delivery IDs currently stored
+    pub fn len(&self) -> usize {
+        self.entries.lock().expect("lock is not poisoned").ids.len()
+    }
+
+    /// Whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[async_trait]
+impl ReplayStore for MemoryReplayStore {
+    async fn insert(&self, delivery_id: &str, expires_at: u32) -> bool {
+        let now = unix_time();
+        let mut entries = self.entries.lock().expect("lock is not poisoned");
+        entries.evict_expired(now);
+
+        if entries.ids.get(delivery_id).is_some_and(|exp| *exp > now) {
+            return false;
+        }
+        entries.ids.insert(delivery_id.to_owned(), expires_at);
+        entries
+            .queue
+            .push_back((expires_at, delivery_id.to_owned()));
+        true
+    }
+}
+
+impl MemoryEntries {
+    /// Remove expired entries from the front of the queue. Entries are inserted with
+    /// roughly increasing expiration times, so this stops at the first unexpired entry.
+    fn evict_expired(&mut self, now: u32) {
+        while let Some((expires_at, _)) = self.queue.front() {
+            if *expires_at > now {
+                break;
+            }
+            let (expires_at, id) = self.queue.pop_front().expect("queue is not empty");
+            // only remove if the ID wasn't re-inserted with a later expiration
+            if self.ids.get(&id) == Some(&expires_at) {
+                self.ids.remove(&id);
+            }
+        }
+    }
+}
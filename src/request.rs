@@ -0,0 +1,119 @@
+//! Framework-agnostic access to webhook request headers
+
+use std::collections::HashMap;
+
+use rocket::{Orbit, Request, Rocket, http::HeaderMap};
+
+/**
+The parts of an incoming request needed to validate a webhook. This is implemented for Rocket's
+[Request] and [HeaderMap], as well as plain header maps and lists of `(name, value)` pairs, so
+webhooks can also be validated outside of Rocket (e.g. from stored headers in a queue consumer).
+
+Header names are matched case-insensitively.
+```
+use rocket_webhook::WebhookRequest;
+
+let headers = [("X-Hub-Signature-256", "sha256=abc"), ("X-GitHub-Event", "push")];
+assert_eq!(headers.header("x-github-event"), Some("push"));
+```
+*/
+pub trait WebhookRequest: Sync {
+    /// Get the first value of the header with the given name
+    fn header(&self, name: &str) -> Option<&str>;
+
+    /// Get the path of the request, if available
+    fn path(&self) -> Option<&str> {
+        None
+    }
+
+    /// Get the Rocket instance that received the request, e.g. to access managed state. Returns
+    /// `None` when validating outside of Rocket.
+    fn rocket(&self) -> Option<&Rocket<Orbit>> {
+        None
+    }
+
+    /// Call the function with the Rocket request, if validating a Rocket request. See
+    /// [`with_rocket_request`](#method.with_rocket_request) to get a value from the request.
+    #[allow(unused_variables)]
+    fn visit_rocket_request(&self, f: &mut dyn FnMut(&Request<'_>)) {}
+}
+
+impl dyn WebhookRequest + '_ {
+    /// Get a value from the Rocket request (e.g. a route parameter, or the request-local cache).
+    /// Returns `None` when validating outside of Rocket.
+    pub fn with_rocket_request<T>(&self, f: impl FnOnce(&Request<'_>) -> T) -> Option<T> {
+        let mut f = Some(f);
+        let mut value = None;
+        self.visit_rocket_request(&mut |req| value = f.take().map(|f| f(req)));
+        value
+    }
+}
+
+impl WebhookRequest for Request<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers().get_one(name)
+    }
+
+    fn path(&self) -> Option<&str> {
+        Some(self.uri().path().as_str())
+    }
+
+    fn rocket(&self) -> Option<&Rocket<Orbit>> {
+        Some(Request::rocket(self))
+    }
+
+    fn visit_rocket_request(&self, f: &mut dyn FnMut(&Request<'_>)) {
+        f(self)
+    }
+}
+
+impl WebhookRequest for HeaderMap<'_> {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.get_one(name)
+    }
+}
+
+impl<K, V, S> WebhookRequest for HashMap<K, V, S>
+where
+    K: AsRef<str> + Sync,
+    V: AsRef<str> + Sync,
+    S: Sync,
+{
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(self.iter(), name)
+    }
+}
+
+impl<K, V, const N: usize> WebhookRequest for [(K, V); N]
+where
+    K: AsRef<str> + Sync,
+    V: AsRef<str> + Sync,
+{
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(self.iter().map(|(k, v)| (k, v)), name)
+    }
+}
+
+impl<K, V> WebhookRequest for Vec<(K, V)>
+where
+    K: AsRef<str> + Sync,
+    V: AsRef<str> + Sync,
+{
+    fn header(&self, name: &str) -> Option<&str> {
+        find_header(self.iter().map(|(k, v)| (k, v)), name)
+    }
+}
+
+/// Find the first header matching the name (case-insensitive)
+fn find_header<'a, K, V>(
+    mut headers: impl Iterator<Item = (&'a K, &'a V)>,
+    name: &str,
+) -> Option<&'a str>
+where
+    K: AsRef<str> + 'a,
+    V: AsRef<str> + 'a,
+{
+    headers
+        .find(|(k, _)| k.as_ref().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_ref())
+}
@@ -1,11 +1,24 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, sync::atomic::Ordering};
 
 use bon::bon;
+use rocket::{
+    http::Status,
+    tokio::io::{AsyncRead, AsyncReadExt},
+};
 
-use crate::webhooks::Webhook;
+use crate::{
+    WebhookError, WebhookRequest,
+    event::FromWebhookEvent,
+    replay::ReplayStore,
+    webhooks::{
+        ValidatedBody, Webhook,
+        utils::{LimitedBody, block_on, body_size, unix_time},
+    },
+};
 
 /**
-Webhook configuration stored in Rocket state.
+Webhook configuration stored in Rocket state. The configuration can also be used to validate
+webhooks outside of Rocket, using [RocketWebhook::verify] or [RocketWebhook::verify_stream].
 
 # Example
 
@@ -32,7 +45,10 @@ where
 {
     pub(crate) webhook: W,
     pub(crate) max_body_size: u32,
+    pub(crate) check_content_length: bool,
     pub(crate) timestamp_tolerance: (u32, u32),
+    pub(crate) replay_store: Option<Box<dyn ReplayStore>>,
+    pub(crate) replay_status: Status,
     marker: PhantomData<M>,
 }
 
@@ -49,15 +65,31 @@ where
         /// The maximum allowed body size of the webhook request in bytes (default: 64 KB)
         #[builder(default = 64 * 1024)]
         max_body_size: u32,
+        /// Whether to reject the request early if the `Content-Length` header exceeds the maximum body
+        /// size, before reading the body (default: true). Bodies that exceed the maximum size are always
+        /// rejected with Payload Too Large (413) after reading.
+        #[builder(default = true)]
+        check_content_length: bool,
         /// For webhooks that use a timestamp, how many seconds in the past and future is allowed to be valid
         /// (default: 5 minutes in past, 15 seconds in future)
         #[builder(default = (5 * 60, 15), with = |past_secs: u32, future_secs: u32| (past_secs, future_secs))]
         timestamp_tolerance: (u32, u32),
+        /// Store used to reject duplicate deliveries (replays) of validated webhooks, see [ReplayStore] for
+        /// which webhooks this protects. Delivery IDs are kept until they fall outside of the timestamp
+        /// tolerance window (default: no replay protection)
+        #[builder(with = |store: impl ReplayStore + 'static| Box::new(store) as Box<dyn ReplayStore>)]
+        replay_store: Option<Box<dyn ReplayStore>>,
+        /// The status to respond with when a duplicate delivery is received (default: 409 Conflict)
+        #[builder(default = Status::Conflict)]
+        replay_status: Status,
     ) -> RocketWebhook<W, W> {
         RocketWebhook {
             webhook,
             max_body_size,
+            check_content_length,
             timestamp_tolerance,
+            replay_store,
+            replay_status,
             marker: PhantomData::<W>,
         }
     }
@@ -98,21 +130,141 @@ where
         /// The webhook to validate
         webhook: W,
         /// A marker struct to distinguish this webhook from other webhooks of the same type
-        #[builder(with = |marker: M| PhantomData)]
+        #[builder(with = |_marker: M| PhantomData)]
         marker: PhantomData<M>,
         /// The maximum allowed body size of the webhook request in bytes (default: 64 KB)
         #[builder(default = 64 * 1024)]
         max_body_size: u32,
+        /// Whether to reject the request early if the `Content-Length` header exceeds the maximum body
+        /// size, before reading the body (default: true). Bodies that exceed the maximum size are always
+        /// rejected with Payload Too Large (413) after reading.
+        #[builder(default = true)]
+        check_content_length: bool,
         /// For webhooks that use a timestamp, how many seconds in the past and future is allowed to be valid
         /// (default: 5 minutes in past, 15 seconds in future)
         #[builder(default = (5 * 60, 15), with = |past_secs: u32, future_secs: u32| (past_secs, future_secs))]
         timestamp_tolerance: (u32, u32),
+        /// Store used to reject duplicate deliveries (replays) of validated webhooks, see [ReplayStore] for
+        /// which webhooks this protects. Delivery IDs are kept until they fall outside of the timestamp
+        /// tolerance window (default: no replay protection)
+        #[builder(with = |store: impl ReplayStore + 'static| Box::new(store) as Box<dyn ReplayStore>)]
+        replay_store: Option<Box<dyn ReplayStore>>,
+        /// The status to respond with when a duplicate delivery is received (default: 409 Conflict)
+        #[builder(default = Status::Conflict)]
+        replay_status: Status,
     ) -> RocketWebhook<W, M> {
         RocketWebhook {
             webhook,
             marker,
             max_body_size,
+            check_content_length,
             timestamp_tolerance,
+            replay_store,
+            replay_status,
         }
     }
 }
+
+impl<W, M> RocketWebhook<W, M>
+where
+    W: Webhook + Sync,
+    M: Sync,
+{
+    /**
+    Validate a webhook from its headers and raw body, independently of Rocket (e.g. in a queue
+    consumer or another web framework). This blocks the current thread until validation
+    completes, so secret resolvers or public keys that need an async runtime should use
+    [RocketWebhook::verify_stream] instead.
+
+    # Example
+    ```
+    use rocket_webhook::{RocketWebhook, webhooks::built_in::GitHubWebhook};
+
+    let webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_secret("test-secret"))
+        .build();
+
+    let headers = [(
+        "X-Hub-Signature-256",
+        "sha256=6e939b5b3d3e8eba83ff81dde0030a8f2190d965e8bec7a17842863e979c4d7d",
+    )];
+    let body = br#"{"action":"opened"}"#;
+    let validated = webhook.verify(&headers, body).expect("signature is valid");
+    assert_eq!(validated.data, body);
+    ```
+    */
+    pub fn verify(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &[u8],
+    ) -> Result<ValidatedBody, WebhookError> {
+        block_on(self.verify_stream(req, body))
+    }
+
+    /// Read and validate a webhook from its headers and streamed body, independently of Rocket.
+    /// Bodies larger than the maximum size are rejected, as well as duplicate deliveries
+    /// if a replay store is configured.
+    pub async fn verify_stream(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+    ) -> Result<ValidatedBody, WebhookError> {
+        let too_large = || WebhookError::PayloadTooLarge(self.max_body_size);
+        if self.check_content_length
+            && body_size(req).is_some_and(|size| size > self.max_body_size as usize)
+        {
+            return Err(too_large());
+        }
+
+        // Read one byte past the limit, to detect bodies that would be truncated
+        let limit = u64::from(self.max_body_size);
+        let (body, exceeded) = LimitedBody::new(body.take(limit + 1), limit);
+        let time_bounds = get_timestamp_bounds(self.timestamp_tolerance);
+        let validation = self.webhook.validate_body(req, body, time_bounds).await;
+        if exceeded.load(Ordering::Relaxed) {
+            return Err(too_large());
+        }
+        let validated_body = validation?;
+
+        if let Some(store) = &self.replay_store
+            && let Some(delivery_id) = self.webhook.delivery_id(req, &validated_body.data)
+        {
+            // Keep the ID until any timestamp accepted now would fall outside the tolerance window
+            let expires_at = time_bounds.1.saturating_add(self.timestamp_tolerance.0);
+            if !store.insert(&delivery_id, expires_at).await {
+                return Err(WebhookError::Duplicate(delivery_id));
+            }
+        }
+
+        Ok(validated_body)
+    }
+
+    /// Deserialize a validated body into a [typed event](FromWebhookEvent), using the webhook's
+    /// event type
+    pub fn parse_event<E: FromWebhookEvent>(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &ValidatedBody,
+    ) -> Result<E, WebhookError> {
+        let event_type = self.webhook.event_type(req, &body.data);
+        E::from_event(event_type.as_deref(), &body.data).map_err(WebhookError::Deserialize)
+    }
+
+    /// The HTTP status to respond with for the given error, using the configured status
+    /// for duplicate deliveries
+    pub fn error_status(&self, error: &WebhookError) -> Status {
+        match error {
+            WebhookError::Duplicate(_) => self.replay_status,
+            _ => error.status(),
+        }
+    }
+}
+
+/// Get the timestamp bounds based on the current unix epoch time in seconds
+fn get_timestamp_bounds((past_secs, future_secs): (u32, u32)) -> (u32, u32) {
+    let unix_time = unix_time();
+    let lower_bound = unix_time.saturating_sub(past_secs);
+    let upper_bound = unix_time + future_secs;
+
+    (lower_bound, upper_bound)
+}
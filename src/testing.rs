@@ -0,0 +1,211 @@
+//! Helpers for testing webhook routes
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rocket::{http::Header, local};
+
+use crate::webhooks::utils::unix_time;
+
+/**
+Trait for webhooks that can produce validly signed test requests. This is implemented for the
+[built-in webhooks](crate::webhooks::built_in) that use HMAC secrets, using the first unexpired
+secret key. Webhooks that use public keys can be tested using a test keypair
+(e.g. [DiscordTestKey], [SendGridTestKey]).
+
+# Example
+```
+use rocket::{http::Status, local::blocking::Client, post, routes};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw,
+    testing::{LocalRequestExt, now},
+    webhooks::built_in::SlackWebhook,
+};
+
+#[post("/slack", data = "<payload>")]
+async fn slack_route(payload: WebhookPayloadRaw<'_, SlackWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+let rocket = rocket::build()
+    .manage(RocketWebhook::builder().webhook(SlackWebhook::with_secret("test-secret")).build())
+    .mount("/", routes![slack_route]);
+let client = Client::tracked(rocket).unwrap();
+
+let signer = SlackWebhook::with_secret("test-secret");
+let response = client.post("/slack").signed(&signer, "hello", now()).dispatch();
+assert_eq!(response.status(), Status::Ok);
+```
+*/
+pub trait TestSigner {
+    /// Get the headers for a request with a valid signature of the body, at the given timestamp
+    /// (Unix epoch seconds)
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)>;
+}
+
+/// Extension trait to sign requests from Rocket's local clients
+pub trait LocalRequestExt {
+    /// Set the body of the request and attach the signed headers from the signer
+    fn signed(self, signer: &impl TestSigner, body: impl AsRef<[u8]>, timestamp: u32) -> Self;
+}
+
+impl LocalRequestExt for local::blocking::LocalRequest<'_> {
+    fn signed(self, signer: &impl TestSigner, body: impl AsRef<[u8]>, timestamp: u32) -> Self {
+        let body = body.as_ref();
+        signer
+            .signed_headers(body, timestamp)
+            .into_iter()
+            .fold(self, |req, (name, value)| {
+                req.header(Header::new(name, value))
+            })
+            .body(body)
+    }
+}
+
+impl LocalRequestExt for local::asynchronous::LocalRequest<'_> {
+    fn signed(self, signer: &impl TestSigner, body: impl AsRef<[u8]>, timestamp: u32) -> Self {
+        let body = body.as_ref();
+        signer
+            .signed_headers(body, timestamp)
+            .into_iter()
+            .fold(self, |req, (name, value)| {
+                req.header(Header::new(name, value))
+            })
+            .body(body)
+    }
+}
+
+/// The current Unix epoch time in seconds
+pub fn now() -> u32 {
+    unix_time()
+}
+
+/// Get a unique delivery ID for a test request
+#[allow(dead_code)]
+pub(crate) fn test_delivery_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(1);
+    format!("test_{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Calculate the HMAC SHA256 of the parts using the first unexpired static secret key. Panics if
+/// the secret keys are resolved for each request, or none are active.
+#[cfg(feature = "hmac")]
+pub(crate) fn hmac_sha256(
+    secret_keys: &crate::webhooks::interface::hmac::HmacSecrets,
+    parts: &[&[u8]],
+) -> Vec<u8> {
+    use crate::webhooks::interface::hmac::HmacSecrets;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    let HmacSecrets::Static(secrets) = secret_keys else {
+        panic!("Test requests can only be signed with static secret keys");
+    };
+    let now = unix_time();
+    let secret = secrets
+        .iter()
+        .find(|secret| !secret.is_expired(now))
+        .expect("Test requests need an unexpired secret key");
+
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.key()).expect("HMAC should take any key length");
+    parts.iter().for_each(|part| mac.update(part));
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Test keypair for the [DiscordWebhook](crate::webhooks::built_in::DiscordWebhook)
+#[cfg(feature = "discord")]
+pub struct DiscordTestKey(ed25519_dalek::SigningKey);
+
+#[cfg(feature = "discord")]
+impl DiscordTestKey {
+    /// Create a test keypair from a fixed seed
+    pub fn new() -> Self {
+        Self(ed25519_dalek::SigningKey::from_bytes(&[7; 32]))
+    }
+
+    /// The hex public key, as provided by Discord
+    pub fn public_key(&self) -> String {
+        hex::encode(self.0.verifying_key().as_bytes())
+    }
+
+    /// A Discord webhook using the test public key
+    pub fn webhook(&self) -> crate::webhooks::built_in::DiscordWebhook {
+        crate::webhooks::built_in::DiscordWebhook::with_public_key(self.public_key())
+            .expect("public key is valid hex")
+    }
+}
+
+#[cfg(feature = "discord")]
+impl Default for DiscordTestKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "discord")]
+impl TestSigner for DiscordTestKey {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        use ed25519_dalek::Signer;
+
+        let timestamp = timestamp.to_string();
+        let signature = self.0.sign(&[timestamp.as_bytes(), body].concat());
+        vec![
+            (
+                "X-Signature-Ed25519".into(),
+                hex::encode(signature.to_bytes()),
+            ),
+            ("X-Signature-Timestamp".into(), timestamp),
+        ]
+    }
+}
+
+/// Test keypair for the [SendGridWebhook](crate::webhooks::built_in::SendGridWebhook)
+#[cfg(feature = "sendgrid")]
+pub struct SendGridTestKey(p256::ecdsa::SigningKey);
+
+#[cfg(feature = "sendgrid")]
+impl SendGridTestKey {
+    /// Create a test keypair from a fixed secret
+    pub fn new() -> Self {
+        let key = p256::ecdsa::SigningKey::from_bytes(&[7; 32].into()).expect("key is valid");
+        Self(key)
+    }
+
+    /// The base64 public key, as provided by SendGrid
+    pub fn public_key(&self) -> String {
+        use base64::{Engine, prelude::BASE64_STANDARD};
+
+        BASE64_STANDARD.encode(self.0.verifying_key().to_encoded_point(false).as_bytes())
+    }
+
+    /// A SendGrid webhook using the test public key
+    pub fn webhook(&self) -> crate::webhooks::built_in::SendGridWebhook {
+        crate::webhooks::built_in::SendGridWebhook::with_public_key(self.public_key())
+            .expect("public key is valid base64")
+    }
+}
+
+#[cfg(feature = "sendgrid")]
+impl Default for SendGridTestKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "sendgrid")]
+impl TestSigner for SendGridTestKey {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        use base64::{Engine, prelude::BASE64_STANDARD};
+        use p256::ecdsa::{Signature, signature::Signer};
+
+        let timestamp = timestamp.to_string();
+        let signature: Signature = self.0.sign(&[timestamp.as_bytes(), body].concat());
+        vec![
+            (
+                "X-Twilio-Email-Event-Webhook-Signature".into(),
+                BASE64_STANDARD.encode(signature.to_der()),
+            ),
+            ("X-Twilio-Email-Event-Webhook-Timestamp".into(), timestamp),
+        ]
+    }
+}
@@ -1,14 +1,33 @@
 //! Webhook traits and implementations
 
-use rocket::{Request, data::Outcome, http::Status, tokio::io::AsyncRead};
+use rocket::tokio::io::AsyncRead;
 
-use crate::WebhookError;
+use crate::{WebhookError, WebhookRequest, challenge::ChallengeResponse};
 
 pub mod built_in;
 pub mod generic;
 pub mod interface;
 
-mod utils;
+pub(crate) mod utils;
+
+/// A webhook body that passed validation
+#[derive(Debug)]
+pub struct ValidatedBody {
+    /// The raw body
+    pub data: Vec<u8>,
+    /// For webhooks with multiple secret keys, the index of the secret key that
+    /// validated the signature
+    pub secret_index: Option<usize>,
+}
+
+impl From<Vec<u8>> for ValidatedBody {
+    fn from(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            secret_index: None,
+        }
+    }
+}
 
 /// Base interface for all webhooks
 pub trait Webhook {
@@ -16,51 +35,77 @@ pub trait Webhook {
     /// is within the expected bounds (bounds are in unix epoch seconds).
     fn validate_body(
         &self,
-        req: &Request<'_>,
-        body_reader: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body_reader: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> impl Future<Output = Outcome<'_, Vec<u8>, WebhookError>> + Send + Sync;
+    ) -> impl Future<Output = Result<ValidatedBody, WebhookError>> + Send;
+
+    /// Get a unique identifier for this delivery, used to reject duplicate deliveries when a
+    /// [ReplayStore](crate::replay::ReplayStore) is configured. The ID should be covered by the
+    /// signature, so it can't be changed when replaying a delivery. If the provider doesn't sign
+    /// an ID, an unsigned delivery header can still be used to reject the provider's retries, but
+    /// this doesn't protect against replays (and should be documented). The body has already been
+    /// validated. The default implementation returns `None`, which skips the replay check.
+    #[allow(unused_variables)]
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        None
+    }
+
+    /// Get the type of the event (e.g. from the `X-GitHub-Event` header, or the `type` field of the
+    /// body), used to deserialize [typed events](crate::event::FromWebhookEvent). The default
+    /// implementation returns `None`.
+    #[allow(unused_variables)]
+    fn event_type(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        None
+    }
+
+    /// Get the response to a verification challenge from the provider (e.g. when the webhook URL
+    /// is registered), if the validated body is a challenge. Challenges are answered automatically
+    /// when the [WebhookChallenges](crate::challenge::WebhookChallenges) fairing is attached. The
+    /// default implementation returns `None`.
+    #[allow(unused_variables)]
+    fn challenge_response(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &ValidatedBody,
+    ) -> impl Future<Output = Option<ChallengeResponse>> + Send {
+        async { None }
+    }
 
     /// Validate a timestamp against the given bounds. The default implementation assumes
-    /// that it is in Unix epoch seconds, and returns a Bad Request error if it is invalid.
+    /// that it is in Unix epoch seconds, and returns a [WebhookError::Timestamp] error if it is invalid.
     fn validate_timestamp(
         &self,
         timestamp: &str,
         (min, max): (u32, u32),
-    ) -> Outcome<'_, (), WebhookError> {
+    ) -> Result<(), WebhookError> {
         let unix_timestamp = timestamp.parse::<u32>().ok();
         match unix_timestamp.map(|t| t >= min && t <= max) {
-            Some(true) => Outcome::Success(()),
-            Some(false) | None => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::Timestamp(timestamp.into()),
-            )),
+            Some(true) => Ok(()),
+            Some(false) | None => Err(WebhookError::Timestamp(timestamp.into())),
         }
     }
 
     /// Retrieve a header that's expected for a webhook request. The default
-    /// implementation looks for the header and returns a Bad Request error if it was not provided.
-    /// It can also optionally strip a given prefix.
+    /// implementation looks for the header and returns a [WebhookError::MissingHeader] error if it was
+    /// not provided. It can also optionally strip a given prefix.
     fn get_header<'r>(
         &self,
-        req: &'r Request<'_>,
+        req: &'r dyn WebhookRequest,
         name: &str,
         prefix: Option<&str>,
-    ) -> Outcome<'_, &'r str, WebhookError> {
-        let Some(mut header) = req.headers().get_one(name) else {
-            return Outcome::Error((Status::BadRequest, WebhookError::MissingHeader(name.into())));
+    ) -> Result<&'r str, WebhookError> {
+        let Some(mut header) = req.header(name) else {
+            return Err(WebhookError::MissingHeader(name.into()));
         };
         if let Some(prefix) = prefix {
             let Some(stripped) = header.strip_prefix(prefix) else {
-                return Outcome::Error((
-                    Status::BadRequest,
-                    WebhookError::InvalidHeader(format!(
-                        "'{name}' is missing prefix '{prefix}': {header}"
-                    )),
-                ));
+                return Err(WebhookError::InvalidHeader(format!(
+                    "'{name}' is missing prefix '{prefix}': {header}"
+                )));
             };
             header = stripped;
         }
-        Outcome::Success(header)
+        Ok(header)
     }
 }
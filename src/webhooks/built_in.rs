@@ -10,6 +10,23 @@ mod github;
 #[cfg(feature = "github")]
 pub use github::GitHubWebhook;
 
+#[cfg(feature = "gitlab")]
+mod gitlab;
+#[cfg(feature = "gitlab")]
+pub use gitlab::{GitLabMetadata, GitLabWebhook};
+
+#[cfg(feature = "meta")]
+mod meta;
+#[cfg(feature = "meta")]
+pub use meta::MetaWebhook;
+
+#[cfg(feature = "pubsub")]
+mod pubsub;
+#[cfg(feature = "pubsub")]
+pub use pubsub::{
+    PubSubEnvelope, PubSubMessage, PubSubPayload, PubSubPayloadRaw, PubSubPushWebhook,
+};
+
 #[cfg(feature = "sendgrid")]
 mod sendgrid;
 #[cfg(feature = "sendgrid")]
@@ -25,12 +42,27 @@ mod slack;
 #[cfg(feature = "slack")]
 pub use slack::SlackWebhook;
 
+#[cfg(feature = "sns")]
+mod sns;
+#[cfg(feature = "sns")]
+pub use sns::{SnsMessage, SnsMessageType, SnsWebhook};
+
 #[cfg(feature = "stripe")]
 mod stripe;
 #[cfg(feature = "stripe")]
 pub use stripe::StripeWebhook;
 
+#[cfg(feature = "twitch")]
+mod twitch;
+#[cfg(feature = "twitch")]
+pub use twitch::TwitchWebhook;
+
 #[cfg(feature = "standard")]
 mod standard;
 #[cfg(feature = "standard")]
 pub use standard::StandardWebhook;
+
+#[cfg(feature = "zoom")]
+mod zoom;
+#[cfg(feature = "zoom")]
+pub use zoom::ZoomWebhook;
@@ -1,12 +1,18 @@
-use hex::FromHexError;
-use rocket::{data::Outcome, http::Status, outcome::try_outcome, tokio::io::AsyncRead};
+use rocket::{
+    serde::json::{Value, json, serde_json},
+    tokio::io::AsyncRead,
+};
 use tokio_util::bytes::{Bytes, BytesMut};
 
 use crate::{
-    WebhookError,
+    WebhookError, WebhookRequest,
+    challenge::ChallengeResponse,
     webhooks::{
-        Webhook,
-        interface::public_key::{WebhookPublicKey, algorithms::ed25519::Ed25519},
+        ValidatedBody, Webhook,
+        interface::public_key::{
+            InvalidPublicKey, PublicKey, WebhookPublicKey, algorithms::ed25519::Ed25519,
+        },
+        utils::signed_content_id,
     },
 };
 
@@ -17,13 +23,13 @@ use crate::{
 ///
 /// [Discord docs](https://discord.com/developers/docs/interactions/overview#setting-up-an-endpoint-validating-security-request-headers)
 pub struct DiscordWebhook {
-    public_key: Bytes,
+    public_key: PublicKey<Ed25519>,
 }
 
 impl DiscordWebhook {
     /// Instantiate using the hex public key from Discord
-    pub fn with_public_key(public_key: impl AsRef<str>) -> Result<Self, FromHexError> {
-        let public_key = Bytes::from(hex::decode(public_key.as_ref())?);
+    pub fn with_public_key(public_key: impl AsRef<str>) -> Result<Self, InvalidPublicKey> {
+        let public_key = PublicKey::from_hex(public_key)?;
         Ok(Self { public_key })
     }
 }
@@ -31,47 +37,63 @@ impl DiscordWebhook {
 impl Webhook for DiscordWebhook {
     async fn validate_body(
         &self,
-        req: &rocket::Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_public_key(req, body, time_bounds).await
     }
+
+    /// Discord doesn't send a delivery ID, so the signed timestamp and a digest of the body are used
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        let timestamp = req.header("X-Signature-Timestamp")?;
+        Some(signed_content_id(timestamp, body))
+    }
+
+    /// Answers PING interactions with a PONG
+    async fn challenge_response(
+        &self,
+        _req: &dyn WebhookRequest,
+        body: &ValidatedBody,
+    ) -> Option<ChallengeResponse> {
+        let interaction: Value = serde_json::from_slice(&body.data).ok()?;
+        (interaction["type"] == 1).then(|| ChallengeResponse::json(&json!({ "type": 1 })))
+    }
 }
 
 impl WebhookPublicKey for DiscordWebhook {
     type ALG = Ed25519;
 
-    async fn public_key(&self, _req: &rocket::Request<'_>) -> Outcome<'_, Bytes, WebhookError> {
-        Outcome::Success(self.public_key.clone())
+    async fn public_key(
+        &self,
+        _req: &dyn WebhookRequest,
+    ) -> Result<PublicKey<Ed25519>, WebhookError> {
+        Ok(self.public_key.clone())
     }
 
-    fn expected_signature(&self, req: &rocket::Request<'_>) -> Outcome<'_, Vec<u8>, WebhookError> {
-        let sig_header = try_outcome!(self.get_header(req, "X-Signature-Ed25519", None));
+    fn expected_signature(&self, req: &dyn WebhookRequest) -> Result<Vec<u8>, WebhookError> {
+        let sig_header = self.get_header(req, "X-Signature-Ed25519", None)?;
         match hex::decode(sig_header) {
-            Ok(bytes) => Outcome::Success(bytes),
-            Err(_) => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::InvalidHeader(format!(
-                    "X-Signature-Ed25519 header was not valid hex: '{sig_header}'"
-                )),
-            )),
+            Ok(bytes) => Ok(bytes),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "X-Signature-Ed25519 header was not valid hex: '{sig_header}'"
+            ))),
         }
     }
 
     fn message_to_verify(
         &self,
-        req: &rocket::Request<'_>,
+        req: &dyn WebhookRequest,
         body: &Bytes,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Bytes, WebhookError> {
-        let timestamp = try_outcome!(self.get_header(req, "X-Signature-Timestamp", None));
-        try_outcome!(self.validate_timestamp(timestamp, time_bounds));
+    ) -> Result<Bytes, WebhookError> {
+        let timestamp = self.get_header(req, "X-Signature-Timestamp", None)?;
+        self.validate_timestamp(timestamp, time_bounds)?;
 
         let mut timestamp_and_body = BytesMut::with_capacity(timestamp.len() + body.len());
         timestamp_and_body.extend_from_slice(timestamp.as_bytes());
         timestamp_and_body.extend_from_slice(body);
 
-        Outcome::Success(timestamp_and_body.freeze())
+        Ok(timestamp_and_body.freeze())
     }
 }
@@ -1,11 +1,13 @@
 use hmac::Hmac;
-use rocket::{Request, data::Outcome, http::Status, outcome::try_outcome, tokio::io::AsyncRead};
+use rocket::tokio::io::AsyncRead;
 use sha2::Sha256;
-use zeroize::Zeroizing;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, interface::hmac::WebhookHmac},
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+    },
 };
 
 /// # GitHub webhook
@@ -13,14 +15,27 @@ use crate::{
 ///
 /// [GitHub docs](https://docs.github.com/en/webhooks/using-webhooks/validating-webhook-deliveries)
 pub struct GitHubWebhook {
-    secret_key: Zeroizing<Vec<u8>>,
+    secret_keys: HmacSecrets,
 }
 
 impl GitHubWebhook {
     /// Instantiate with the secret key
     pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple secret keys in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
         Self {
-            secret_key: Zeroizing::new(secret_key.into()),
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
         }
     }
 }
@@ -28,31 +43,55 @@ impl GitHubWebhook {
 impl Webhook for GitHubWebhook {
     async fn validate_body(
         &self,
-        req: &Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_hmac(req, body, time_bounds).await
     }
+
+    /// The `X-GitHub-Delivery` header. It isn't signed, so this only rejects GitHub's retries of a
+    /// delivery, and doesn't protect against replays.
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header("X-GitHub-Delivery").map(str::to_owned)
+    }
+
+    fn event_type(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header("X-GitHub-Event").map(str::to_owned)
+    }
 }
 
 impl WebhookHmac for GitHubWebhook {
     type MAC = Hmac<Sha256>;
 
-    fn secret_key(&self) -> &[u8] {
-        &self.secret_key
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
     }
 
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError> {
-        let sig_header = try_outcome!(self.get_header(req, "X-Hub-Signature-256", Some("sha256=")));
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, "X-Hub-Signature-256", Some("sha256="))?;
         match hex::decode(sig_header) {
-            Ok(bytes) => Outcome::Success(vec![bytes]),
-            Err(_) => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::InvalidHeader(format!(
-                    "X-Hub-Signature-256 header was not valid hex: '{sig_header}'"
-                )),
-            )),
+            Ok(bytes) => Ok(vec![bytes]),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "X-Hub-Signature-256 header was not valid hex: '{sig_header}'"
+            ))),
         }
     }
 }
+
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for GitHubWebhook {
+    fn signed_headers(&self, body: &[u8], _timestamp: u32) -> Vec<(String, String)> {
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[body]);
+        vec![
+            (
+                "X-Hub-Signature-256".into(),
+                format!("sha256={}", hex::encode(signature)),
+            ),
+            (
+                "X-GitHub-Delivery".into(),
+                crate::testing::test_delivery_id(),
+            ),
+        ]
+    }
+}
@@ -0,0 +1,161 @@
+use rocket::tokio::io::{AsyncRead, AsyncReadExt};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+use crate::{
+    WebhookError, WebhookEvent, WebhookPayload, WebhookPayloadRaw, WebhookRequest,
+    webhooks::{ValidatedBody, Webhook, built_in::StandardWebhook, utils::body_size},
+};
+
+const TOKEN_HEADER: &str = "X-Gitlab-Token";
+const EVENT_HEADER: &str = "X-Gitlab-Event";
+const INSTANCE_HEADER: &str = "X-Gitlab-Instance";
+const EVENT_UUID_HEADER: &str = "X-Gitlab-Event-UUID";
+
+/// # GitLab webhook
+/// Supports both of GitLab's verification modes:
+/// - **Secret token**: compares the `X-Gitlab-Token` header to the secret token (in constant time)
+/// - **Signing token**: validates the `webhook-id`, `webhook-timestamp` and `webhook-signature`
+///   headers, following the Standard Webhooks spec (see [StandardWebhook])
+///
+/// The `X-Gitlab-Event`, `X-Gitlab-Instance` and `X-Gitlab-Event-UUID` headers are available
+/// from the data guards as [GitLabMetadata], e.g. `payload.metadata()`.
+///
+/// [GitLab docs](https://docs.gitlab.com/user/project/integrations/webhooks/)
+pub struct GitLabWebhook {
+    mode: GitLabMode,
+}
+
+enum GitLabMode {
+    Token(Zeroizing<Vec<u8>>),
+    Signed(StandardWebhook),
+}
+
+impl GitLabWebhook {
+    /// Instantiate with the secret token, sent by GitLab in the `X-Gitlab-Token` header
+    pub fn with_token(secret_token: impl Into<Vec<u8>>) -> Self {
+        Self {
+            mode: GitLabMode::Token(Zeroizing::new(secret_token.into())),
+        }
+    }
+
+    /// Instantiate with the signing token starting with `whsec_`, used by GitLab to sign the
+    /// webhook
+    pub fn with_signing_token(signing_token: impl AsRef<str>) -> Result<Self, base64::DecodeError> {
+        Ok(Self {
+            mode: GitLabMode::Signed(StandardWebhook::with_secret(signing_token)?),
+        })
+    }
+
+    /// Validate the secret token header, and read the body
+    async fn validate_with_token(
+        &self,
+        req: &dyn WebhookRequest,
+        secret_token: &[u8],
+        mut body: impl AsyncRead + Unpin + Send,
+    ) -> Result<ValidatedBody, WebhookError> {
+        let token = self.get_header(req, TOKEN_HEADER, None)?;
+        if !bool::from(token.as_bytes().ct_eq(secret_token)) {
+            return Err(WebhookError::Signature(format!(
+                "{TOKEN_HEADER} header did not match the secret token"
+            )));
+        }
+
+        let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(512));
+        body.read_to_end(&mut raw_body)
+            .await
+            .map_err(WebhookError::Read)?;
+        Ok(raw_body.into())
+    }
+}
+
+impl Webhook for GitLabWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        match &self.mode {
+            GitLabMode::Token(secret_token) => {
+                self.validate_with_token(req, secret_token, body).await
+            }
+            GitLabMode::Signed(standard) => standard.validate_body(req, body, time_bounds).await,
+        }
+    }
+
+    /// With a signing token, uses the signed `webhook-id` header. With a secret token nothing is
+    /// signed, so the `Idempotency-Key` (or `X-Gitlab-Event-UUID`) header is used: this only
+    /// rejects GitLab's retries of a delivery, and doesn't protect against replays.
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        match &self.mode {
+            GitLabMode::Signed(standard) => standard.delivery_id(req, body),
+            GitLabMode::Token(_) => req
+                .header("Idempotency-Key")
+                .or_else(|| req.header(EVENT_UUID_HEADER))
+                .map(str::to_owned),
+        }
+    }
+
+    fn event_type(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header(EVENT_HEADER).map(str::to_owned)
+    }
+}
+
+/// Metadata sent with GitLab webhooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GitLabMetadata<'r> {
+    /// The event type, e.g. `Push Hook` (`X-Gitlab-Event`)
+    pub event: Option<&'r str>,
+    /// The hostname of the GitLab instance that sent the webhook (`X-Gitlab-Instance`)
+    pub instance: Option<&'r str>,
+    /// Unique ID of the event (`X-Gitlab-Event-UUID`)
+    pub event_uuid: Option<&'r str>,
+}
+
+impl<'r> GitLabMetadata<'r> {
+    /// Get the metadata from the request headers
+    pub fn from_request(req: &'r dyn WebhookRequest) -> Self {
+        Self {
+            event: req.header(EVENT_HEADER),
+            instance: req.header(INSTANCE_HEADER),
+            event_uuid: req.header(EVENT_UUID_HEADER),
+        }
+    }
+}
+
+impl<'r, T, M> WebhookPayload<'r, T, GitLabWebhook, M> {
+    /// The GitLab metadata sent with the webhook
+    pub fn metadata(&self) -> GitLabMetadata<'r> {
+        GitLabMetadata::from_request(self.headers)
+    }
+}
+
+impl<'r, M> WebhookPayloadRaw<'r, GitLabWebhook, M> {
+    /// The GitLab metadata sent with the webhook
+    pub fn metadata(&self) -> GitLabMetadata<'r> {
+        GitLabMetadata::from_request(self.headers)
+    }
+}
+
+impl<'r, E, M> WebhookEvent<'r, E, GitLabWebhook, M> {
+    /// The GitLab metadata sent with the webhook
+    pub fn metadata(&self) -> GitLabMetadata<'r> {
+        GitLabMetadata::from_request(self.headers)
+    }
+}
+
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for GitLabWebhook {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        let mut headers = match &self.mode {
+            GitLabMode::Token(secret_token) => vec![(
+                TOKEN_HEADER.into(),
+                String::from_utf8_lossy(secret_token).into_owned(),
+            )],
+            GitLabMode::Signed(standard) => standard.signed_headers(body, timestamp),
+        };
+        headers.push((EVENT_UUID_HEADER.into(), crate::testing::test_delivery_id()));
+        headers
+    }
+}
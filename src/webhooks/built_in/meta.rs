@@ -0,0 +1,158 @@
+use hmac::Hmac;
+use rocket::{
+    Data, Request, Route, async_trait,
+    http::{Method, Status},
+    route::{Handler, Outcome},
+    tokio::io::AsyncRead,
+};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+
+use crate::{
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+        utils::json_str_field,
+    },
+};
+
+/**
+# Meta webhook
+Webhooks from Meta apps (WhatsApp, Messenger, Instagram, etc.)
+
+Looks for hex signature in `X-Hub-Signature-256` header with a `sha256=` prefix, using the
+app secret.
+
+Meta verifies the webhook URL using a separate GET request, which can be answered automatically
+by mounting the [MetaWebhook::verification_route] at the same path.
+
+[Meta docs](https://developers.facebook.com/docs/graph-api/webhooks/getting-started)
+
+# Example
+```
+use rocket::{post, routes};
+use rocket_webhook::{RocketWebhook, WebhookPayloadRaw, webhooks::built_in::MetaWebhook};
+
+#[post("/", data = "<payload>")]
+async fn meta_events(payload: WebhookPayloadRaw<'_, MetaWebhook>) {}
+
+let meta_webhook = RocketWebhook::builder()
+    .webhook(MetaWebhook::with_secret("my-app-secret"))
+    .build();
+let rocket = rocket::build()
+    .manage(meta_webhook)
+    .mount("/webhooks/meta", routes![meta_events])
+    .mount("/webhooks/meta", [MetaWebhook::verification_route("my-verify-token")]);
+```
+*/
+pub struct MetaWebhook {
+    secret_keys: HmacSecrets,
+}
+
+impl MetaWebhook {
+    /// Instantiate with the app secret
+    pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple app secrets in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the app secret(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
+        }
+    }
+
+    /// A GET route that answers Meta's verification request, by responding with the
+    /// `hub.challenge` if the `hub.verify_token` matches the given verify token. Mount it at
+    /// the same path as the webhook route.
+    pub fn verification_route(verify_token: impl Into<String>) -> Route {
+        let handler = MetaVerification {
+            verify_token: verify_token.into(),
+        };
+        Route::new(Method::Get, "/", handler)
+    }
+}
+
+/// Handler for Meta's verification request
+#[derive(Clone)]
+struct MetaVerification {
+    verify_token: String,
+}
+
+#[async_trait]
+impl Handler for MetaVerification {
+    async fn handle<'r>(&self, req: &'r Request<'_>, _data: Data<'r>) -> Outcome<'r> {
+        let query_value = |name: &str| {
+            req.query_fields()
+                .find(|field| field.name.source() == name)
+                .map(|field| field.value)
+        };
+        let mode = query_value("hub.mode");
+        let token = query_value("hub.verify_token").unwrap_or_default();
+        let challenge = query_value("hub.challenge");
+
+        match challenge {
+            Some(challenge)
+                if mode == Some("subscribe")
+                    && bool::from(token.as_bytes().ct_eq(self.verify_token.as_bytes())) =>
+            {
+                Outcome::from(req, challenge.to_owned())
+            }
+            _ => Outcome::Error(Status::Forbidden),
+        }
+    }
+}
+
+impl Webhook for MetaWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        self.validate_with_hmac(req, body, time_bounds).await
+    }
+
+    /// The `object` field of the event (e.g. `whatsapp_business_account`)
+    fn event_type(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "object")
+    }
+}
+
+impl WebhookHmac for MetaWebhook {
+    type MAC = Hmac<Sha256>;
+
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
+    }
+
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, "X-Hub-Signature-256", Some("sha256="))?;
+        match hex::decode(sig_header) {
+            Ok(bytes) => Ok(vec![bytes]),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "X-Hub-Signature-256 header was not valid hex: '{sig_header}'"
+            ))),
+        }
+    }
+}
+
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for MetaWebhook {
+    fn signed_headers(&self, body: &[u8], _timestamp: u32) -> Vec<(String, String)> {
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[body]);
+        vec![(
+            "X-Hub-Signature-256".into(),
+            format!("sha256={}", hex::encode(signature)),
+        )]
+    }
+}
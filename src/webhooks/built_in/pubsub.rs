@@ -0,0 +1,378 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use rocket::{
+    Request, async_trait,
+    data::{FromData, Outcome},
+    http::{HeaderMap, Status},
+    outcome::try_outcome,
+    serde::{Deserialize, DeserializeOwned, Deserializer, de::Error, json::serde_json},
+    tokio::io::AsyncRead,
+};
+
+use crate::{
+    WebhookError, WebhookRequest,
+    guard::validate_request,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::{
+            jwt::{JwtClaims, JwtHeader, JwtKey, JwtKeyProvider, WebhookJwt},
+            public_key::{
+                algorithms::rsa::RsaPkcs1v15Sha256,
+                remote::{KeyFetcher, RemotePublicKeys},
+            },
+        },
+        utils::unix_time,
+    },
+};
+
+const AUTH_HEADER: &str = "Authorization";
+const GOOGLE_CERTS_URL: &str = "https://www.googleapis.com/oauth2/v3/certs";
+const GOOGLE_ISSUERS: [&str; 2] = ["https://accounts.google.com", "accounts.google.com"];
+/// Lifetime of the tokens signed by Google, which Pub/Sub may reuse for several deliveries
+const TOKEN_LIFETIME: u32 = 60 * 60;
+
+/// # Google Cloud Pub/Sub push webhook
+/// Validates push deliveries from a Pub/Sub subscription with authentication enabled. The
+/// `Authorization: Bearer` header contains an OIDC token signed by Google (`RS256`), which is
+/// verified with Google's public keys (fetched from its JWKS URL and cached). The token must
+/// have the configured audience, Google as its issuer, and the email of the subscription's
+/// service account (verified).
+///
+/// Pub/Sub may reuse a token for up to an hour, so the token's issued at time (`iat`) may be
+/// older than the timestamp tolerance, as long as the token hasn't expired.
+///
+/// **Pub/Sub push has no replay protection.** The token doesn't cover the body, so a captured
+/// token can be sent again with any body until it expires (up to an hour), and the push envelope
+/// (including its `messageId`) can't be trusted as a delivery ID. A configured
+/// [ReplayStore](crate::replay::ReplayStore) has no effect on this webhook. Pub/Sub also
+/// delivers messages at least once, so make the route idempotent (e.g. using
+/// [PubSubPayload::message_id]).
+///
+/// Use the [PubSubPayload] or [PubSubPayloadRaw] data guards to get the decoded message data
+/// and its attributes, instead of the push envelope.
+///
+/// # Example
+/// ```
+/// # #[cfg(feature = "jwks-reqwest")] {
+/// use rocket::{post, serde::Deserialize};
+/// use rocket_webhook::{
+///     RocketWebhook,
+///     webhooks::built_in::{PubSubPayload, PubSubPushWebhook},
+/// };
+///
+/// let pubsub_webhook = RocketWebhook::builder()
+///     .webhook(PubSubPushWebhook::new(
+///         reqwest::Client::new(),
+///         "https://example.com/pubsub",
+///         "push-sender@my-project.iam.gserviceaccount.com",
+///     ))
+///     .build();
+///
+/// #[derive(Deserialize)]
+/// #[serde(crate = "rocket::serde")]
+/// struct Order {
+///     id: u64,
+/// }
+///
+/// #[post("/pubsub", data = "<payload>")]
+/// async fn pubsub_route(payload: PubSubPayload<'_, Order>) -> String {
+///     let source = payload.attributes.get("source");
+///     format!("Received order {} from {source:?}", payload.data.id)
+/// }
+/// # }
+/// ```
+///
+/// [Google docs](https://cloud.google.com/pubsub/docs/authenticate-push-subscriptions)
+pub struct PubSubPushWebhook {
+    keys: RemotePublicKeys<RsaPkcs1v15Sha256>,
+    audience: String,
+    service_account: String,
+}
+
+impl PubSubPushWebhook {
+    /// Instantiate with the HTTP client used to fetch Google's public keys, the audience
+    /// configured on the subscription (defaults to the push endpoint URL), and the email of the
+    /// service account used by the subscription
+    pub fn new(
+        fetcher: impl KeyFetcher + 'static,
+        audience: impl Into<String>,
+        service_account: impl Into<String>,
+    ) -> Self {
+        Self::with_keys(
+            RemotePublicKeys::builder()
+                .url(GOOGLE_CERTS_URL)
+                .fetcher(fetcher)
+                .build(),
+            audience,
+            service_account,
+        )
+    }
+
+    /// Instantiate with the source of Google's public keys, e.g. to configure how long they
+    /// are cached
+    pub fn with_keys(
+        keys: RemotePublicKeys<RsaPkcs1v15Sha256>,
+        audience: impl Into<String>,
+        service_account: impl Into<String>,
+    ) -> Self {
+        Self {
+            keys,
+            audience: audience.into(),
+            service_account: service_account.into(),
+        }
+    }
+}
+
+impl Webhook for PubSubPushWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        (min, max): (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        // Accept reused tokens, and check the expiration in `validate_claims`
+        let time_bounds = (min.saturating_sub(TOKEN_LIFETIME), max);
+        self.validate_with_jwt(req, body, time_bounds).await
+    }
+}
+
+impl WebhookJwt for PubSubPushWebhook {
+    fn token<'r>(&self, req: &'r dyn WebhookRequest) -> Result<&'r str, WebhookError> {
+        self.get_header(req, AUTH_HEADER, None)
+    }
+
+    async fn verification_keys(
+        &self,
+        _req: &dyn WebhookRequest,
+        header: &JwtHeader,
+    ) -> Result<Vec<JwtKey>, WebhookError> {
+        JwtKeyProvider::key(&self.keys, header)
+            .await
+            .map(|key| vec![key])
+    }
+
+    async fn refreshed_verification_keys(
+        &self,
+        _req: &dyn WebhookRequest,
+        header: &JwtHeader,
+        stale: &[JwtKey],
+    ) -> Result<Option<Vec<JwtKey>>, WebhookError> {
+        let [stale] = stale else {
+            return Ok(None);
+        };
+        let refreshed = self.keys.refreshed_key(header, stale).await?;
+        Ok(refreshed.map(|key| vec![key]))
+    }
+
+    fn audience(&self) -> Option<&str> {
+        Some(&self.audience)
+    }
+
+    fn validate_claims(
+        &self,
+        _req: &dyn WebhookRequest,
+        claims: &JwtClaims,
+    ) -> Result<(), WebhookError> {
+        let issuer = claims.get_str("iss").unwrap_or_default();
+        if !GOOGLE_ISSUERS.contains(&issuer) {
+            return Err(WebhookError::Signature(format!(
+                "Token issuer '{issuer}' is not Google"
+            )));
+        }
+        if claims.get_str("email") != Some(&self.service_account)
+            || claims.get("email_verified").and_then(|v| v.as_bool()) != Some(true)
+        {
+            return Err(WebhookError::Signature(format!(
+                "Token email is not the verified service account '{}'",
+                self.service_account
+            )));
+        }
+        if claims
+            .get_time("exp")
+            .is_none_or(|exp| exp < i64::from(unix_time()))
+        {
+            return Err(WebhookError::Timestamp("Token has expired".into()));
+        }
+        Ok(())
+    }
+}
+
+/// The JSON body of a Pub/Sub push delivery
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct PubSubEnvelope {
+    /// The message
+    pub message: PubSubMessage,
+    /// Name of the subscription, e.g. `projects/my-project/subscriptions/my-subscription`
+    pub subscription: String,
+    /// Number of delivery attempts, if the subscription has a dead-letter topic
+    pub delivery_attempt: Option<u32>,
+}
+
+/// A Pub/Sub message
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct PubSubMessage {
+    /// The message data (decoded from base64)
+    #[serde(default, deserialize_with = "deserialize_base64")]
+    pub data: Vec<u8>,
+    /// The message attributes
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    /// Unique ID of the message
+    pub message_id: String,
+    /// Time the message was published (RFC 3339)
+    pub publish_time: String,
+    /// Ordering key of the message, if any
+    pub ordering_key: Option<String>,
+}
+
+/// Deserialize the base64-encoded message data
+fn deserialize_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let data = String::deserialize(deserializer)?;
+    BASE64_STANDARD.decode(data).map_err(D::Error::custom)
+}
+
+/**
+Data guard to validate a [Pub/Sub push delivery](PubSubPushWebhook), and deserialize the JSON
+data of the message into the `T` type. The webhook configuration must be in Rocket
+state using [RocketWebhook](crate::RocketWebhook).
+```
+use rocket::{post, serde::Deserialize};
+use rocket_webhook::webhooks::built_in::PubSubPayload;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Order {
+    id: u64,
+}
+
+#[post("/pubsub", data = "<payload>")]
+async fn pubsub_route(payload: PubSubPayload<'_, Order>) -> &'static str {
+    payload.data; // access the deserialized message data
+    payload.attributes; // access the message attributes
+    payload.message_id; // access the ID of the message
+
+    "OK"
+}
+```
+*/
+pub struct PubSubPayload<'r, T, M = PubSubPushWebhook> {
+    /// The deserialized message data
+    pub data: T,
+    /// The message attributes
+    pub attributes: HashMap<String, String>,
+    /// Unique ID of the message
+    pub message_id: String,
+    /// Time the message was published (RFC 3339)
+    pub publish_time: String,
+    /// Ordering key of the message, if any
+    pub ordering_key: Option<String>,
+    /// Name of the subscription
+    pub subscription: String,
+    /// Number of delivery attempts, if the subscription has a dead-letter topic
+    pub delivery_attempt: Option<u32>,
+    /// The headers sent with the webhook request
+    pub headers: &'r HeaderMap<'r>,
+    _marker: PhantomData<M>,
+}
+
+#[async_trait]
+impl<'r, T, M> FromData<'r> for PubSubPayload<'r, T, M>
+where
+    T: DeserializeOwned,
+    M: Send + Sync + 'static,
+{
+    type Error = WebhookError;
+
+    async fn from_data(
+        req: &'r Request<'_>,
+        data: rocket::Data<'r>,
+    ) -> Outcome<'r, Self, Self::Error> {
+        let raw = try_outcome!(PubSubPayloadRaw::<M>::from_data(req, data).await);
+
+        match serde_json::from_slice(&raw.data) {
+            Ok(data) => Outcome::Success(Self {
+                data,
+                attributes: raw.attributes,
+                message_id: raw.message_id,
+                publish_time: raw.publish_time,
+                ordering_key: raw.ordering_key,
+                subscription: raw.subscription,
+                delivery_attempt: raw.delivery_attempt,
+                headers: raw.headers,
+                _marker: PhantomData,
+            }),
+            Err(e) => Outcome::Error((Status::BadRequest, WebhookError::Deserialize(e))),
+        }
+    }
+}
+
+/**
+Data guard to validate a [Pub/Sub push delivery](PubSubPushWebhook), and get the raw data of the
+message. The webhook configuration must be in Rocket state using
+[RocketWebhook](crate::RocketWebhook).
+```
+use rocket::post;
+use rocket_webhook::webhooks::built_in::PubSubPayloadRaw;
+
+#[post("/pubsub", data = "<payload>")]
+async fn pubsub_route(payload: PubSubPayloadRaw<'_>) -> &'static str {
+    payload.data; // access the message data (Vec<u8>)
+    payload.attributes; // access the message attributes
+
+    "OK"
+}
+```
+*/
+pub struct PubSubPayloadRaw<'r, M = PubSubPushWebhook> {
+    /// The message data
+    pub data: Vec<u8>,
+    /// The message attributes
+    pub attributes: HashMap<String, String>,
+    /// Unique ID of the message
+    pub message_id: String,
+    /// Time the message was published (RFC 3339)
+    pub publish_time: String,
+    /// Ordering key of the message, if any
+    pub ordering_key: Option<String>,
+    /// Name of the subscription
+    pub subscription: String,
+    /// Number of delivery attempts, if the subscription has a dead-letter topic
+    pub delivery_attempt: Option<u32>,
+    /// The headers sent with the webhook request
+    pub headers: &'r HeaderMap<'r>,
+    _marker: PhantomData<M>,
+}
+
+#[async_trait]
+impl<'r, M> FromData<'r> for PubSubPayloadRaw<'r, M>
+where
+    M: Send + Sync + 'static,
+{
+    type Error = WebhookError;
+
+    async fn from_data(
+        req: &'r Request<'_>,
+        data: rocket::Data<'r>,
+    ) -> Outcome<'r, Self, Self::Error> {
+        let (_, validated_body) =
+            try_outcome!(validate_request::<PubSubPushWebhook, M>(req, data).await);
+
+        match serde_json::from_slice::<PubSubEnvelope>(&validated_body.data) {
+            Ok(envelope) => Outcome::Success(Self {
+                data: envelope.message.data,
+                attributes: envelope.message.attributes,
+                message_id: envelope.message.message_id,
+                publish_time: envelope.message.publish_time,
+                ordering_key: envelope.message.ordering_key,
+                subscription: envelope.subscription,
+                delivery_attempt: envelope.delivery_attempt,
+                headers: req.headers(),
+                _marker: PhantomData,
+            }),
+            Err(e) => Outcome::Error((Status::BadRequest, WebhookError::Deserialize(e))),
+        }
+    }
+}
@@ -1,12 +1,15 @@
 use base64::{Engine, prelude::BASE64_STANDARD};
-use rocket::{data::Outcome, http::Status, outcome::try_outcome, tokio::io::AsyncRead};
+use rocket::tokio::io::AsyncRead;
 use tokio_util::bytes::{Bytes, BytesMut};
 
 use crate::{
-    WebhookError,
+    WebhookError, WebhookRequest,
     webhooks::{
-        Webhook,
-        interface::public_key::{WebhookPublicKey, algorithms::p256::EcdsaP256Asn1},
+        ValidatedBody, Webhook,
+        interface::public_key::{
+            InvalidPublicKey, PublicKey, WebhookPublicKey, algorithms::p256::EcdsaP256Asn1,
+        },
+        utils::signed_content_id,
     },
 };
 
@@ -17,13 +20,13 @@ use crate::{
 ///
 /// [SendGrid docs](https://www.twilio.com/docs/sendgrid/for-developers/tracking-events/getting-started-event-webhook-security-features#verify-the-signature)
 pub struct SendGridWebhook {
-    public_key: Bytes,
+    public_key: PublicKey<EcdsaP256Asn1>,
 }
 
 impl SendGridWebhook {
-    /// Instantiate using the base64 public key from SendGrid
-    pub fn with_public_key(public_key: impl AsRef<str>) -> Result<Self, base64::DecodeError> {
-        let public_key = Bytes::from(BASE64_STANDARD.decode(public_key.as_ref())?);
+    /// Instantiate using the base64 public key from SendGrid (PEM is also accepted)
+    pub fn with_public_key(public_key: impl AsRef<str>) -> Result<Self, InvalidPublicKey> {
+        let public_key = PublicKey::from_base64(public_key)?;
         Ok(Self { public_key })
     }
 }
@@ -31,49 +34,53 @@ impl SendGridWebhook {
 impl Webhook for SendGridWebhook {
     async fn validate_body(
         &self,
-        req: &rocket::Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_public_key(req, body, time_bounds).await
     }
+
+    /// SendGrid doesn't send a delivery ID, so the signed timestamp and a digest of the body are used
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        let timestamp = req.header("X-Twilio-Email-Event-Webhook-Timestamp")?;
+        Some(signed_content_id(timestamp, body))
+    }
 }
 
 impl WebhookPublicKey for SendGridWebhook {
     type ALG = EcdsaP256Asn1;
 
-    async fn public_key(&self, _req: &rocket::Request<'_>) -> Outcome<'_, Bytes, WebhookError> {
-        Outcome::Success(self.public_key.clone())
+    async fn public_key(
+        &self,
+        _req: &dyn WebhookRequest,
+    ) -> Result<PublicKey<EcdsaP256Asn1>, WebhookError> {
+        Ok(self.public_key.clone())
     }
 
-    fn expected_signature(&self, req: &rocket::Request<'_>) -> Outcome<'_, Vec<u8>, WebhookError> {
-        let sig_header =
-            try_outcome!(self.get_header(req, "X-Twilio-Email-Event-Webhook-Signature", None));
+    fn expected_signature(&self, req: &dyn WebhookRequest) -> Result<Vec<u8>, WebhookError> {
+        let sig_header = self.get_header(req, "X-Twilio-Email-Event-Webhook-Signature", None)?;
         match BASE64_STANDARD.decode(sig_header) {
-            Ok(bytes) => Outcome::Success(bytes),
-            Err(_) => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::InvalidHeader(format!(
-                    "X-Twilio-Email-Event-Webhook-Signature header was not valid base64: '{sig_header}'"
-                )),
-            )),
+            Ok(bytes) => Ok(bytes),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "X-Twilio-Email-Event-Webhook-Signature header was not valid base64: '{sig_header}'"
+            ))),
         }
     }
 
     fn message_to_verify(
         &self,
-        req: &rocket::Request<'_>,
+        req: &dyn WebhookRequest,
         body: &Bytes,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Bytes, WebhookError> {
-        let timestamp =
-            try_outcome!(self.get_header(req, "X-Twilio-Email-Event-Webhook-Timestamp", None));
-        try_outcome!(self.validate_timestamp(timestamp, time_bounds));
+    ) -> Result<Bytes, WebhookError> {
+        let timestamp = self.get_header(req, "X-Twilio-Email-Event-Webhook-Timestamp", None)?;
+        self.validate_timestamp(timestamp, time_bounds)?;
 
         let mut timestamp_and_body = BytesMut::with_capacity(timestamp.len() + body.len());
         timestamp_and_body.extend_from_slice(timestamp.as_bytes());
         timestamp_and_body.extend_from_slice(body);
 
-        Outcome::Success(timestamp_and_body.freeze())
+        Ok(timestamp_and_body.freeze())
     }
 }
@@ -1,12 +1,14 @@
 use base64::{Engine, prelude::BASE64_STANDARD};
 use hmac::Hmac;
-use rocket::{Request, data::Outcome, http::Status, outcome::try_outcome};
+use rocket::tokio::io::AsyncRead;
 use sha2::Sha256;
-use zeroize::Zeroizing;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, interface::hmac::WebhookHmac},
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+    },
 };
 
 /// # Shopify webhook
@@ -14,14 +16,27 @@ use crate::{
 ///
 /// [Shopify docs](https://shopify.dev/docs/apps/build/webhooks/subscribe/https#step-5-verify-the-webhook)
 pub struct ShopifyWebhook {
-    secret_key: Zeroizing<Vec<u8>>,
+    secret_keys: HmacSecrets,
 }
 
 impl ShopifyWebhook {
     /// Instantiate with the secret key
     pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple secret keys in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
         Self {
-            secret_key: Zeroizing::new(secret_key.into()),
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
         }
     }
 }
@@ -29,31 +44,55 @@ impl ShopifyWebhook {
 impl Webhook for ShopifyWebhook {
     async fn validate_body(
         &self,
-        req: &Request<'_>,
-        body: impl rocket::tokio::io::AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_hmac(req, body, time_bounds).await
     }
+
+    /// The `X-Shopify-Webhook-Id` header. It isn't signed, so this only rejects Shopify's retries
+    /// of a delivery, and doesn't protect against replays.
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header("X-Shopify-Webhook-Id").map(str::to_owned)
+    }
+
+    fn event_type(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header("X-Shopify-Topic").map(str::to_owned)
+    }
 }
 
 impl WebhookHmac for ShopifyWebhook {
     type MAC = Hmac<Sha256>;
 
-    fn secret_key(&self) -> &[u8] {
-        &self.secret_key
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
     }
 
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError> {
-        let sig_header = try_outcome!(self.get_header(req, "X-Shopify-Hmac-Sha256", None));
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, "X-Shopify-Hmac-Sha256", None)?;
         match BASE64_STANDARD.decode(sig_header) {
-            Ok(bytes) => Outcome::Success(vec![bytes]),
-            Err(_) => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::InvalidHeader(format!(
-                    "X-Shopify-Hmac-Sha256 header was not valid base64: '{sig_header}'"
-                )),
-            )),
+            Ok(bytes) => Ok(vec![bytes]),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "X-Shopify-Hmac-Sha256 header was not valid base64: '{sig_header}'"
+            ))),
         }
     }
 }
+
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for ShopifyWebhook {
+    fn signed_headers(&self, body: &[u8], _timestamp: u32) -> Vec<(String, String)> {
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[body]);
+        vec![
+            (
+                "X-Shopify-Hmac-Sha256".into(),
+                BASE64_STANDARD.encode(signature),
+            ),
+            (
+                "X-Shopify-Webhook-Id".into(),
+                crate::testing::test_delivery_id(),
+            ),
+        ]
+    }
+}
@@ -1,11 +1,18 @@
 use hmac::Hmac;
-use rocket::{Request, data::Outcome, http::Status, outcome::try_outcome, tokio::io::AsyncRead};
+use rocket::{
+    serde::json::{Value, serde_json},
+    tokio::io::AsyncRead,
+};
 use sha2::Sha256;
-use zeroize::Zeroizing;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, interface::hmac::WebhookHmac},
+    WebhookError, WebhookRequest,
+    challenge::ChallengeResponse,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+        utils::{json_str_field, signed_content_id},
+    },
 };
 
 /// # Slack webhook
@@ -16,14 +23,27 @@ use crate::{
 ///
 /// [Slack docs](https://docs.slack.dev/authentication/verifying-requests-from-slack/#validating-a-request)
 pub struct SlackWebhook {
-    secret_key: Zeroizing<Vec<u8>>,
+    secret_keys: HmacSecrets,
 }
 
 impl SlackWebhook {
     /// Instantiate with the secret key
     pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple secret keys in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
         Self {
-            secret_key: Zeroizing::new(secret_key.into()),
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
         }
     }
 }
@@ -31,43 +51,80 @@ impl SlackWebhook {
 impl Webhook for SlackWebhook {
     async fn validate_body(
         &self,
-        req: &Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_hmac(req, body, time_bounds).await
     }
+
+    /// Slack doesn't send a delivery ID, so the signed timestamp and a digest of the body are used
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        let timestamp = req.header("X-Slack-Request-Timestamp")?;
+        Some(signed_content_id(timestamp, body))
+    }
+
+    /// The `type` field of the event (e.g. `event_callback`)
+    fn event_type(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "type")
+    }
+
+    /// Answers the `url_verification` event with the challenge
+    async fn challenge_response(
+        &self,
+        _req: &dyn WebhookRequest,
+        body: &ValidatedBody,
+    ) -> Option<ChallengeResponse> {
+        let event: Value = serde_json::from_slice(&body.data).ok()?;
+        if event["type"] != "url_verification" {
+            return None;
+        }
+        event["challenge"].as_str().map(ChallengeResponse::text)
+    }
 }
 
 impl WebhookHmac for SlackWebhook {
     type MAC = Hmac<Sha256>;
 
-    fn secret_key(&self) -> &[u8] {
-        &self.secret_key
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
     }
 
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError> {
-        let sig_header = try_outcome!(self.get_header(req, "X-Slack-Signature", Some("v0=")));
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, "X-Slack-Signature", Some("v0="))?;
         match hex::decode(sig_header) {
-            Ok(bytes) => Outcome::Success(vec![bytes]),
-            Err(_) => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::InvalidHeader(format!(
-                    "X-Slack-Signature header was not valid hex: '{sig_header}'"
-                )),
-            )),
+            Ok(bytes) => Ok(vec![bytes]),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "X-Slack-Signature header was not valid hex: '{sig_header}'"
+            ))),
         }
     }
 
     fn body_prefix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        let timestamp = try_outcome!(self.get_header(req, "X-Slack-Request-Timestamp", None));
-        try_outcome!(self.validate_timestamp(timestamp, time_bounds));
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        let timestamp = self.get_header(req, "X-Slack-Request-Timestamp", None)?;
+        self.validate_timestamp(timestamp, time_bounds)?;
+
+        let prefix = [b"v0:", timestamp.as_bytes(), b":"].concat();
+        Ok(Some(prefix))
+    }
+}
 
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for SlackWebhook {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        let timestamp = timestamp.to_string();
         let prefix = [b"v0:", timestamp.as_bytes(), b":"].concat();
-        Outcome::Success(Some(prefix))
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[&prefix, body]);
+        vec![
+            (
+                "X-Slack-Signature".into(),
+                format!("v0={}", hex::encode(signature)),
+            ),
+            ("X-Slack-Request-Timestamp".into(), timestamp),
+        ]
     }
 }
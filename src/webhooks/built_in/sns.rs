@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+
+use base64::{Engine, prelude::BASE64_STANDARD};
+use rocket::{
+    serde::{Deserialize, json::serde_json},
+    tokio::{
+        io::{AsyncRead, AsyncReadExt},
+        sync::Mutex as AsyncMutex,
+    },
+};
+use x509_cert::{
+    Certificate,
+    der::{Decode, DecodePem, Encode},
+};
+
+use crate::{
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::public_key::{
+            PublicKey, WebhookPublicKeyAlgorithm,
+            algorithms::rsa::{RsaPkcs1v15Sha1, RsaPkcs1v15Sha256},
+            remote::KeyFetcher,
+        },
+        utils::{body_size, json_str_field, parse_rfc3339},
+    },
+};
+
+const DEFAULT_CERT_HOSTS: [&str; 2] = ["sns.*.amazonaws.com", "sns.*.amazonaws.com.cn"];
+/// How long a failed certificate fetch is cached, so a bad URL isn't fetched for every request
+const FAILED_FETCH_TTL: Duration = Duration::from_secs(60);
+
+/// # AWS SNS webhook
+/// Validates notifications sent by Amazon SNS to an HTTP(S) subscription. The body is a JSON
+/// [SnsMessage], signed with the certificate at its `SigningCertURL`. Supports `SignatureVersion`
+/// 1 (RSA SHA1) and 2 (RSA SHA256), for `Notification`, `SubscriptionConfirmation` and
+/// `UnsubscribeConfirmation` messages. Raw message delivery isn't supported, as it isn't signed.
+///
+/// The certificate is only fetched from an HTTPS URL whose host is allowed (by default, the
+/// `sns.<region>.amazonaws.com` hosts), and is cached by URL (failed fetches are cached for 1
+/// minute). Any AWS account can send signed messages from its own topics, so you should also
+/// restrict the topics with [SnsWebhook::with_topics].
+///
+/// The signed `Timestamp` of the message is checked against the timestamp tolerance. SNS keeps
+/// the original timestamp when it retries a delivery, so the tolerance should cover the retry
+/// time of the subscription's delivery policy (up to 1 hour). A
+/// [ReplayStore](crate::replay::ReplayStore) rejects duplicates using the signed `MessageId`.
+///
+/// # Example
+/// The route receives the [SnsMessage], and confirms the subscription when it is created by
+/// visiting its `SubscribeURL`:
+/// ```
+/// # #[cfg(feature = "jwks-reqwest")] {
+/// use rocket::{http::Status, post};
+/// use rocket_webhook::{
+///     RocketWebhook, WebhookPayload,
+///     webhooks::built_in::{SnsMessage, SnsMessageType, SnsWebhook},
+/// };
+///
+/// let sns_webhook = RocketWebhook::builder()
+///     .webhook(
+///         SnsWebhook::new(reqwest::Client::new())
+///             .with_topics(["arn:aws:sns:us-east-1:123456789012:my-topic"]),
+///     )
+///     .timestamp_tolerance(60 * 60, 15)
+///     .build();
+///
+/// #[post("/sns", data = "<payload>")]
+/// async fn sns_route(
+///     payload: WebhookPayload<'_, SnsMessage, SnsWebhook>,
+/// ) -> Status {
+///     let message = payload.data;
+///     match message.message_type {
+///         SnsMessageType::SubscriptionConfirmation => {
+///             // the SubscribeURL is part of the signed message
+///             let subscribe_url = message.subscribe_url.unwrap_or_default();
+///             if reqwest::get(subscribe_url).await.is_err() {
+///                 return Status::InternalServerError;
+///             }
+///         }
+///         SnsMessageType::Notification => {
+///             println!("{}", message.message);
+///         }
+///         SnsMessageType::UnsubscribeConfirmation => {}
+///     }
+///     Status::Ok
+/// }
+/// # }
+/// ```
+///
+/// [AWS docs](https://docs.aws.amazon.com/sns/latest/dg/sns-verify-signature-of-message.html)
+pub struct SnsWebhook {
+    fetcher: Box<dyn KeyFetcher>,
+    topic_arns: Option<Vec<String>>,
+    cert_hosts: Vec<String>,
+    certificates: RwLock<HashMap<String, CachedCertificate>>,
+    /// Per-URL locks, so concurrent requests share a single fetch of each certificate
+    fetch_locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+/// A fetched certificate, or the error from fetching it
+enum CachedCertificate {
+    Valid(SnsCertificate),
+    Failed { error: String, expires_at: Instant },
+}
+
+/// The public key of a signing certificate, for both signature versions
+#[derive(Clone)]
+struct SnsCertificate {
+    sha1: PublicKey<RsaPkcs1v15Sha1>,
+    sha256: PublicKey<RsaPkcs1v15Sha256>,
+}
+
+impl SnsWebhook {
+    /// Instantiate with the HTTP client used to fetch the signing certificates
+    pub fn new(fetcher: impl KeyFetcher + 'static) -> Self {
+        Self {
+            fetcher: Box::new(fetcher),
+            topic_arns: None,
+            cert_hosts: DEFAULT_CERT_HOSTS.map(str::to_owned).to_vec(),
+            certificates: RwLock::default(),
+            fetch_locks: Mutex::default(),
+        }
+    }
+
+    /// Only accept messages from the topics with the given ARNs
+    pub fn with_topics(mut self, topic_arns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.topic_arns = Some(topic_arns.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set the hosts that certificates can be fetched from, replacing the default
+    /// `sns.*.amazonaws.com` and `sns.*.amazonaws.com.cn`. A `*` matches a single part of the
+    /// host name (e.g. the region).
+    pub fn with_cert_hosts(
+        mut self,
+        host_patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.cert_hosts = host_patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether the certificate URL uses HTTPS, one of the allowed hosts, and a `.pem` path
+    fn is_allowed_cert_url(&self, url: &str) -> bool {
+        let Some((host, path)) = url
+            .strip_prefix("https://")
+            .and_then(|url| url.split_once('/'))
+        else {
+            return false;
+        };
+        path.ends_with(".pem")
+            && self
+                .cert_hosts
+                .iter()
+                .any(|pattern| host_matches(pattern, host))
+    }
+
+    /// Get the signing certificate from the cache, or fetch it
+    async fn certificate(&self, url: &str) -> Result<SnsCertificate, WebhookError> {
+        if !self.is_allowed_cert_url(url) {
+            return Err(WebhookError::Signature(format!(
+                "SigningCertURL is not allowed: {url}"
+            )));
+        }
+
+        if let Some(certificate) = self.cached_certificate(url) {
+            return certificate;
+        }
+        let fetch_lock = self
+            .fetch_locks
+            .lock()
+            .expect("lock is not poisoned")
+            .entry(url.to_owned())
+            .or_default()
+            .clone();
+        let _guard = fetch_lock.lock().await;
+        // The certificate may have been fetched by another request while waiting
+        if let Some(certificate) = self.cached_certificate(url) {
+            return certificate;
+        }
+
+        let result = self.fetch_certificate(url).await;
+        let cached = match &result {
+            Ok(certificate) => CachedCertificate::Valid(certificate.clone()),
+            Err(error) => CachedCertificate::Failed {
+                error: error.clone(),
+                expires_at: Instant::now() + FAILED_FETCH_TTL,
+            },
+        };
+        {
+            let mut certificates = self.certificates.write().expect("lock is not poisoned");
+            let now = Instant::now();
+            certificates.retain(|_, cached| {
+                !matches!(cached, CachedCertificate::Failed { expires_at, .. } if *expires_at <= now)
+            });
+            certificates.insert(url.to_owned(), cached);
+        }
+        self.fetch_locks
+            .lock()
+            .expect("lock is not poisoned")
+            .remove(url);
+        result.map_err(WebhookError::Secret)
+    }
+
+    /// The cached certificate (or fetch error) for the URL, unless a failed fetch has expired
+    fn cached_certificate(&self, url: &str) -> Option<Result<SnsCertificate, WebhookError>> {
+        let certificates = self.certificates.read().expect("lock is not poisoned");
+        match certificates.get(url)? {
+            CachedCertificate::Valid(certificate) => Some(Ok(certificate.clone())),
+            CachedCertificate::Failed { error, expires_at } if Instant::now() < *expires_at => {
+                Some(Err(WebhookError::Secret(error.clone())))
+            }
+            CachedCertificate::Failed { .. } => None,
+        }
+    }
+
+    /// Fetch and parse the certificate from the URL
+    async fn fetch_certificate(&self, url: &str) -> Result<SnsCertificate, String> {
+        let cert = self
+            .fetcher
+            .fetch(url)
+            .await
+            .map_err(|e| format!("Failed to fetch SNS certificate from '{url}': {e}"))?;
+        parse_certificate(&cert)
+            .map_err(|e| format!("SNS certificate from '{url}' is invalid: {e}"))
+    }
+}
+
+impl Webhook for SnsWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        mut body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(1024));
+        body.read_to_end(&mut raw_body)
+            .await
+            .map_err(WebhookError::Read)?;
+        let message: SnsMessage =
+            serde_json::from_slice(&raw_body).map_err(WebhookError::Deserialize)?;
+
+        if let Some(topic_arns) = &self.topic_arns
+            && !topic_arns.contains(&message.topic_arn)
+        {
+            return Err(WebhookError::Signature(format!(
+                "Topic is not allowed: {}",
+                message.topic_arn
+            )));
+        }
+
+        self.validate_timestamp(&message.timestamp, time_bounds)?;
+
+        let signature = BASE64_STANDARD
+            .decode(&message.signature)
+            .map_err(|e| WebhookError::Signature(format!("Signature is not valid base64: {e}")))?;
+        let string_to_sign = message.string_to_sign()?;
+        let certificate = self.certificate(&message.signing_cert_url).await?;
+        let verification = match message.signature_version.as_str() {
+            "1" => RsaPkcs1v15Sha1::verify(
+                certificate.sha1.key(),
+                string_to_sign.as_bytes(),
+                &signature,
+            ),
+            "2" => RsaPkcs1v15Sha256::verify(
+                certificate.sha256.key(),
+                string_to_sign.as_bytes(),
+                &signature,
+            ),
+            version => Err(format!("Unsupported SignatureVersion '{version}'")),
+        };
+        verification.map_err(WebhookError::Signature)?;
+
+        Ok(raw_body.into())
+    }
+
+    /// The signed `MessageId` of the message
+    fn delivery_id(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "MessageId")
+    }
+
+    /// The signed `Type` of the message
+    fn event_type(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "Type")
+    }
+
+    /// SNS timestamps are in ISO 8601 format
+    fn validate_timestamp(
+        &self,
+        timestamp: &str,
+        (min, max): (u32, u32),
+    ) -> Result<(), WebhookError> {
+        match parse_rfc3339(timestamp) {
+            Some(t) if t >= min && t <= max => Ok(()),
+            _ => Err(WebhookError::Timestamp(timestamp.into())),
+        }
+    }
+}
+
+/// Type of an SNS message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub enum SnsMessageType {
+    /// A message published to the topic
+    Notification,
+    /// Sent when the subscription is created. It must be confirmed by visiting the
+    /// `SubscribeURL` of the message
+    SubscriptionConfirmation,
+    /// Sent when the subscription is deleted
+    UnsubscribeConfirmation,
+}
+
+/// A message sent by SNS
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "PascalCase")]
+pub struct SnsMessage {
+    /// Type of the message
+    #[serde(rename = "Type")]
+    pub message_type: SnsMessageType,
+    /// Unique ID of the message
+    pub message_id: String,
+    /// ARN of the topic
+    pub topic_arn: String,
+    /// The message that was published (for notifications), or a description of the
+    /// confirmation message
+    pub message: String,
+    /// Subject of the notification, if one was published
+    pub subject: Option<String>,
+    /// Time the message was sent (ISO 8601)
+    pub timestamp: String,
+    /// Version of the signature: `1` (SHA1) or `2` (SHA256)
+    pub signature_version: String,
+    /// Base64 signature of the message
+    pub signature: String,
+    /// URL of the certificate used to sign the message
+    #[serde(rename = "SigningCertURL")]
+    pub signing_cert_url: String,
+    /// Token of the confirmation message
+    pub token: Option<String>,
+    /// URL to visit to confirm the subscription
+    #[serde(rename = "SubscribeURL")]
+    pub subscribe_url: Option<String>,
+    /// URL to visit to unsubscribe from the topic (for notifications)
+    #[serde(rename = "UnsubscribeURL")]
+    pub unsubscribe_url: Option<String>,
+}
+
+impl SnsMessage {
+    /// Build the canonical string that SNS signs for this type of message
+    fn string_to_sign(&self) -> Result<String, WebhookError> {
+        let required = |name: &str, value: &Option<String>| {
+            value.clone().ok_or_else(|| {
+                WebhookError::InvalidEvent(format!("SNS message is missing '{name}'"))
+            })
+        };
+        let message_type = match self.message_type {
+            SnsMessageType::Notification => "Notification",
+            SnsMessageType::SubscriptionConfirmation => "SubscriptionConfirmation",
+            SnsMessageType::UnsubscribeConfirmation => "UnsubscribeConfirmation",
+        };
+
+        let mut fields = vec![
+            ("Message", self.message.clone()),
+            ("MessageId", self.message_id.clone()),
+        ];
+        match self.message_type {
+            SnsMessageType::Notification => {
+                if let Some(subject) = &self.subject {
+                    fields.push(("Subject", subject.clone()));
+                }
+            }
+            SnsMessageType::SubscriptionConfirmation | SnsMessageType::UnsubscribeConfirmation => {
+                fields.push((
+                    "SubscribeURL",
+                    required("SubscribeURL", &self.subscribe_url)?,
+                ));
+            }
+        }
+        fields.push(("Timestamp", self.timestamp.clone()));
+        if self.message_type != SnsMessageType::Notification {
+            fields.push(("Token", required("Token", &self.token)?));
+        }
+        fields.push(("TopicArn", self.topic_arn.clone()));
+        fields.push(("Type", message_type.to_owned()));
+
+        Ok(fields
+            .into_iter()
+            .map(|(name, value)| format!("{name}\n{value}\n"))
+            .collect())
+    }
+}
+
+/// Parse the public key of a PEM or DER certificate
+fn parse_certificate(cert: &[u8]) -> Result<SnsCertificate, String> {
+    let certificate = Certificate::from_pem(cert)
+        .or_else(|_| Certificate::from_der(cert))
+        .map_err(|e| e.to_string())?;
+    let public_key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| e.to_string())?;
+    Ok(SnsCertificate {
+        sha1: PublicKey::parse(&public_key).map_err(|e| e.to_string())?,
+        sha256: PublicKey::parse(&public_key).map_err(|e| e.to_string())?,
+    })
+}
+
+/// Whether the host matches the pattern, where `*` matches a single label
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let (pattern, host): (Vec<_>, Vec<_>) =
+        (pattern.split('.').collect(), host.split('.').collect());
+    pattern.len() == host.len()
+        && pattern.iter().zip(&host).all(|(pattern, label)| {
+            !label.is_empty()
+                && (*pattern == "*" && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    || pattern.eq_ignore_ascii_case(label))
+        })
+}
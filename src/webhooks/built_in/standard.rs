@@ -1,12 +1,22 @@
 use base64::{Engine, prelude::BASE64_STANDARD};
 use hmac::Hmac;
-use rocket::{Request, data::Outcome, http::Status, outcome::try_outcome, tokio::io::AsyncRead};
+use rocket::tokio::io::{AsyncRead, AsyncReadExt};
 use sha2::Sha256;
+use tokio_util::bytes::{Bytes, BytesMut};
 use zeroize::Zeroizing;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, interface::hmac::WebhookHmac},
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::{
+            hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+            public_key::{
+                InvalidPublicKey, PublicKey, WebhookPublicKey, algorithms::ed25519::Ed25519,
+            },
+        },
+        utils::{body_size, json_str_field},
+    },
 };
 
 const ID_HEADER: &str = "id";
@@ -19,13 +29,16 @@ const SIG_HEADER: &str = "signature";
 /// Looks for headers `webhook-id`, `webhook-timestamp`, `webhook-signature`. (`webhook-` prefix can
 /// be configured)
 ///
-/// Signature is base64 HMAC of `<id>.<timestamp>.<body>`
+/// Signature is base64 HMAC (`v1`) or Ed25519 signature (`v1a`) of `<id>.<timestamp>.<body>`. Use
+/// [StandardWebhook::with_public_key] for Ed25519 signatures, or [StandardWebhook::accept_public_key]
+/// to accept either while migrating from HMAC signatures.
 ///
 /// ## Links
 /// - [Standard Webhooks spec](https://github.com/standard-webhooks/standard-webhooks/blob/main/spec/standard-webhooks.md)
 /// - [Svix docs](https://docs.svix.com/receiving/verifying-payloads/how-manual)
 pub struct StandardWebhook {
-    secret_key: Zeroizing<Vec<u8>>,
+    secret_keys: Option<HmacSecrets>,
+    public_key: Option<PublicKey<Ed25519>>,
     id_header: String,
     time_header: String,
     sig_header: String,
@@ -35,17 +48,7 @@ impl StandardWebhook {
     /// Instantiate using the secret key starting with `whsec_`. Assumes headers have a prefix
     /// of `webhook-`.
     pub fn with_secret(secret_key: impl AsRef<str>) -> Result<Self, base64::DecodeError> {
-        let stripped_key = secret_key
-            .as_ref()
-            .strip_prefix("whsec_")
-            .unwrap_or(secret_key.as_ref());
-        let secret_key = Zeroizing::new(BASE64_STANDARD.decode(stripped_key)?);
-        Ok(Self {
-            secret_key,
-            id_header: format!("webhook-{ID_HEADER}"),
-            sig_header: format!("webhook-{SIG_HEADER}"),
-            time_header: format!("webhook-{TIMESTAMP_HEADER}"),
-        })
+        Self::with_secrets_and_prefix([HmacSecret::new(secret_key.as_ref())], "webhook-")
     }
 
     /// Instantiate using the secret key starting with `whsec_` and a header prefix (include the
@@ -54,70 +57,276 @@ impl StandardWebhook {
         secret_key: impl AsRef<str>,
         header_prefix: impl AsRef<str>,
     ) -> Result<Self, base64::DecodeError> {
-        let stripped_key = secret_key
-            .as_ref()
-            .strip_prefix("whsec_")
-            .unwrap_or(secret_key.as_ref());
-        let secret_key = Zeroizing::new(BASE64_STANDARD.decode(stripped_key)?);
-        Ok(Self {
-            secret_key,
+        Self::with_secrets_and_prefix([HmacSecret::new(secret_key.as_ref())], header_prefix)
+    }
+
+    /// Instantiate using multiple secret keys starting with `whsec_`, in order of preference (e.g. the
+    /// new and old secrets while rotating). Assumes headers have a prefix of `webhook-`.
+    pub fn with_secrets(
+        secret_keys: impl IntoIterator<Item = HmacSecret>,
+    ) -> Result<Self, base64::DecodeError> {
+        Self::with_secrets_and_prefix(secret_keys, "webhook-")
+    }
+
+    /// Instantiate using multiple secret keys starting with `whsec_`, in order of preference, and a
+    /// header prefix (include the dash when providing the header prefix, e.g. `svix-`).
+    pub fn with_secrets_and_prefix(
+        secret_keys: impl IntoIterator<Item = HmacSecret>,
+        header_prefix: impl AsRef<str>,
+    ) -> Result<Self, base64::DecodeError> {
+        let secret_keys = secret_keys
+            .into_iter()
+            .map(Self::decode_secret)
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(
+            Some(HmacSecrets::Static(secret_keys)),
+            None,
+            header_prefix,
+        ))
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request. Assumes
+    /// headers have a prefix of `webhook-`.
+    ///
+    /// Resolved secret keys must already be decoded (see [StandardWebhook::decode_secret]).
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
+        Self::with_resolver_and_prefix(resolver, "webhook-")
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request, and a header
+    /// prefix (include the dash when providing the header prefix, e.g. `svix-`).
+    ///
+    /// Resolved secret keys must already be decoded (see [StandardWebhook::decode_secret]).
+    pub fn with_resolver_and_prefix(
+        resolver: impl SecretResolver + 'static,
+        header_prefix: impl AsRef<str>,
+    ) -> Self {
+        Self::new(
+            Some(HmacSecrets::Resolver(Box::new(resolver))),
+            None,
+            header_prefix,
+        )
+    }
+
+    /// Instantiate using the public key starting with `whpk_`, to verify asymmetric (`v1a`)
+    /// signatures. Assumes headers have a prefix of `webhook-`.
+    pub fn with_public_key(public_key: impl AsRef<str>) -> Result<Self, InvalidPublicKey> {
+        Self::with_public_key_and_prefix(public_key, "webhook-")
+    }
+
+    /// Instantiate using the public key starting with `whpk_` and a header prefix (include the
+    /// dash when providing the header prefix, e.g. `svix-`).
+    pub fn with_public_key_and_prefix(
+        public_key: impl AsRef<str>,
+        header_prefix: impl AsRef<str>,
+    ) -> Result<Self, InvalidPublicKey> {
+        let public_key = Self::decode_public_key(public_key.as_ref())?;
+        Ok(Self::new(None, Some(public_key), header_prefix))
+    }
+
+    /// Also accept asymmetric (`v1a`) signatures using the public key starting with `whpk_`, e.g.
+    /// while migrating from HMAC signatures. A request is valid if any of its signatures match.
+    pub fn accept_public_key(
+        mut self,
+        public_key: impl AsRef<str>,
+    ) -> Result<Self, InvalidPublicKey> {
+        self.public_key = Some(Self::decode_public_key(public_key.as_ref())?);
+        Ok(self)
+    }
+
+    /// Decode a secret key starting with `whsec_` to its raw bytes, keeping the expiration time
+    pub fn decode_secret(secret: HmacSecret) -> Result<HmacSecret, base64::DecodeError> {
+        let stripped_key = secret.key.strip_prefix(b"whsec_").unwrap_or(&secret.key);
+        Ok(HmacSecret {
+            key: Zeroizing::new(BASE64_STANDARD.decode(stripped_key)?),
+            expires_at: secret.expires_at,
+        })
+    }
+
+    fn decode_public_key(public_key: &str) -> Result<PublicKey<Ed25519>, InvalidPublicKey> {
+        let stripped_key = public_key.strip_prefix("whpk_").unwrap_or(public_key);
+        PublicKey::from_base64(stripped_key)
+    }
+
+    fn new(
+        secret_keys: Option<HmacSecrets>,
+        public_key: Option<PublicKey<Ed25519>>,
+        header_prefix: impl AsRef<str>,
+    ) -> Self {
+        Self {
+            secret_keys,
+            public_key,
             id_header: format!("{}{ID_HEADER}", header_prefix.as_ref()),
             sig_header: format!("{}{SIG_HEADER}", header_prefix.as_ref()),
             time_header: format!("{}{TIMESTAMP_HEADER}", header_prefix.as_ref()),
-        })
+        }
+    }
+
+    /// Read the body once, and verify with the public key if any `v1a` signatures were provided,
+    /// falling back to the HMAC secret keys
+    async fn validate_with_either(
+        &self,
+        req: &dyn WebhookRequest,
+        mut body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(512));
+        body.read_to_end(&mut raw_body)
+            .await
+            .map_err(WebhookError::Read)?;
+
+        let header = self.get_header(req, &self.sig_header, None)?;
+        let has_version = |version: &str| header.split(' ').any(|sig| sig.starts_with(version));
+        if has_version("v1a,") {
+            let validation = self
+                .validate_with_public_key(req, raw_body.as_slice(), time_bounds)
+                .await;
+            if validation.is_ok() || !has_version("v1,") {
+                return validation;
+            }
+        }
+        self.validate_with_hmac(req, raw_body.as_slice(), time_bounds)
+            .await
+    }
+
+    /// The `<id>.<timestamp>.` prefix of the signed content
+    fn signed_prefix(
+        &self,
+        req: &dyn WebhookRequest,
+        time_bounds: (u32, u32),
+    ) -> Result<Vec<u8>, WebhookError> {
+        let id = self.get_header(req, &self.id_header, None)?;
+        let timestamp = self.get_header(req, &self.time_header, None)?;
+        self.validate_timestamp(timestamp, time_bounds)?;
+
+        Ok([id.as_bytes(), b".", timestamp.as_bytes(), b"."].concat())
+    }
+
+    /// Multiple space delimited signatures in header, prefixed by the signature version (e.g. `v1,`)
+    fn signatures(
+        &self,
+        req: &dyn WebhookRequest,
+        version: &str,
+    ) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let header = self.get_header(req, &self.sig_header, None)?;
+        let mut signatures = Vec::new();
+        for base64_sig in header.split(' ').filter_map(|s| s.strip_prefix(version)) {
+            match BASE64_STANDARD.decode(base64_sig) {
+                Ok(bytes) => signatures.push(bytes),
+                Err(_) => {
+                    return Err(WebhookError::InvalidHeader(format!(
+                        "Signature in '{}' header was not valid base64: got '{base64_sig}'",
+                        self.sig_header
+                    )));
+                }
+            }
+        }
+
+        Ok(signatures)
     }
 }
 
 impl Webhook for StandardWebhook {
     async fn validate_body(
         &self,
-        req: &Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
-        self.validate_with_hmac(req, body, time_bounds).await
+    ) -> Result<ValidatedBody, WebhookError> {
+        match (&self.secret_keys, &self.public_key) {
+            (Some(_), Some(_)) => self.validate_with_either(req, body, time_bounds).await,
+            (None, Some(_)) => self.validate_with_public_key(req, body, time_bounds).await,
+            _ => self.validate_with_hmac(req, body, time_bounds).await,
+        }
+    }
+
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header(&self.id_header).map(str::to_owned)
+    }
+
+    /// The `type` field of the event
+    fn event_type(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "type")
     }
 }
 
+/// Used when the webhook only has a public key
+static NO_SECRETS: HmacSecrets = HmacSecrets::Static(Vec::new());
+
 impl WebhookHmac for StandardWebhook {
     type MAC = Hmac<Sha256>;
 
-    fn secret_key(&self) -> &[u8] {
-        &self.secret_key
+    fn secret_keys(&self) -> &HmacSecrets {
+        self.secret_keys.as_ref().unwrap_or(&NO_SECRETS)
     }
 
     fn body_prefix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        let id = try_outcome!(self.get_header(req, &self.id_header, None));
-        let timestamp = try_outcome!(self.get_header(req, &self.time_header, None));
-        try_outcome!(self.validate_timestamp(timestamp, time_bounds));
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        self.signed_prefix(req, time_bounds).map(Some)
+    }
 
-        let prefix = [id.as_bytes(), b".", timestamp.as_bytes(), b"."].concat();
-        Outcome::Success(Some(prefix))
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        self.signatures(req, "v1,")
     }
+}
 
-    /// Multiple space delimited signatures in header, prefixed by `v1,`
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError> {
-        let header = try_outcome!(self.get_header(req, &self.sig_header, None));
-        let mut signatures = Vec::new();
-        for base64_sig in header.split(' ').filter_map(|s| s.strip_prefix("v1,")) {
-            match BASE64_STANDARD.decode(base64_sig) {
-                Ok(bytes) => signatures.push(bytes),
-                Err(_) => {
-                    return Outcome::Error((
-                        Status::BadRequest,
-                        WebhookError::InvalidHeader(format!(
-                            "Signature in '{}' header was not valid base64: got '{base64_sig}'",
-                            self.sig_header
-                        )),
-                    ));
-                }
-            }
-        }
+impl WebhookPublicKey for StandardWebhook {
+    type ALG = Ed25519;
+
+    async fn public_key(
+        &self,
+        _req: &dyn WebhookRequest,
+    ) -> Result<PublicKey<Ed25519>, WebhookError> {
+        self.public_key
+            .clone()
+            .ok_or_else(|| WebhookError::Signature("No public key configured".into()))
+    }
+
+    fn expected_signature(&self, req: &dyn WebhookRequest) -> Result<Vec<u8>, WebhookError> {
+        self.signatures(req, "v1a,")?
+            .into_iter()
+            .next()
+            .ok_or_else(|| WebhookError::Signature("No v1a signatures provided".into()))
+    }
 
-        Outcome::Success(signatures)
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        self.signatures(req, "v1a,")
+    }
+
+    fn message_to_verify(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &Bytes,
+        time_bounds: (u32, u32),
+    ) -> Result<Bytes, WebhookError> {
+        let prefix = self.signed_prefix(req, time_bounds)?;
+
+        let mut message = BytesMut::with_capacity(prefix.len() + body.len());
+        message.extend_from_slice(&prefix);
+        message.extend_from_slice(body);
+
+        Ok(message.freeze())
+    }
+}
+
+/// Signs with the HMAC secret key (`v1`), so the webhook must have a secret key
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for StandardWebhook {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        let id = crate::testing::test_delivery_id();
+        let timestamp = timestamp.to_string();
+        let prefix = [id.as_bytes(), b".", timestamp.as_bytes(), b"."].concat();
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[&prefix, body]);
+        vec![
+            (self.id_header.clone(), id),
+            (self.time_header.clone(), timestamp),
+            (
+                self.sig_header.clone(),
+                format!("v1,{}", BASE64_STANDARD.encode(signature)),
+            ),
+        ]
     }
 }
@@ -1,11 +1,14 @@
 use hmac::Hmac;
-use rocket::{Request, data::Outcome, http::Status, outcome::try_outcome, tokio::io::AsyncRead};
+use rocket::tokio::io::AsyncRead;
 use sha2::Sha256;
-use zeroize::Zeroizing;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, interface::hmac::WebhookHmac},
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+        utils::{json_str_field, signed_content_id},
+    },
 };
 
 /// # Stripe webhook
@@ -16,14 +19,27 @@ use crate::{
 ///
 /// [Stripe docs](https://docs.stripe.com/webhooks?verify=verify-manually#verify-manually)
 pub struct StripeWebhook {
-    secret_key: Zeroizing<Vec<u8>>,
+    secret_keys: HmacSecrets,
 }
 
 impl StripeWebhook {
     /// Instantiate with the secret key
     pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple secret keys in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
         Self {
-            secret_key: Zeroizing::new(secret_key.into()),
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
         }
     }
 }
@@ -33,61 +49,83 @@ const SIG_HEADER: &str = "Stripe-Signature";
 impl Webhook for StripeWebhook {
     async fn validate_body(
         &self,
-        req: &Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_hmac(req, body, time_bounds).await
     }
+
+    /// Stripe doesn't send a delivery ID, so the signed timestamp and a digest of the body are used
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        let timestamp = header_timestamp(req.header(SIG_HEADER)?)?;
+        Some(signed_content_id(timestamp, body))
+    }
+
+    /// The `type` field of the event
+    fn event_type(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "type")
+    }
 }
 
 impl WebhookHmac for StripeWebhook {
     type MAC = Hmac<Sha256>;
 
-    fn secret_key(&self) -> &[u8] {
-        &self.secret_key
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
     }
 
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError> {
-        let header = try_outcome!(self.get_header(req, SIG_HEADER, None));
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let header = self.get_header(req, SIG_HEADER, None)?;
         let mut signatures = Vec::new();
         for hex_sig in header.split(',').filter_map(|s| s.strip_prefix("v1=")) {
             match hex::decode(hex_sig) {
                 Ok(bytes) => signatures.push(bytes),
                 Err(_) => {
-                    return Outcome::Error((
-                        Status::BadRequest,
-                        WebhookError::InvalidHeader(format!(
-                            "Signature in {SIG_HEADER} header was not valid hex: '{hex_sig}'"
-                        )),
-                    ));
+                    return Err(WebhookError::InvalidHeader(format!(
+                        "Signature in {SIG_HEADER} header was not valid hex: '{hex_sig}'"
+                    )));
                 }
             };
         }
 
-        Outcome::Success(signatures)
+        Ok(signatures)
     }
 
     fn body_prefix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        let sig_header = try_outcome!(self.get_header(req, SIG_HEADER, None));
-        let Some(timestamp) = sig_header
-            .split(',')
-            .find_map(|part| part.strip_prefix("t="))
-        else {
-            return Outcome::Error((
-                Status::BadRequest,
-                WebhookError::InvalidHeader(format!(
-                    "Did not find timestamp in header: '{sig_header}'"
-                )),
-            ));
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, SIG_HEADER, None)?;
+        let Some(timestamp) = header_timestamp(sig_header) else {
+            return Err(WebhookError::InvalidHeader(format!(
+                "Did not find timestamp in header: '{sig_header}'"
+            )));
         };
-        try_outcome!(self.validate_timestamp(timestamp, time_bounds));
+        self.validate_timestamp(timestamp, time_bounds)?;
+
+        let prefix = [timestamp.as_bytes(), b"."].concat();
+        Ok(Some(prefix))
+    }
+}
+
+/// Get the `t=<timestamp>` part of the signature header
+fn header_timestamp(sig_header: &str) -> Option<&str> {
+    sig_header
+        .split(',')
+        .find_map(|part| part.strip_prefix("t="))
+}
 
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for StripeWebhook {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        let timestamp = timestamp.to_string();
         let prefix = [timestamp.as_bytes(), b"."].concat();
-        Outcome::Success(Some(prefix))
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[&prefix, body]);
+        vec![(
+            SIG_HEADER.into(),
+            format!("t={timestamp},v1={}", hex::encode(signature)),
+        )]
     }
 }
@@ -0,0 +1,173 @@
+use hmac::Hmac;
+use rocket::{
+    serde::json::{Value, serde_json},
+    tokio::io::AsyncRead,
+};
+use sha2::Sha256;
+
+use crate::{
+    WebhookError, WebhookRequest,
+    challenge::ChallengeResponse,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+        utils::parse_rfc3339,
+    },
+};
+
+const ID_HEADER: &str = "Twitch-Eventsub-Message-Id";
+const TIMESTAMP_HEADER: &str = "Twitch-Eventsub-Message-Timestamp";
+const SIG_HEADER: &str = "Twitch-Eventsub-Message-Signature";
+
+/// # Twitch EventSub webhook
+/// Looks for hex signature in `Twitch-Eventsub-Message-Signature` header with a `sha256=` prefix,
+/// along with the `Twitch-Eventsub-Message-Id` and `Twitch-Eventsub-Message-Timestamp` (RFC 3339)
+/// headers.
+///
+/// Signature is HMAC of `{id}{timestamp}{body}`
+///
+/// [Twitch docs](https://dev.twitch.tv/docs/eventsub/handling-webhook-events/#verifying-the-event-message)
+pub struct TwitchWebhook {
+    secret_keys: HmacSecrets,
+}
+
+impl TwitchWebhook {
+    /// Instantiate with the secret key
+    pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple secret keys in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the secret key(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
+        }
+    }
+}
+
+impl Webhook for TwitchWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        self.validate_with_hmac(req, body, time_bounds).await
+    }
+
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header(ID_HEADER).map(str::to_owned)
+    }
+
+    fn event_type(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        req.header("Twitch-Eventsub-Subscription-Type")
+            .map(str::to_owned)
+    }
+
+    /// Answers the `webhook_callback_verification` message with the challenge
+    async fn challenge_response(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &ValidatedBody,
+    ) -> Option<ChallengeResponse> {
+        if req.header("Twitch-Eventsub-Message-Type") != Some("webhook_callback_verification") {
+            return None;
+        }
+        let message: Value = serde_json::from_slice(&body.data).ok()?;
+        message["challenge"].as_str().map(ChallengeResponse::text)
+    }
+
+    /// Twitch timestamps are in RFC 3339 format
+    fn validate_timestamp(
+        &self,
+        timestamp: &str,
+        (min, max): (u32, u32),
+    ) -> Result<(), WebhookError> {
+        match parse_rfc3339(timestamp) {
+            Some(t) if t >= min && t <= max => Ok(()),
+            _ => Err(WebhookError::Timestamp(timestamp.into())),
+        }
+    }
+}
+
+impl WebhookHmac for TwitchWebhook {
+    type MAC = Hmac<Sha256>;
+
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
+    }
+
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, SIG_HEADER, Some("sha256="))?;
+        match hex::decode(sig_header) {
+            Ok(bytes) => Ok(vec![bytes]),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "{SIG_HEADER} header was not valid hex: '{sig_header}'"
+            ))),
+        }
+    }
+
+    fn body_prefix(
+        &self,
+        req: &dyn WebhookRequest,
+        time_bounds: (u32, u32),
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        let id = self.get_header(req, ID_HEADER, None)?;
+        let timestamp = self.get_header(req, TIMESTAMP_HEADER, None)?;
+        self.validate_timestamp(timestamp, time_bounds)?;
+
+        let prefix = [id.as_bytes(), timestamp.as_bytes()].concat();
+        Ok(Some(prefix))
+    }
+}
+
+/// Format Unix epoch seconds as an RFC 3339 timestamp in UTC
+#[cfg(feature = "testing")]
+fn format_rfc3339(unix_secs: u32) -> String {
+    // Civil date from days since the Unix epoch (http://howardhinnant.github.io/date_algorithms.html#civil_from_days)
+    let days = i64::from(unix_secs / 86400) + 719468;
+    let secs_of_day = unix_secs % 86400;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for TwitchWebhook {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        let id = crate::testing::test_delivery_id();
+        let timestamp = format_rfc3339(timestamp);
+        let prefix = [id.as_bytes(), timestamp.as_bytes()].concat();
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[&prefix, body]);
+        vec![
+            (ID_HEADER.into(), id),
+            (TIMESTAMP_HEADER.into(), timestamp),
+            (
+                SIG_HEADER.into(),
+                format!("sha256={}", hex::encode(signature)),
+            ),
+            ("Twitch-Eventsub-Message-Type".into(), "notification".into()),
+        ]
+    }
+}
@@ -0,0 +1,143 @@
+use hmac::{Hmac, Mac};
+use rocket::{
+    serde::json::{Value, json, serde_json},
+    tokio::io::AsyncRead,
+};
+use sha2::Sha256;
+
+use crate::{
+    WebhookError, WebhookRequest,
+    challenge::ChallengeResponse,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+        utils::{json_str_field, signed_content_id},
+    },
+};
+
+/// # Zoom webhook
+/// Looks for hex signature in `x-zm-signature` header with a `v0=` prefix, and a timestamp in the
+/// `x-zm-request-timestamp` header.
+///
+/// Signature is HMAC of `v0:{timestamp}:{body}`, using the secret token of the Zoom app
+///
+/// [Zoom docs](https://developers.zoom.us/docs/api/webhooks/#verify-webhook-events)
+pub struct ZoomWebhook {
+    secret_keys: HmacSecrets,
+}
+
+impl ZoomWebhook {
+    /// Instantiate with the secret token
+    pub fn with_secret(secret_key: impl Into<Vec<u8>>) -> Self {
+        Self::with_secrets([HmacSecret::new(secret_key)])
+    }
+
+    /// Instantiate with multiple secret tokens in order of preference, e.g. the new and old
+    /// secrets while rotating
+    pub fn with_secrets(secret_keys: impl IntoIterator<Item = HmacSecret>) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Static(secret_keys.into_iter().collect()),
+        }
+    }
+
+    /// Instantiate with a resolver that gets the secret token(s) for each request
+    pub fn with_resolver(resolver: impl SecretResolver + 'static) -> Self {
+        Self {
+            secret_keys: HmacSecrets::Resolver(Box::new(resolver)),
+        }
+    }
+}
+
+impl Webhook for ZoomWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        self.validate_with_hmac(req, body, time_bounds).await
+    }
+
+    /// Zoom doesn't send a delivery ID, so the signed timestamp and a digest of the body are used
+    fn delivery_id(&self, req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        let timestamp = req.header("x-zm-request-timestamp")?;
+        Some(signed_content_id(timestamp, body))
+    }
+
+    /// The `event` field of the event
+    fn event_type(&self, _req: &dyn WebhookRequest, body: &[u8]) -> Option<String> {
+        json_str_field(body, "event")
+    }
+
+    /// Answers the `endpoint.url_validation` event with the plain token and its HMAC, using
+    /// the secret token that validated the signature
+    async fn challenge_response(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &ValidatedBody,
+    ) -> Option<ChallengeResponse> {
+        let event: Value = serde_json::from_slice(&body.data).ok()?;
+        if event["event"] != "endpoint.url_validation" {
+            return None;
+        }
+        let plain_token = event["payload"]["plainToken"].as_str()?;
+
+        let secret_keys = self.secret_keys.resolve(req).await.ok()?;
+        let secret = secret_keys.get(body.secret_index?)?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.key())
+            .expect("HMAC should take any key length");
+        mac.update(plain_token.as_bytes());
+        let encrypted_token = hex::encode(mac.finalize().into_bytes());
+
+        Some(ChallengeResponse::json(&json!({
+            "plainToken": plain_token,
+            "encryptedToken": encrypted_token,
+        })))
+    }
+}
+
+impl WebhookHmac for ZoomWebhook {
+    type MAC = Hmac<Sha256>;
+
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secret_keys
+    }
+
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        let sig_header = self.get_header(req, "x-zm-signature", Some("v0="))?;
+        match hex::decode(sig_header) {
+            Ok(bytes) => Ok(vec![bytes]),
+            Err(_) => Err(WebhookError::InvalidHeader(format!(
+                "x-zm-signature header was not valid hex: '{sig_header}'"
+            ))),
+        }
+    }
+
+    fn body_prefix(
+        &self,
+        req: &dyn WebhookRequest,
+        time_bounds: (u32, u32),
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        let timestamp = self.get_header(req, "x-zm-request-timestamp", None)?;
+        self.validate_timestamp(timestamp, time_bounds)?;
+
+        let prefix = [b"v0:", timestamp.as_bytes(), b":"].concat();
+        Ok(Some(prefix))
+    }
+}
+
+#[cfg(feature = "testing")]
+impl crate::testing::TestSigner for ZoomWebhook {
+    fn signed_headers(&self, body: &[u8], timestamp: u32) -> Vec<(String, String)> {
+        let timestamp = timestamp.to_string();
+        let prefix = [b"v0:", timestamp.as_bytes(), b":"].concat();
+        let signature = crate::testing::hmac_sha256(self.secret_keys(), &[&prefix, body]);
+        vec![
+            (
+                "x-zm-signature".into(),
+                format!("v0={}", hex::encode(signature)),
+            ),
+            ("x-zm-request-timestamp".into(), timestamp),
+        ]
+    }
+}
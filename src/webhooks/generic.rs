@@ -2,5 +2,32 @@
 
 #[cfg(feature = "hmac")]
 mod hmac;
+#[cfg(feature = "jwt")]
+mod jwt;
+#[cfg(feature = "public-key")]
+mod public_key;
+#[cfg(feature = "token")]
+mod token;
+
+#[cfg(feature = "sha1")]
+pub use hmac::HmacSha1Webhook;
 #[cfg(feature = "hmac")]
-pub use hmac::Hmac256Webhook;
+pub use hmac::{Hmac256Webhook, Hmac384Webhook, Hmac512Webhook, HmacDigest, HmacWebhook};
+#[cfg(feature = "jwt")]
+pub use jwt::JwtWebhook;
+#[cfg(all(feature = "p256", feature = "public-key"))]
+pub use public_key::EcdsaP256Webhook;
+#[cfg(all(feature = "ed25519", feature = "public-key"))]
+pub use public_key::Ed25519Webhook;
+#[cfg(feature = "public-key")]
+pub use public_key::PublicKeyWebhook;
+#[cfg(feature = "token")]
+pub use token::TokenWebhook;
+
+/// Function to get the expected, decoded signature(s) from the request
+#[cfg(any(feature = "hmac", feature = "public-key"))]
+type SignaturesFn = Box<dyn Fn(&dyn crate::WebhookRequest) -> Option<Vec<Vec<u8>>> + Send + Sync>;
+
+/// Function to get a unique ID for the delivery from the request
+#[cfg(any(feature = "hmac", feature = "public-key", feature = "token"))]
+type DeliveryIdFn = Box<dyn Fn(&dyn crate::WebhookRequest) -> Option<String> + Send + Sync>;
@@ -1,16 +1,64 @@
+use std::marker::PhantomData;
+
 use bon::Builder;
-use hmac::Hmac;
-use rocket::{Request, data::Outcome, http::Status, tokio::io::AsyncRead};
-use sha2::Sha256;
-use zeroize::Zeroizing;
+use hmac::{Hmac, Mac, digest::KeyInit};
+use rocket::tokio::io::AsyncRead;
+use sha2::{Sha256, Sha384, Sha512};
 
+use super::{DeliveryIdFn, SignaturesFn};
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, interface::hmac::WebhookHmac},
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::hmac::{HmacSecret, HmacSecrets, WebhookHmac, resolver::SecretResolver},
+    },
 };
 
+/// Function to get a prefix or suffix for the body, given the request and time bounds
+type BodyFn =
+    Box<dyn Fn(&dyn WebhookRequest, (u32, u32)) -> Result<Vec<u8>, WebhookError> + Send + Sync>;
+
+/// Hash algorithm used by the generic [HmacWebhook] builder. This is implemented for SHA-256,
+/// SHA-384 and SHA-512, and SHA-1 with the `sha1` feature.
+pub trait HmacDigest {
+    /// MAC algorithm (from the `hmac` crate) using this digest
+    type MAC: Mac + KeyInit + Send + Sync;
+}
+
+impl HmacDigest for Sha256 {
+    type MAC = Hmac<Sha256>;
+}
+
+impl HmacDigest for Sha384 {
+    type MAC = Hmac<Sha384>;
+}
+
+impl HmacDigest for Sha512 {
+    type MAC = Hmac<Sha512>;
+}
+
+#[cfg(feature = "sha1")]
+impl HmacDigest for sha1::Sha1 {
+    type MAC = Hmac<sha1::Sha1>;
+}
+
+/// A custom webhook using HMAC SHA256 verification. See [HmacWebhook] for details.
+pub type Hmac256Webhook = HmacWebhook<Sha256>;
+/// A custom webhook using HMAC SHA384 verification. See [HmacWebhook] for details.
+pub type Hmac384Webhook = HmacWebhook<Sha384>;
+/// A custom webhook using HMAC SHA512 verification. See [HmacWebhook] for details.
+pub type Hmac512Webhook = HmacWebhook<Sha512>;
+/// A custom webhook using HMAC SHA1 verification, for older providers. See [HmacWebhook] for details.
+#[cfg(feature = "sha1")]
+pub type HmacSha1Webhook = HmacWebhook<sha1::Sha1>;
+
 /**
-A custom webhook builder using HMAC SHA256 verification of the request body.
+A custom webhook builder using HMAC verification of the request body, with the hash
+algorithm `D` (SHA256 by default). Use one of the aliases to select the algorithm, e.g.
+[Hmac256Webhook] or [Hmac512Webhook].
+
+The functions used to extract the signature and build the signed message can be closures that
+capture configuration, such as a header name loaded at runtime.
 
 # Example
 This sets up a webhook that expects a hex-encoded signature in the `Signature-SHA256` header, and a
@@ -23,96 +71,174 @@ use rocket_webhook::{WebhookError, webhooks::generic::Hmac256Webhook};
 let my_webhook = Hmac256Webhook::builder()
     .secret("my-secret")
     .expected_signatures(|req| {
-        req.headers()
-            .get_one("Signature-SHA256")
+        req.header("Signature-SHA256")
             .and_then(|header| hex::decode(header).ok())
             .map(|header| vec![header])
     })
     .body_suffix(|req, (min_time, max_time)| {
-        req.headers()
-            .get_one("Timestamp")
+        req.header("Timestamp")
             .filter(|time| time.parse::<u32>().is_ok_and(|t| t > min_time && t < max_time))
             .map(|time| time.as_bytes().to_vec())
             .ok_or_else(|| WebhookError::Timestamp("Missing/invalid Timestamp header".into()))
     })
     .build();
 ```
+
+A webhook that expects a hex-encoded HMAC SHA1 signature in the `X-Hub-Signature` header, like
+GitHub's legacy signature (requires the `sha1` feature):
+```
+# #[cfg(feature = "sha1")] {
+use rocket_webhook::webhooks::generic::HmacSha1Webhook;
+
+let my_webhook = HmacSha1Webhook::builder()
+    .secret("my-secret")
+    .expected_signatures(|req| {
+        req.header("X-Hub-Signature")
+            .and_then(|header| header.strip_prefix("sha1="))
+            .and_then(|header| hex::decode(header).ok())
+            .map(|header| vec![header])
+    })
+    .build();
+# }
+```
+
+A webhook configured at runtime, with the signature header name captured by the closure:
+```
+use rocket_webhook::webhooks::generic::Hmac256Webhook;
+
+fn tenant_webhook(secret: String, signature_header: String) -> Hmac256Webhook {
+    Hmac256Webhook::builder()
+        .secret(secret)
+        .expected_signatures(move |req| {
+            req.header(&signature_header)
+                .and_then(|header| hex::decode(header).ok())
+                .map(|header| vec![header])
+        })
+        .build()
+}
+```
 */
 #[derive(Builder)]
-pub struct Hmac256Webhook {
-    /// The secret used to sign the webhook. If the key is encoded in hex or base64, etc., it
-    /// must be decoded to bytes first
-    #[builder(with = |secret: impl Into<Vec<u8>>| Zeroizing::new(secret.into()))]
-    secret: Zeroizing<Vec<u8>>,
+pub struct HmacWebhook<D: HmacDigest = Sha256> {
+    /// The secret(s) used to sign the webhook. Set using `.secret()`, `.secrets()` or `.resolver()`.
+    #[builder(setters(name = secrets_internal, vis = ""))]
+    secrets: HmacSecrets,
     /// Function to get the expected, decoded signature(s) from the request (typically
     /// derived from one of the request headers).
     /// If `None` is returned, signature is presumed to be missing or invalid.
-    expected_signatures: fn(req: &Request<'_>) -> Option<Vec<Vec<u8>>>,
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<Vec<Vec<u8>>> + Send + Sync + 'static| Box::new(f) as SignaturesFn)]
+    expected_signatures: SignaturesFn,
     /// Function to get the prefix to attach to the body when calculating the signature. For replay
     /// prevention, any timestamp should be validated against the given time bounds (in Unix epoch seconds).
-    body_prefix:
-        Option<fn(req: &Request<'_>, time_bounds: (u32, u32)) -> Result<Vec<u8>, WebhookError>>,
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest, (u32, u32)) -> Result<Vec<u8>, WebhookError> + Send + Sync + 'static| Box::new(f) as BodyFn)]
+    body_prefix: Option<BodyFn>,
     /// Function to get the suffix to attach to the body when calculating the signature. For replay
     /// prevention, any timestamp should be validated against the given time bounds (in Unix epoch seconds).
-    body_suffix:
-        Option<fn(req: &Request<'_>, time_bounds: (u32, u32)) -> Result<Vec<u8>, WebhookError>>,
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest, (u32, u32)) -> Result<Vec<u8>, WebhookError> + Send + Sync + 'static| Box::new(f) as BodyFn)]
+    body_suffix: Option<BodyFn>,
+    /// Function to get a unique ID for the delivery from the request, used to reject duplicate
+    /// deliveries when a [ReplayStore](crate::replay::ReplayStore) is configured.
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<String> + Send + Sync + 'static| Box::new(f) as DeliveryIdFn)]
+    delivery_id: Option<DeliveryIdFn>,
+    #[builder(skip)]
+    _digest: PhantomData<fn() -> D>,
+}
+
+impl<D: HmacDigest, S: hmac_webhook_builder::State> HmacWebhookBuilder<D, S> {
+    /// The secret used to sign the webhook. If the key is encoded in hex or base64, etc., it
+    /// must be decoded to bytes first
+    pub fn secret(
+        self,
+        secret: impl Into<Vec<u8>>,
+    ) -> HmacWebhookBuilder<D, hmac_webhook_builder::SetSecrets<S>>
+    where
+        S::Secrets: hmac_webhook_builder::IsUnset,
+    {
+        self.secrets([HmacSecret::new(secret)])
+    }
+
+    /// The secrets used to sign the webhook, in order of preference (e.g. the new and old
+    /// secrets while rotating)
+    pub fn secrets(
+        self,
+        secrets: impl IntoIterator<Item = HmacSecret>,
+    ) -> HmacWebhookBuilder<D, hmac_webhook_builder::SetSecrets<S>>
+    where
+        S::Secrets: hmac_webhook_builder::IsUnset,
+    {
+        self.secrets_internal(HmacSecrets::Static(secrets.into_iter().collect()))
+    }
+
+    /// Resolver that gets the secret key(s) for each request
+    pub fn resolver(
+        self,
+        resolver: impl SecretResolver + 'static,
+    ) -> HmacWebhookBuilder<D, hmac_webhook_builder::SetSecrets<S>>
+    where
+        S::Secrets: hmac_webhook_builder::IsUnset,
+    {
+        self.secrets_internal(HmacSecrets::Resolver(Box::new(resolver)))
+    }
 }
 
-impl Webhook for Hmac256Webhook {
+impl<D: HmacDigest> Webhook for HmacWebhook<D> {
     async fn validate_body(
         &self,
-        req: &Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Vec<u8>, WebhookError> {
+    ) -> Result<ValidatedBody, WebhookError> {
         self.validate_with_hmac(req, body, time_bounds).await
     }
+
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        self.delivery_id.as_ref().and_then(|id_fn| (id_fn)(req))
+    }
 }
 
-impl WebhookHmac for Hmac256Webhook {
-    type MAC = Hmac<Sha256>;
+impl<D: HmacDigest> WebhookHmac for HmacWebhook<D> {
+    type MAC = D::MAC;
 
-    fn secret_key(&self) -> &[u8] {
-        &self.secret
+    fn secret_keys(&self) -> &HmacSecrets {
+        &self.secrets
     }
 
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError> {
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
         match (self.expected_signatures)(req) {
-            Some(signatures) => Outcome::Success(signatures),
-            None => Outcome::Error((
-                Status::BadRequest,
-                WebhookError::Signature("Valid signature(s) not provided in request".into()),
+            Some(signatures) => Ok(signatures),
+            None => Err(WebhookError::Signature(
+                "Valid signature(s) not provided in request".into(),
             )),
         }
     }
 
     fn body_prefix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        if let Some(prefix_fn) = self.body_prefix {
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        if let Some(prefix_fn) = &self.body_prefix {
             match (prefix_fn)(req, time_bounds) {
-                Ok(prefix) => Outcome::Success(Some(prefix)),
-                Err(err) => Outcome::Error((Status::BadRequest, err)),
+                Ok(prefix) => Ok(Some(prefix)),
+                Err(err) => Err(err),
             }
         } else {
-            Outcome::Success(None)
+            Ok(None)
         }
     }
 
     fn body_suffix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        if let Some(suffix_fn) = self.body_suffix {
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        if let Some(suffix_fn) = &self.body_suffix {
             match (suffix_fn)(req, time_bounds) {
-                Ok(suffix) => Outcome::Success(Some(suffix)),
-                Err(err) => Outcome::Error((Status::BadRequest, err)),
+                Ok(suffix) => Ok(Some(suffix)),
+                Err(err) => Err(err),
             }
         } else {
-            Outcome::Success(None)
+            Ok(None)
         }
     }
 }
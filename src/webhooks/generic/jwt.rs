@@ -0,0 +1,198 @@
+use bon::Builder;
+use rocket::tokio::io::AsyncRead;
+
+use super::DeliveryIdFn;
+use crate::{
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::jwt::{JwtClaims, JwtHeader, JwtKey, JwtKeyProvider, WebhookJwt},
+    },
+};
+
+/// Function to validate additional claims of the token
+type ClaimsFn =
+    Box<dyn Fn(&dyn WebhookRequest, &JwtClaims) -> Result<(), WebhookError> + Send + Sync>;
+
+/// Where the keys to verify the token come from
+enum JwtKeys {
+    /// Static keys
+    Static(Vec<JwtKey>),
+    /// Keys resolved from the token header (e.g. fetched from a JWKS URL)
+    Provider(Box<dyn JwtKeyProvider>),
+}
+
+/**
+A custom webhook builder for providers that authenticate deliveries with a signed JSON Web Token
+(JWT), sent in the `Authorization` header by default. The token's signature and expiration are
+verified, along with the issuer and audience if configured. The token can also be bound to the
+body using a claim that contains the SHA-256 hash of the body.
+
+Supports `HS256`, `RS256` (`rsa` feature), `ES256` (`p256` feature) and `EdDSA` (`ed25519`
+feature) tokens. The claims of a validated token can be read using [JwtClaims::from_token].
+
+# Example
+This sets up a webhook that expects an `HS256` token in the `Authorization: Bearer <token>` header,
+with the hex-encoded SHA-256 hash of the body in the `payload_hash` claim:
+
+```
+use rocket_webhook::webhooks::{generic::JwtWebhook, interface::jwt::JwtKey};
+
+let my_webhook = JwtWebhook::builder()
+    .key(JwtKey::hs256("my-signature-secret"))
+    .body_hash_claim("payload_hash")
+    .build();
+```
+
+A webhook that expects an `ES256` token in the `Provider-Verification` header, signed by a key
+from the provider's JWKS URL, with the given issuer and audience:
+
+```
+# #[cfg(all(feature = "jwks-reqwest", feature = "p256"))] {
+use rocket_webhook::webhooks::{
+    generic::JwtWebhook,
+    interface::public_key::{algorithms::p256::EcdsaP256Fixed, remote::RemotePublicKeys},
+};
+
+let keys = RemotePublicKeys::<EcdsaP256Fixed>::builder()
+    .url("https://example.com/.well-known/jwks.json")
+    .fetcher(reqwest::Client::new())
+    .build();
+let my_webhook = JwtWebhook::builder()
+    .header("Provider-Verification")
+    .key_provider(keys)
+    .issuer("https://example.com")
+    .audience("my-app")
+    .build();
+# }
+```
+*/
+#[derive(Builder)]
+pub struct JwtWebhook {
+    /// The header containing the token (with an optional `Bearer` prefix). Defaults
+    /// to `Authorization`.
+    #[builder(into, default = "Authorization".to_owned())]
+    header: String,
+    /// The key(s) used to verify the token. Set using `.key()`, `.keys()` or `.key_provider()`.
+    #[builder(setters(name = keys_internal, vis = ""))]
+    keys: JwtKeys,
+    /// The expected issuer of the token (`iss` claim)
+    #[builder(into)]
+    issuer: Option<String>,
+    /// The expected audience of the token (`aud` claim)
+    #[builder(into)]
+    audience: Option<String>,
+    /// The claim containing the SHA-256 hash of the body (hex or base64-encoded)
+    #[builder(into)]
+    body_hash_claim: Option<String>,
+    /// Function to validate any other claims of the verified token
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest, &JwtClaims) -> Result<(), WebhookError> + Send + Sync + 'static| Box::new(f) as ClaimsFn)]
+    validate_claims: Option<ClaimsFn>,
+    /// Function to get a unique ID for the delivery from the request, used to reject duplicate
+    /// deliveries when a [ReplayStore](crate::replay::ReplayStore) is configured.
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<String> + Send + Sync + 'static| Box::new(f) as DeliveryIdFn)]
+    delivery_id: Option<DeliveryIdFn>,
+}
+
+impl<S: jwt_webhook_builder::State> JwtWebhookBuilder<S> {
+    /// The key used to verify the token
+    pub fn key(self, key: JwtKey) -> JwtWebhookBuilder<jwt_webhook_builder::SetKeys<S>>
+    where
+        S::Keys: jwt_webhook_builder::IsUnset,
+    {
+        self.keys([key])
+    }
+
+    /// The keys accepted to verify the token (e.g. the new and old keys while rotating)
+    pub fn keys(
+        self,
+        keys: impl IntoIterator<Item = JwtKey>,
+    ) -> JwtWebhookBuilder<jwt_webhook_builder::SetKeys<S>>
+    where
+        S::Keys: jwt_webhook_builder::IsUnset,
+    {
+        self.keys_internal(JwtKeys::Static(keys.into_iter().collect()))
+    }
+
+    /// Provider of the key used to verify each token (e.g.
+    /// [RemotePublicKeys](crate::webhooks::interface::public_key::remote::RemotePublicKeys))
+    pub fn key_provider(
+        self,
+        provider: impl JwtKeyProvider + 'static,
+    ) -> JwtWebhookBuilder<jwt_webhook_builder::SetKeys<S>>
+    where
+        S::Keys: jwt_webhook_builder::IsUnset,
+    {
+        self.keys_internal(JwtKeys::Provider(Box::new(provider)))
+    }
+}
+
+impl Webhook for JwtWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        self.validate_with_jwt(req, body, time_bounds).await
+    }
+
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        self.delivery_id.as_ref().and_then(|id_fn| (id_fn)(req))
+    }
+}
+
+impl WebhookJwt for JwtWebhook {
+    fn token<'r>(&self, req: &'r dyn WebhookRequest) -> Result<&'r str, WebhookError> {
+        self.get_header(req, &self.header, None)
+    }
+
+    async fn verification_keys(
+        &self,
+        _req: &dyn WebhookRequest,
+        header: &JwtHeader,
+    ) -> Result<Vec<JwtKey>, WebhookError> {
+        match &self.keys {
+            JwtKeys::Static(keys) => Ok(keys.clone()),
+            JwtKeys::Provider(provider) => provider.key(header).await.map(|key| vec![key]),
+        }
+    }
+
+    async fn refreshed_verification_keys(
+        &self,
+        _req: &dyn WebhookRequest,
+        header: &JwtHeader,
+        stale: &[JwtKey],
+    ) -> Result<Option<Vec<JwtKey>>, WebhookError> {
+        match (&self.keys, stale) {
+            (JwtKeys::Provider(provider), [stale]) => {
+                let refreshed = provider.refreshed_key(header, stale).await?;
+                Ok(refreshed.map(|key| vec![key]))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn issuer(&self) -> Option<&str> {
+        self.issuer.as_deref()
+    }
+
+    fn audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
+    fn body_hash_claim(&self) -> Option<&str> {
+        self.body_hash_claim.as_deref()
+    }
+
+    fn validate_claims(
+        &self,
+        req: &dyn WebhookRequest,
+        claims: &JwtClaims,
+    ) -> Result<(), WebhookError> {
+        match &self.validate_claims {
+            Some(validate_fn) => (validate_fn)(req, claims),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use std::marker::PhantomData;
+
+use bon::Builder;
+use rocket::tokio::io::AsyncRead;
+use tokio_util::bytes::Bytes;
+
+use super::{DeliveryIdFn, SignaturesFn};
+#[cfg(feature = "jwks")]
+use crate::webhooks::interface::public_key::remote::RemotePublicKeys;
+use crate::{
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        interface::public_key::{PublicKey, WebhookPublicKey, WebhookPublicKeyAlgorithm},
+    },
+};
+
+/// Function to get the message to verify, given the request, raw body and time bounds
+type MessageFn = Box<
+    dyn Fn(&dyn WebhookRequest, &Bytes, (u32, u32)) -> Result<Bytes, WebhookError> + Send + Sync,
+>;
+/// Function to get the ID of the signing key from the request
+#[cfg(feature = "jwks")]
+type KeyIdFn = Box<dyn Fn(&dyn WebhookRequest) -> Option<String> + Send + Sync>;
+
+/// Where the public key comes from
+enum KeySource<A: WebhookPublicKeyAlgorithm> {
+    /// A single, static key
+    Static(PublicKey<A>),
+    /// Keys fetched from a URL
+    #[cfg(feature = "jwks")]
+    Remote(RemotePublicKeys<A>),
+}
+
+/// A custom webhook using Ed25519 signatures. See [PublicKeyWebhook] for details.
+#[cfg(feature = "ed25519")]
+pub type Ed25519Webhook =
+    PublicKeyWebhook<crate::webhooks::interface::public_key::algorithms::ed25519::Ed25519>;
+/// A custom webhook using ECDSA P-256 signatures (DER-encoded). See [PublicKeyWebhook] for details.
+#[cfg(feature = "p256")]
+pub type EcdsaP256Webhook =
+    PublicKeyWebhook<crate::webhooks::interface::public_key::algorithms::p256::EcdsaP256Asn1>;
+
+/**
+A custom webhook builder using public key verification of the request body, with the
+algorithm `A`. Use one of the aliases to select the algorithm, e.g. [Ed25519Webhook].
+
+# Example
+This sets up a webhook that expects a hex-encoded Ed25519 signature in the `Signature` header,
+and a Unix epoch timestamp in the `Timestamp` header that is attached as a prefix to the body
+(as `{timestamp}|{body}`) when verifying the signature:
+
+```
+# #[cfg(feature = "ed25519")] {
+use rocket_webhook::{
+    WebhookError,
+    webhooks::{generic::Ed25519Webhook, interface::public_key::PublicKey},
+};
+use tokio_util::bytes::BytesMut;
+
+let public_key = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAGb9ECWmEzf6FQbrBZ9w7lshQhqowtrbLDFw4rXAxZuE=
+-----END PUBLIC KEY-----";
+let my_webhook = Ed25519Webhook::builder()
+    .public_key(PublicKey::parse(public_key).expect("valid key"))
+    .expected_signatures(|req| {
+        req.header("Signature")
+            .and_then(|header| hex::decode(header).ok())
+            .map(|header| vec![header])
+    })
+    .message_to_verify(|req, body, (min_time, max_time)| {
+        let timestamp = req
+            .header("Timestamp")
+            .filter(|time| time.parse::<u32>().is_ok_and(|t| t > min_time && t < max_time))
+            .ok_or_else(|| WebhookError::Timestamp("Missing/invalid Timestamp header".into()))?;
+        let mut message = BytesMut::from(timestamp.as_bytes());
+        message.extend_from_slice(b"|");
+        message.extend_from_slice(body);
+        Ok(message.freeze())
+    })
+    .build();
+# }
+```
+*/
+#[derive(Builder)]
+pub struct PublicKeyWebhook<A: WebhookPublicKeyAlgorithm> {
+    /// The public key used to verify the webhook. Set using `.public_key()`, or `.remote_keys()`
+    /// to fetch the keys from a URL.
+    #[builder(setters(name = key_source_internal, vis = ""))]
+    key_source: KeySource<A>,
+    /// Function to get the ID of the key used to sign the request (e.g. from a header), to select
+    /// the key when using `.remote_keys()`
+    #[cfg(feature = "jwks")]
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<String> + Send + Sync + 'static| Box::new(f) as KeyIdFn)]
+    key_id: Option<KeyIdFn>,
+    /// Function to get the expected, decoded signature(s) from the request (typically
+    /// derived from one of the request headers).
+    /// If `None` is returned, signature is presumed to be missing or invalid.
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<Vec<Vec<u8>>> + Send + Sync + 'static| Box::new(f) as SignaturesFn)]
+    expected_signatures: SignaturesFn,
+    /// Function to get the message to verify from the raw body (e.g. with a timestamp prefix).
+    /// If not set, the raw body is verified. For replay prevention, any timestamp should be
+    /// validated against the given time bounds (in Unix epoch seconds).
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest, &Bytes, (u32, u32)) -> Result<Bytes, WebhookError> + Send + Sync + 'static| Box::new(f) as MessageFn)]
+    message_to_verify: Option<MessageFn>,
+    /// Function to get a unique ID for the delivery from the request, used to reject duplicate
+    /// deliveries when a [ReplayStore](crate::replay::ReplayStore) is configured.
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<String> + Send + Sync + 'static| Box::new(f) as DeliveryIdFn)]
+    delivery_id: Option<DeliveryIdFn>,
+    #[builder(skip)]
+    _algorithm: PhantomData<fn() -> A>,
+}
+
+impl<A: WebhookPublicKeyAlgorithm, S: public_key_webhook_builder::State>
+    PublicKeyWebhookBuilder<A, S>
+{
+    /// The parsed public key used to verify the webhook (see [PublicKey] for the supported formats)
+    pub fn public_key(
+        self,
+        public_key: PublicKey<A>,
+    ) -> PublicKeyWebhookBuilder<A, public_key_webhook_builder::SetKeySource<S>>
+    where
+        S::KeySource: public_key_webhook_builder::IsUnset,
+    {
+        self.key_source_internal(KeySource::Static(public_key))
+    }
+
+    /// Public keys fetched from a URL (e.g. a JWKS). Use `.key_id()` to select the key
+    /// for each request.
+    #[cfg(feature = "jwks")]
+    pub fn remote_keys(
+        self,
+        keys: RemotePublicKeys<A>,
+    ) -> PublicKeyWebhookBuilder<A, public_key_webhook_builder::SetKeySource<S>>
+    where
+        S::KeySource: public_key_webhook_builder::IsUnset,
+    {
+        self.key_source_internal(KeySource::Remote(keys))
+    }
+}
+
+impl<A: WebhookPublicKeyAlgorithm> PublicKeyWebhook<A> {
+    /// Get the ID of the signing key from the request
+    #[cfg(feature = "jwks")]
+    fn get_key_id(&self, req: &dyn WebhookRequest) -> Option<String> {
+        self.key_id.as_ref().and_then(|id_fn| (id_fn)(req))
+    }
+}
+
+impl<A: WebhookPublicKeyAlgorithm> Webhook for PublicKeyWebhook<A> {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        self.validate_with_public_key(req, body, time_bounds).await
+    }
+
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        self.delivery_id.as_ref().and_then(|id_fn| (id_fn)(req))
+    }
+}
+
+impl<A: WebhookPublicKeyAlgorithm> WebhookPublicKey for PublicKeyWebhook<A> {
+    type ALG = A;
+
+    #[allow(unused_variables)]
+    async fn public_key(&self, req: &dyn WebhookRequest) -> Result<PublicKey<A>, WebhookError> {
+        match &self.key_source {
+            KeySource::Static(public_key) => Ok(public_key.clone()),
+            #[cfg(feature = "jwks")]
+            KeySource::Remote(keys) => keys.key(self.get_key_id(req).as_deref()).await,
+        }
+    }
+
+    #[allow(unused_variables)]
+    async fn refreshed_public_key(
+        &self,
+        req: &dyn WebhookRequest,
+        stale: &PublicKey<A>,
+    ) -> Result<Option<PublicKey<A>>, WebhookError> {
+        match &self.key_source {
+            KeySource::Static(_) => Ok(None),
+            #[cfg(feature = "jwks")]
+            KeySource::Remote(keys) => keys.refresh(self.get_key_id(req).as_deref(), stale).await,
+        }
+    }
+
+    fn expected_signature(&self, req: &dyn WebhookRequest) -> Result<Vec<u8>, WebhookError> {
+        self.expected_signatures(req)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| WebhookError::Signature("No signatures provided".into()))
+    }
+
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        match (self.expected_signatures)(req) {
+            Some(signatures) => Ok(signatures),
+            None => Err(WebhookError::Signature(
+                "Valid signature(s) not provided in request".into(),
+            )),
+        }
+    }
+
+    fn message_to_verify(
+        &self,
+        req: &dyn WebhookRequest,
+        body: &Bytes,
+        time_bounds: (u32, u32),
+    ) -> Result<Bytes, WebhookError> {
+        match &self.message_to_verify {
+            Some(message_fn) => (message_fn)(req, body, time_bounds),
+            None => Ok(body.clone()),
+        }
+    }
+}
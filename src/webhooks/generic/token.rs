@@ -0,0 +1,207 @@
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bon::Builder;
+use rocket::tokio::io::{AsyncRead, AsyncReadExt};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroizing;
+
+use super::DeliveryIdFn;
+use crate::{
+    WebhookError, WebhookRequest,
+    webhooks::{ValidatedBody, Webhook, utils::body_size},
+};
+
+/// The credentials accepted by the webhook
+struct Credentials {
+    /// Authorization scheme expected before the credential (e.g. `Bearer`)
+    scheme: Option<&'static str>,
+    /// The accepted credentials, in order of preference
+    values: Vec<Zeroizing<Vec<u8>>>,
+}
+
+/**
+A custom webhook builder for providers that don't sign the body, and instead authenticate with
+a static token or HTTP Basic credentials sent in a header. Credentials are compared in constant
+time, and multiple credentials can be accepted (e.g. the new and old tokens while rotating).
+
+# Example
+This sets up a webhook that expects a secret token in the `X-Webhook-Secret` header:
+
+```
+use rocket_webhook::webhooks::generic::TokenWebhook;
+
+let my_webhook = TokenWebhook::builder()
+    .header("X-Webhook-Secret")
+    .tokens(["new-token", "old-token"])
+    .build();
+```
+
+A webhook using HTTP Basic credentials in the `Authorization` header:
+```
+use rocket_webhook::webhooks::generic::TokenWebhook;
+
+let my_webhook = TokenWebhook::builder()
+    .basic_auth("username", "password")
+    .build();
+```
+*/
+#[derive(Builder)]
+pub struct TokenWebhook {
+    /// The header containing the credential. Defaults to `Authorization`.
+    #[builder(into, default = "Authorization".to_owned())]
+    header: String,
+    /// The accepted credential(s). Set using `.token()`, `.tokens()`, `.bearer_token()`,
+    /// `.bearer_tokens()`, `.basic_auth()` or `.basic_auth_credentials()`.
+    #[builder(setters(name = credentials_internal, vis = ""))]
+    credentials: Credentials,
+    /// Function to get a unique ID for the delivery from the request, used to reject duplicate
+    /// deliveries when a [ReplayStore](crate::replay::ReplayStore) is configured.
+    #[builder(with = |f: impl Fn(&dyn WebhookRequest) -> Option<String> + Send + Sync + 'static| Box::new(f) as DeliveryIdFn)]
+    delivery_id: Option<DeliveryIdFn>,
+}
+
+impl<S: token_webhook_builder::State> TokenWebhookBuilder<S> {
+    /// The token expected as the full value of the header
+    pub fn token(
+        self,
+        token: impl Into<Vec<u8>>,
+    ) -> TokenWebhookBuilder<token_webhook_builder::SetCredentials<S>>
+    where
+        S::Credentials: token_webhook_builder::IsUnset,
+    {
+        self.tokens([token])
+    }
+
+    /// The tokens expected as the full value of the header, in order of preference
+    pub fn tokens(
+        self,
+        tokens: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+    ) -> TokenWebhookBuilder<token_webhook_builder::SetCredentials<S>>
+    where
+        S::Credentials: token_webhook_builder::IsUnset,
+    {
+        self.credentials_internal(Credentials::new(None, tokens))
+    }
+
+    /// The token expected using the `Bearer` scheme (e.g. `Authorization: Bearer <token>`)
+    pub fn bearer_token(
+        self,
+        token: impl Into<Vec<u8>>,
+    ) -> TokenWebhookBuilder<token_webhook_builder::SetCredentials<S>>
+    where
+        S::Credentials: token_webhook_builder::IsUnset,
+    {
+        self.bearer_tokens([token])
+    }
+
+    /// The tokens expected using the `Bearer` scheme, in order of preference
+    pub fn bearer_tokens(
+        self,
+        tokens: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+    ) -> TokenWebhookBuilder<token_webhook_builder::SetCredentials<S>>
+    where
+        S::Credentials: token_webhook_builder::IsUnset,
+    {
+        self.credentials_internal(Credentials::new(Some("Bearer"), tokens))
+    }
+
+    /// The username and password expected using HTTP Basic authentication
+    pub fn basic_auth(
+        self,
+        username: impl AsRef<str>,
+        password: impl AsRef<str>,
+    ) -> TokenWebhookBuilder<token_webhook_builder::SetCredentials<S>>
+    where
+        S::Credentials: token_webhook_builder::IsUnset,
+    {
+        self.basic_auth_credentials([(username, password)])
+    }
+
+    /// The `(username, password)` pairs expected using HTTP Basic authentication, in order
+    /// of preference
+    pub fn basic_auth_credentials<U: AsRef<str>, P: AsRef<str>>(
+        self,
+        credentials: impl IntoIterator<Item = (U, P)>,
+    ) -> TokenWebhookBuilder<token_webhook_builder::SetCredentials<S>>
+    where
+        S::Credentials: token_webhook_builder::IsUnset,
+    {
+        let encoded = credentials.into_iter().map(|(username, password)| {
+            let user_pass = Zeroizing::new(format!("{}:{}", username.as_ref(), password.as_ref()));
+            BASE64_STANDARD.encode(user_pass.as_bytes())
+        });
+        self.credentials_internal(Credentials::new(Some("Basic"), encoded))
+    }
+}
+
+impl Credentials {
+    fn new(
+        scheme: Option<&'static str>,
+        values: impl IntoIterator<Item = impl Into<Vec<u8>>>,
+    ) -> Self {
+        Self {
+            scheme,
+            values: values
+                .into_iter()
+                .map(|value| Zeroizing::new(value.into()))
+                .collect(),
+        }
+    }
+}
+
+impl TokenWebhook {
+    /// Get the credential from the header, stripping the authorization scheme if needed
+    fn get_credential<'r>(&self, req: &'r dyn WebhookRequest) -> Result<&'r str, WebhookError> {
+        let header = self.get_header(req, &self.header, None)?;
+        let Some(scheme) = self.credentials.scheme else {
+            return Ok(header);
+        };
+        match header.split_once(' ') {
+            Some((req_scheme, credential)) if req_scheme.eq_ignore_ascii_case(scheme) => {
+                Ok(credential.trim_start())
+            }
+            _ => Err(WebhookError::InvalidHeader(format!(
+                "'{}' is missing the '{scheme}' scheme",
+                self.header
+            ))),
+        }
+    }
+}
+
+impl Webhook for TokenWebhook {
+    async fn validate_body(
+        &self,
+        req: &dyn WebhookRequest,
+        mut body: impl AsyncRead + Unpin + Send,
+        _time_bounds: (u32, u32),
+    ) -> Result<ValidatedBody, WebhookError> {
+        let credential = self.get_credential(req)?.as_bytes();
+
+        // Check all credentials, so the comparison takes the same time regardless of which matched
+        let mut secret_index = None;
+        for (idx, expected) in self.credentials.values.iter().enumerate() {
+            let is_match: Choice = credential.ct_eq(expected);
+            if bool::from(is_match) && secret_index.is_none() {
+                secret_index = Some(idx);
+            }
+        }
+        if secret_index.is_none() {
+            return Err(WebhookError::Signature(format!(
+                "'{}' header did not match an accepted credential",
+                self.header
+            )));
+        }
+
+        let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(512));
+        body.read_to_end(&mut raw_body)
+            .await
+            .map_err(WebhookError::Read)?;
+        Ok(ValidatedBody {
+            data: raw_body,
+            secret_index,
+        })
+    }
+
+    fn delivery_id(&self, req: &dyn WebhookRequest, _body: &[u8]) -> Option<String> {
+        self.delivery_id.as_ref().and_then(|id_fn| (id_fn)(req))
+    }
+}
@@ -2,5 +2,7 @@
 
 #[cfg(feature = "hmac")]
 pub mod hmac;
+#[cfg(feature = "jwt")]
+pub mod jwt;
 #[cfg(feature = "public-key")]
 pub mod public_key;
@@ -1,39 +1,118 @@
 //! Interface for webhooks that use HMAC signature validation
 
+use std::borrow::Cow;
+
 use hmac::{Mac, digest::KeyInit};
-use rocket::{
-    Request, data::Outcome, futures::StreamExt, http::Status, outcome::try_outcome,
-    tokio::io::AsyncRead,
-};
+use rocket::{futures::StreamExt, tokio::io::AsyncRead};
 use subtle::ConstantTimeEq;
 use tokio_util::io::ReaderStream;
+use zeroize::Zeroizing;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, utils::body_size},
+    WebhookError, WebhookRequest,
+    webhooks::{
+        ValidatedBody, Webhook,
+        utils::{body_size, unix_time},
+    },
 };
 
+/// Resolving secret keys per request
+pub mod resolver;
+
+use resolver::SecretResolver;
+
+/**
+A secret key used to sign HMAC webhooks, with an optional expiration time. Multiple secrets
+can be configured on a webhook to support rotating the secret without downtime.
+
+# Example
+```
+use rocket_webhook::webhooks::{built_in::GitHubWebhook, interface::hmac::HmacSecret};
+
+let webhook = GitHubWebhook::with_secrets([
+    HmacSecret::new("new-secret"),
+    HmacSecret::new("old-secret").expires_at(1767225600), // no longer accepted after this time
+]);
+```
+*/
+#[derive(Clone)]
+pub struct HmacSecret {
+    pub(crate) key: Zeroizing<Vec<u8>>,
+    pub(crate) expires_at: Option<u32>,
+}
+
+impl HmacSecret {
+    /// Create a secret from the given key. If the key is encoded in hex or base64, etc., it
+    /// must be decoded to bytes first
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self {
+            key: Zeroizing::new(key.into()),
+            expires_at: None,
+        }
+    }
+
+    /// Set the time (in Unix epoch seconds) after which this secret is no longer accepted
+    pub fn expires_at(mut self, unix_secs: u32) -> Self {
+        self.expires_at = Some(unix_secs);
+        self
+    }
+
+    /// The secret key
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Whether the secret has expired at the given time (in Unix epoch seconds)
+    pub fn is_expired(&self, unix_secs: u32) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| unix_secs > expires_at)
+    }
+}
+
+/// Secret keys of an HMAC webhook, which can be static or resolved for each request
+pub enum HmacSecrets {
+    /// Static secret keys, in order of preference
+    Static(Vec<HmacSecret>),
+    /// Secret keys resolved for each request (e.g. for multi-tenant webhooks where each
+    /// account has its own secret)
+    Resolver(Box<dyn SecretResolver>),
+}
+
+impl HmacSecrets {
+    /// Get the secret keys to use for the request
+    pub async fn resolve(
+        &self,
+        req: &dyn WebhookRequest,
+    ) -> Result<Cow<'_, [HmacSecret]>, WebhookError> {
+        match self {
+            HmacSecrets::Static(secrets) => Ok(Cow::Borrowed(secrets)),
+            HmacSecrets::Resolver(resolver) => resolver.resolve(req).await.map(Cow::Owned),
+        }
+    }
+}
+
 /// Trait for webhooks that use HMAC signature validation.
 pub trait WebhookHmac: Webhook {
     /// MAC algorithm (from the `hmac` crate) used to calculate the signature
     type MAC: Mac + KeyInit + Send;
 
-    /// Get the secret key used to sign the webhook
-    fn secret_key(&self) -> &[u8];
+    /// Get the secret key(s) used to sign the webhook, in order of preference (e.g. the current
+    /// secret followed by previous secrets that are still accepted during rotation)
+    fn secret_keys(&self) -> &HmacSecrets;
 
     /// Get the expected signature(s) from the request. To obtain required headers,
     /// you can use the `self.get_header()` utility.
-    fn expected_signatures(&self, req: &Request<'_>) -> Outcome<'_, Vec<Vec<u8>>, WebhookError>;
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError>;
 
     /// Get an optional prefix to attach to the raw body when calculating the signature. Timestamps
     /// should be validated against the given bounds.
     #[allow(unused_variables)]
     fn body_prefix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        Outcome::Success(None)
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        Ok(None)
     }
 
     /// Get an optional suffix to attach to the raw body when calculating the signature. Timestamps
@@ -41,69 +120,83 @@ pub trait WebhookHmac: Webhook {
     #[allow(unused_variables)]
     fn body_suffix(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Option<Vec<u8>>, WebhookError> {
-        Outcome::Success(None)
+    ) -> Result<Option<Vec<u8>>, WebhookError> {
+        Ok(None)
     }
 
-    /// Read the request body and verify the HMAC signature. Calculates the HMAC
-    /// directly from the raw streamed body (with a prefix if configured).
+    /// Read the request body and verify the HMAC signature. Calculates the HMAC for each
+    /// unexpired secret key directly from the raw streamed body (with a prefix if configured),
+    /// and returns the index of the secret key that matched.
     fn validate_with_hmac(
         &self,
-        req: &Request<'_>,
-        body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> impl Future<Output = Outcome<'_, Vec<u8>, WebhookError>> + Send + Sync
+    ) -> impl Future<Output = Result<ValidatedBody, WebhookError>> + Send
     where
         Self: Sync,
         Self::MAC: Sync,
     {
         async move {
             // Get expected signatures from request
-            let expected_signatures = try_outcome!(self.expected_signatures(req));
+            let expected_signatures = self.expected_signatures(req)?;
 
-            // Get secret key and initialize HMAC
-            let key = self.secret_key();
-            let mut mac = <<Self as WebhookHmac>::MAC as hmac::Mac>::new_from_slice(key)
-                .expect("HMAC should take any key length");
+            // Get secret keys and initialize HMAC for each active key
+            let secret_keys = self.secret_keys().resolve(req).await?;
+            let now = unix_time();
+            let mut macs: Vec<(usize, Self::MAC)> = secret_keys
+                .iter()
+                .enumerate()
+                .filter(|(_, secret)| !secret.is_expired(now))
+                .map(|(idx, secret)| {
+                    let mac = <Self::MAC as hmac::Mac>::new_from_slice(secret.key())
+                        .expect("HMAC should take any key length");
+                    (idx, mac)
+                })
+                .collect();
+            if macs.is_empty() {
+                return Err(WebhookError::Signature(
+                    "No active secret keys configured".into(),
+                ));
+            }
 
-            // Update HMAC with prefix if there is one
-            if let Some(prefix) = try_outcome!(self.body_prefix(req, time_bounds)) {
-                mac.update(&prefix);
+            // Update HMACs with prefix if there is one
+            if let Some(prefix) = self.body_prefix(req, time_bounds)? {
+                macs.iter_mut().for_each(|(_, mac)| mac.update(&prefix));
             }
 
-            // Read body stream while calculating HMAC
+            // Read body stream while calculating HMACs
             let mut body_stream = ReaderStream::new(body);
-            let mut raw_body = Vec::with_capacity(body_size(req.headers()).unwrap_or(512));
+            let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(512));
             while let Some(chunk_result) = body_stream.next().await {
-                match chunk_result {
-                    Ok(chunk_bytes) => {
-                        mac.update(&chunk_bytes);
-                        raw_body.extend_from_slice(&chunk_bytes);
-                    }
-                    Err(e) => {
-                        return Outcome::Error((Status::BadRequest, WebhookError::Read(e)));
-                    }
-                }
+                let chunk_bytes = chunk_result.map_err(WebhookError::Read)?;
+                macs.iter_mut()
+                    .for_each(|(_, mac)| mac.update(&chunk_bytes));
+                raw_body.extend_from_slice(&chunk_bytes);
             }
 
-            // Update HMAC with suffix if there is one
-            if let Some(suffix) = try_outcome!(self.body_suffix(req, time_bounds)) {
-                mac.update(&suffix);
+            // Update HMACs with suffix if there is one
+            if let Some(suffix) = self.body_suffix(req, time_bounds)? {
+                macs.iter_mut().for_each(|(_, mac)| mac.update(&suffix));
             }
 
-            // Check HMAC against all provided signatures
-            let body_sig = mac.finalize().into_bytes();
-            for signature in expected_signatures {
-                if body_sig.ct_eq(&signature).into() {
-                    return Outcome::Success(raw_body);
+            // Check HMACs against all provided signatures
+            for (idx, mac) in macs {
+                let body_sig = mac.finalize().into_bytes();
+                for signature in &expected_signatures {
+                    if body_sig.ct_eq(signature).into() {
+                        return Ok(ValidatedBody {
+                            data: raw_body,
+                            secret_index: Some(idx),
+                        });
+                    }
                 }
             }
-            return Outcome::Error((
-                Status::Unauthorized,
-                WebhookError::Signature("HMAC didn't match any provided signature".into()),
-            ));
+            Err(WebhookError::Signature(
+                "HMAC didn't match any provided signature".into(),
+            ))
         }
     }
 }
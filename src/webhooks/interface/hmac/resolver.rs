@@ -0,0 +1,141 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use rocket::async_trait;
+
+use crate::{WebhookError, WebhookRequest};
+
+use super::HmacSecret;
+
+/**
+Trait to resolve the secret key(s) of an HMAC webhook for each request. This is useful for multi-tenant
+webhooks, where each account has its own signing secret (e.g. stored in a database). When validating a
+Rocket request, the resolver can read the route parameters or request-local cache with
+`with_rocket_request`, and managed state with [WebhookRequest::rocket].
+
+Resolved secrets can be cached using the [CachedSecretResolver], keyed by the [SecretResolver::cache_key].
+
+# Example
+```
+use rocket::async_trait;
+use rocket_webhook::{
+    WebhookError, WebhookRequest,
+    webhooks::{
+        built_in::StripeWebhook,
+        interface::hmac::{HmacSecret, resolver::{CachedSecretResolver, SecretResolver}},
+    },
+};
+use std::time::Duration;
+
+/// Database of account secrets, managed by Rocket
+struct Db;
+
+impl Db {
+    async fn signing_secret(&self, account_id: &str) -> Result<Option<String>, String> {
+        Ok(Some(format!("secret-for-{account_id}")))
+    }
+}
+
+struct StripeAccountSecrets;
+
+#[async_trait]
+impl SecretResolver for StripeAccountSecrets {
+    fn cache_key(&self, req: &dyn WebhookRequest) -> Option<String> {
+        // Get the account ID from the route, e.g. `/webhooks/stripe/<account_id>`
+        req.with_rocket_request(|req| req.param::<String>(2)?.ok())?
+    }
+
+    async fn resolve(&self, req: &dyn WebhookRequest) -> Result<Vec<HmacSecret>, WebhookError> {
+        let Some(account_id) = self.cache_key(req) else {
+            return Ok(Vec::new()); // No secrets, so the signature will be rejected
+        };
+        let db = req
+            .rocket()
+            .and_then(|rocket| rocket.state::<Db>())
+            .ok_or_else(|| WebhookError::Secret("database is not managed".into()))?;
+        match db.signing_secret(&account_id).await {
+            Ok(secret) => Ok(secret.into_iter().map(HmacSecret::new).collect()),
+            Err(e) => Err(WebhookError::Secret(e)),
+        }
+    }
+}
+
+let webhook = StripeWebhook::with_resolver(
+    CachedSecretResolver::new(StripeAccountSecrets, Duration::from_secs(5 * 60))
+);
+```
+*/
+#[async_trait]
+pub trait SecretResolver: Send + Sync {
+    /// Get the key used to cache the resolved secrets for this request, typically the account or
+    /// tenant ID. The default implementation returns `None`, which disables caching.
+    #[allow(unused_variables)]
+    fn cache_key(&self, req: &dyn WebhookRequest) -> Option<String> {
+        None
+    }
+
+    /// Resolve the secret key(s) for the request, in order of preference. Return an empty list if no
+    /// secrets exist for the request (the webhook will be rejected as unauthorized). If the secrets
+    /// can't be fetched, return a [WebhookError::Secret] error (Internal Server Error).
+    async fn resolve(&self, req: &dyn WebhookRequest) -> Result<Vec<HmacSecret>, WebhookError>;
+}
+
+/// Secret resolver that caches the secrets of another resolver for the given time-to-live,
+/// using the resolver's [SecretResolver::cache_key].
+pub struct CachedSecretResolver<R> {
+    resolver: R,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Instant, Vec<HmacSecret>)>>,
+}
+
+impl<R: SecretResolver> CachedSecretResolver<R> {
+    /// Cache the secrets of the given resolver for the time-to-live
+    pub fn new(resolver: R, ttl: Duration) -> Self {
+        Self {
+            resolver,
+            ttl,
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Remove the cached secrets for the given key, e.g. after a secret was changed
+    pub fn invalidate(&self, cache_key: &str) {
+        self.cache
+            .lock()
+            .expect("lock is not poisoned")
+            .remove(cache_key);
+    }
+}
+
+#[async_trait]
+impl<R: SecretResolver> SecretResolver for CachedSecretResolver<R> {
+    fn cache_key(&self, req: &dyn WebhookRequest) -> Option<String> {
+        self.resolver.cache_key(req)
+    }
+
+    async fn resolve(&self, req: &dyn WebhookRequest) -> Result<Vec<HmacSecret>, WebhookError> {
+        let Some(cache_key) = self.resolver.cache_key(req) else {
+            return self.resolver.resolve(req).await;
+        };
+
+        let now = Instant::now();
+        {
+            let cache = self.cache.lock().expect("lock is not poisoned");
+            if let Some((expires_at, secrets)) = cache.get(&cache_key)
+                && *expires_at > now
+            {
+                return Ok(secrets.clone());
+            }
+        }
+
+        let secrets = self.resolver.resolve(req).await?;
+        let mut cache = self.cache.lock().expect("lock is not poisoned");
+        cache.retain(|_, (expires_at, _)| *expires_at > now);
+        cache.insert(cache_key, (now + self.ttl, secrets.clone()));
+
+        Ok(secrets)
+    }
+}
@@ -0,0 +1,475 @@
+//! Interface for webhooks that are authenticated with a JSON Web Token (JWT)
+
+use base64::{
+    Engine,
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+};
+use hmac::{Hmac, Mac};
+use rocket::{
+    async_trait,
+    serde::{
+        Deserialize,
+        json::{Value, serde_json},
+    },
+    tokio::io::{AsyncRead, AsyncReadExt},
+};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroizing;
+
+#[cfg(all(feature = "ed25519", feature = "public-key"))]
+use super::public_key::algorithms::ed25519::Ed25519;
+#[cfg(all(feature = "p256", feature = "public-key"))]
+use super::public_key::algorithms::p256::EcdsaP256Fixed;
+#[cfg(feature = "rsa")]
+use super::public_key::algorithms::rsa::RsaPkcs1v15Sha256;
+#[cfg(all(
+    feature = "public-key",
+    any(
+        feature = "ed25519",
+        feature = "jwks",
+        feature = "p256",
+        feature = "rsa"
+    )
+))]
+use super::public_key::{PublicKey, WebhookPublicKeyAlgorithm};
+use crate::{
+    WebhookError, WebhookRequest,
+    webhooks::{ValidatedBody, Webhook, utils::body_size},
+};
+
+/**
+A key used to verify JWTs. A token is only verified with keys that match the algorithm in its
+header (`alg`), so a token signed with a different algorithm (e.g. `none`) is always rejected.
+
+# Example
+```
+use rocket_webhook::webhooks::interface::jwt::JwtKey;
+
+let key = JwtKey::hs256("my-secret");
+assert_eq!(key.algorithm(), "HS256");
+
+# #[cfg(feature = "p256")] {
+use rocket_webhook::webhooks::interface::public_key::PublicKey;
+
+let key = JwtKey::Es256(
+    PublicKey::parse(
+        r#"{"kty":"EC","crv":"P-256","x":"PbltMw-jO8Cj9Xx_O3Aqm_UvD6d_RNHG_kLNT5JNhj0",
+            "y":"psUKV-CVBDmzM-qj7YtIF7SoQPA4ZhT-YLz6Nf9lPdU"}"#,
+    )
+    .expect("valid key"),
+);
+assert_eq!(key.algorithm(), "ES256");
+# }
+```
+*/
+#[derive(Clone)]
+pub enum JwtKey {
+    /// HMAC using SHA-256 (`HS256`), with a shared secret
+    Hs256(Zeroizing<Vec<u8>>),
+    /// RSA PKCS#1 v1.5 using SHA-256 (`RS256`)
+    #[cfg(feature = "rsa")]
+    Rs256(PublicKey<RsaPkcs1v15Sha256>),
+    /// ECDSA using P-256 and SHA-256 (`ES256`)
+    #[cfg(all(feature = "p256", feature = "public-key"))]
+    Es256(PublicKey<EcdsaP256Fixed>),
+    /// Ed25519 (`EdDSA`)
+    #[cfg(all(feature = "ed25519", feature = "public-key"))]
+    EdDsa(PublicKey<Ed25519>),
+}
+
+impl JwtKey {
+    /// Create an `HS256` key from the shared secret. If the secret is encoded in hex or base64,
+    /// etc., it must be decoded to bytes first
+    pub fn hs256(secret: impl Into<Vec<u8>>) -> Self {
+        Self::Hs256(Zeroizing::new(secret.into()))
+    }
+
+    /// The JWT algorithm (`alg`) of the key
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            JwtKey::Hs256(_) => "HS256",
+            #[cfg(feature = "rsa")]
+            JwtKey::Rs256(_) => "RS256",
+            #[cfg(all(feature = "p256", feature = "public-key"))]
+            JwtKey::Es256(_) => "ES256",
+            #[cfg(all(feature = "ed25519", feature = "public-key"))]
+            JwtKey::EdDsa(_) => "EdDSA",
+        }
+    }
+
+    /// Verify the signature of the token's signing input (`{header}.{payload}`)
+    pub fn verify(&self, signing_input: &[u8], signature: &[u8]) -> Result<(), String> {
+        match self {
+            JwtKey::Hs256(secret) => {
+                let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret)
+                    .expect("HMAC should take any key length");
+                mac.update(signing_input);
+                mac.verify_slice(signature)
+                    .map_err(|_| "HS256 signature didn't match".to_owned())
+            }
+            #[cfg(feature = "rsa")]
+            JwtKey::Rs256(key) => RsaPkcs1v15Sha256::verify(key.key(), signing_input, signature),
+            #[cfg(all(feature = "p256", feature = "public-key"))]
+            JwtKey::Es256(key) => EcdsaP256Fixed::verify(key.key(), signing_input, signature),
+            #[cfg(all(feature = "ed25519", feature = "public-key"))]
+            JwtKey::EdDsa(key) => Ed25519::verify(key.key(), signing_input, signature),
+        }
+    }
+
+    /// Whether both are clones of the same public key
+    #[cfg(feature = "jwks")]
+    fn ptr_eq(&self, other: &JwtKey) -> bool {
+        match (self, other) {
+            #[cfg(feature = "rsa")]
+            (JwtKey::Rs256(key), JwtKey::Rs256(other)) => key.ptr_eq(other),
+            #[cfg(feature = "p256")]
+            (JwtKey::Es256(key), JwtKey::Es256(other)) => key.ptr_eq(other),
+            #[cfg(feature = "ed25519")]
+            (JwtKey::EdDsa(key), JwtKey::EdDsa(other)) => key.ptr_eq(other),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "rsa")]
+impl From<PublicKey<RsaPkcs1v15Sha256>> for JwtKey {
+    fn from(key: PublicKey<RsaPkcs1v15Sha256>) -> Self {
+        Self::Rs256(key)
+    }
+}
+
+#[cfg(all(feature = "p256", feature = "public-key"))]
+impl From<PublicKey<EcdsaP256Fixed>> for JwtKey {
+    fn from(key: PublicKey<EcdsaP256Fixed>) -> Self {
+        Self::Es256(key)
+    }
+}
+
+#[cfg(all(feature = "ed25519", feature = "public-key"))]
+impl From<PublicKey<Ed25519>> for JwtKey {
+    fn from(key: PublicKey<Ed25519>) -> Self {
+        Self::EdDsa(key)
+    }
+}
+
+/**
+Provider of the keys used to verify JWTs, e.g. keys fetched from the provider's JWKS URL.
+This is implemented for [RemotePublicKeys](super::public_key::remote::RemotePublicKeys)
+when the `jwks` feature is enabled.
+*/
+#[async_trait]
+pub trait JwtKeyProvider: Send + Sync {
+    /// Get the key to verify the token with the given header (e.g. selected by its `kid`)
+    async fn key(&self, header: &JwtHeader) -> Result<JwtKey, WebhookError>;
+
+    /// Get a new key after the token couldn't be verified with the `stale` key (e.g. if the
+    /// provider rotated its key without changing the key ID). The default implementation
+    /// returns `None`.
+    #[allow(unused_variables)]
+    async fn refreshed_key(
+        &self,
+        header: &JwtHeader,
+        stale: &JwtKey,
+    ) -> Result<Option<JwtKey>, WebhookError> {
+        Ok(None)
+    }
+}
+
+#[cfg(feature = "jwks")]
+#[async_trait]
+impl<A> JwtKeyProvider for super::public_key::remote::RemotePublicKeys<A>
+where
+    A: WebhookPublicKeyAlgorithm + 'static,
+    PublicKey<A>: Into<JwtKey>,
+{
+    async fn key(&self, header: &JwtHeader) -> Result<JwtKey, WebhookError> {
+        self.key(header.kid.as_deref()).await.map(Into::into)
+    }
+
+    async fn refreshed_key(
+        &self,
+        header: &JwtHeader,
+        stale: &JwtKey,
+    ) -> Result<Option<JwtKey>, WebhookError> {
+        let is_stale = |key: &PublicKey<A>| stale.ptr_eq(&key.clone().into());
+        let refreshed = self.refresh_where(header.kid.as_deref(), is_stale).await?;
+        Ok(refreshed.map(Into::into))
+    }
+}
+
+/// The header of a JWT
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct JwtHeader {
+    /// Algorithm used to sign the token (e.g. `RS256`)
+    pub alg: String,
+    /// ID of the key used to sign the token
+    pub kid: Option<String>,
+    /// Type of the token (e.g. `JWT`)
+    pub typ: Option<String>,
+}
+
+/// The claims of a JWT
+#[derive(Debug, Clone)]
+pub struct JwtClaims(serde_json::Map<String, Value>);
+
+impl JwtClaims {
+    /// Decode the claims from a token, with an optional `Bearer` prefix. **The token is not
+    /// verified**, so this should only be used for a webhook that was already validated (e.g. to
+    /// read the claims in a route handler).
+    pub fn from_token(token: &str) -> Result<Self, WebhookError> {
+        DecodedJwt::decode(token).map(|jwt| jwt.claims)
+    }
+
+    /// Get a claim
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// Get a claim with a string value
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.0.get(name).and_then(Value::as_str)
+    }
+
+    /// Get a claim with a numeric date value (Unix epoch seconds), e.g. `exp`
+    pub fn get_time(&self, name: &str) -> Option<i64> {
+        let value = self.0.get(name)?;
+        value
+            .as_i64()
+            .or_else(|| value.as_f64().map(|time| time as i64))
+    }
+
+    /// Whether the audience (`aud`) claim, which can be a string or an array, contains `audience`
+    pub fn has_audience(&self, audience: &str) -> bool {
+        match self.0.get("aud") {
+            Some(Value::String(aud)) => aud == audience,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+            _ => false,
+        }
+    }
+
+    /// The claims as a JSON map
+    pub fn as_map(&self) -> &serde_json::Map<String, Value> {
+        &self.0
+    }
+}
+
+/// A decoded, but not yet verified, JWT
+struct DecodedJwt<'t> {
+    header: JwtHeader,
+    claims: JwtClaims,
+    /// The encoded header and payload (`{header}.{payload}`)
+    signing_input: &'t str,
+    signature: Vec<u8>,
+}
+
+impl<'t> DecodedJwt<'t> {
+    fn decode(token: &'t str) -> Result<Self, WebhookError> {
+        let token = token.trim();
+        let token = match token.split_once(' ') {
+            Some((scheme, token)) if scheme.eq_ignore_ascii_case("Bearer") => token.trim_start(),
+            _ => token,
+        };
+        let invalid = |err: &str| WebhookError::InvalidHeader(format!("Token is not a JWT: {err}"));
+        let decode_part = |part: &str| {
+            BASE64_URL_SAFE_NO_PAD
+                .decode(part)
+                .map_err(|e| invalid(&e.to_string()))
+        };
+
+        let (signing_input, signature) = token
+            .rsplit_once('.')
+            .ok_or_else(|| invalid("expected 3 parts"))?;
+        let (header, claims) = signing_input
+            .split_once('.')
+            .ok_or_else(|| invalid("expected 3 parts"))?;
+        let header = serde_json::from_slice(&decode_part(header)?)
+            .map_err(|e| invalid(&format!("invalid header: {e}")))?;
+        let claims = serde_json::from_slice(&decode_part(claims)?)
+            .map_err(|e| invalid(&format!("invalid claims: {e}")))?;
+
+        Ok(Self {
+            header,
+            claims: JwtClaims(claims),
+            signing_input,
+            signature: decode_part(signature)?,
+        })
+    }
+}
+
+/// Trait for webhooks that are authenticated with a JWT, which is signed by the provider and
+/// contains claims about the delivery (e.g. issuer, audience, expiration, and a hash of the body).
+pub trait WebhookJwt: Webhook {
+    /// Get the token from the request (e.g. from the `Authorization` header). A `Bearer` prefix
+    /// is removed automatically.
+    fn token<'r>(&self, req: &'r dyn WebhookRequest) -> Result<&'r str, WebhookError>;
+
+    /// Get the key(s) that can verify the token with the given header
+    fn verification_keys(
+        &self,
+        req: &dyn WebhookRequest,
+        header: &JwtHeader,
+    ) -> impl Future<Output = Result<Vec<JwtKey>, WebhookError>> + Send;
+
+    /// Get new key(s) after the token couldn't be verified with the `stale` keys (e.g. if the
+    /// provider rotated its keys). If keys are returned, the token is verified again with them.
+    /// The default implementation returns `None`.
+    #[allow(unused_variables)]
+    fn refreshed_verification_keys(
+        &self,
+        req: &dyn WebhookRequest,
+        header: &JwtHeader,
+        stale: &[JwtKey],
+    ) -> impl Future<Output = Result<Option<Vec<JwtKey>>, WebhookError>> + Send {
+        async { Ok(None) }
+    }
+
+    /// The expected issuer of the token (`iss` claim). The default implementation returns `None`,
+    /// which skips the check.
+    fn issuer(&self) -> Option<&str> {
+        None
+    }
+
+    /// The expected audience of the token (`aud` claim). The default implementation returns
+    /// `None`, which skips the check.
+    fn audience(&self) -> Option<&str> {
+        None
+    }
+
+    /// The claim containing the SHA-256 hash of the body (hex or base64-encoded), which binds the
+    /// token to the body (e.g. `request_body_sha256`). The default implementation returns `None`,
+    /// which skips the check.
+    fn body_hash_claim(&self) -> Option<&str> {
+        None
+    }
+
+    /// Validate any other claims of the verified token. The default implementation accepts
+    /// all claims.
+    #[allow(unused_variables)]
+    fn validate_claims(
+        &self,
+        req: &dyn WebhookRequest,
+        claims: &JwtClaims,
+    ) -> Result<(), WebhookError> {
+        Ok(())
+    }
+
+    /// Verify the token and its claims, then read the body and check its hash (if configured).
+    /// The expiration (`exp`), not before (`nbf`) and issued at (`iat`) claims are validated
+    /// against the time bounds, and the token must have at least one of `exp` or `iat`.
+    fn validate_with_jwt(
+        &self,
+        req: &dyn WebhookRequest,
+        mut body: impl AsyncRead + Unpin + Send,
+        time_bounds: (u32, u32),
+    ) -> impl Future<Output = Result<ValidatedBody, WebhookError>> + Send
+    where
+        Self: Sync,
+    {
+        async move {
+            // Decode token and verify signature
+            let jwt = DecodedJwt::decode(self.token(req)?)?;
+            let verify = |keys: &[JwtKey]| {
+                let mut verification = Err(format!("No key for algorithm '{}'", jwt.header.alg));
+                for key in keys.iter().filter(|key| key.algorithm() == jwt.header.alg) {
+                    verification = key.verify(jwt.signing_input.as_bytes(), &jwt.signature);
+                    if verification.is_ok() {
+                        break;
+                    }
+                }
+                verification
+            };
+            let keys = self.verification_keys(req, &jwt.header).await?;
+            let mut verification = verify(&keys);
+
+            // Retry once with refreshed keys. If refreshing fails, the original error is kept.
+            if verification.is_err()
+                && let Ok(Some(refreshed_keys)) = self
+                    .refreshed_verification_keys(req, &jwt.header, &keys)
+                    .await
+            {
+                verification = verify(&refreshed_keys);
+            }
+            verification.map_err(WebhookError::Signature)?;
+
+            // Validate claims
+            validate_time_claims(&jwt.claims, time_bounds)?;
+            if let Some(issuer) = self.issuer()
+                && jwt.claims.get_str("iss") != Some(issuer)
+            {
+                return Err(WebhookError::Signature(format!(
+                    "Token issuer is not '{issuer}'"
+                )));
+            }
+            if let Some(audience) = self.audience()
+                && !jwt.claims.has_audience(audience)
+            {
+                return Err(WebhookError::Signature(format!(
+                    "Token audience is not '{audience}'"
+                )));
+            }
+            self.validate_claims(req, &jwt.claims)?;
+
+            // Read body stream and check hash
+            let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(512));
+            body.read_to_end(&mut raw_body)
+                .await
+                .map_err(WebhookError::Read)?;
+            if let Some(claim) = self.body_hash_claim() {
+                let expected_hash = jwt.claims.get_str(claim).ok_or_else(|| {
+                    WebhookError::Signature(format!("Token is missing the '{claim}' claim"))
+                })?;
+                if !matches_hash(expected_hash, &Sha256::digest(&raw_body)) {
+                    return Err(WebhookError::Signature(format!(
+                        "Body hash doesn't match the '{claim}' claim"
+                    )));
+                }
+            }
+
+            Ok(raw_body.into())
+        }
+    }
+}
+
+/// Validate the `exp`, `nbf` and `iat` claims against the time bounds
+fn validate_time_claims(claims: &JwtClaims, (min, max): (u32, u32)) -> Result<(), WebhookError> {
+    let (exp, nbf, iat) = (
+        claims.get_time("exp"),
+        claims.get_time("nbf"),
+        claims.get_time("iat"),
+    );
+    if exp.is_none() && iat.is_none() {
+        return Err(WebhookError::Timestamp(
+            "Token has no expiration or issued at time".into(),
+        ));
+    }
+    if exp.is_some_and(|exp| exp < i64::from(min)) {
+        return Err(WebhookError::Timestamp("Token has expired".into()));
+    }
+    if nbf.is_some_and(|nbf| nbf > i64::from(max)) {
+        return Err(WebhookError::Timestamp("Token is not valid yet".into()));
+    }
+    if iat.is_some_and(|iat| iat < i64::from(min) || iat > i64::from(max)) {
+        return Err(WebhookError::Timestamp(format!(
+            "Token issued at time is out of bounds: {}",
+            iat.unwrap_or_default()
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the hex or base64-encoded hash matches the digest
+fn matches_hash(expected: &str, digest: &[u8]) -> bool {
+    let candidates = [
+        hex::decode(expected).ok(),
+        BASE64_URL_SAFE_NO_PAD
+            .decode(expected.trim_end_matches('='))
+            .ok(),
+        BASE64_STANDARD.decode(expected).ok(),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .any(|decoded| decoded.ct_eq(digest).into())
+}
@@ -1,25 +1,33 @@
 //! Interface for webhooks that use asymmetric keys for signatures
 
-use rocket::{
-    Request,
-    data::Outcome,
-    http::Status,
-    outcome::try_outcome,
-    tokio::io::{AsyncRead, AsyncReadExt},
-};
+use rocket::tokio::io::{AsyncRead, AsyncReadExt};
 use tokio_util::bytes::Bytes;
 
 use crate::{
-    WebhookError,
-    webhooks::{Webhook, utils::body_size},
+    WebhookError, WebhookRequest,
+    webhooks::{ValidatedBody, Webhook, utils::body_size},
 };
 
 /// Public key algorithms
 pub mod algorithms;
+mod key;
+/// Public keys fetched from a remote URL
+#[cfg(feature = "jwks")]
+pub mod remote;
+
+pub use key::{EncodedPublicKey, InvalidPublicKey, Jwk, PublicKey};
 
 /// Trait for algorithms to use for asymmetric key verification
 pub trait WebhookPublicKeyAlgorithm {
-    fn verify(public_key: &Bytes, message: &[u8], signature: &[u8]) -> Result<(), String>;
+    /// The parsed public key used for verification
+    type Key: Send + Sync + 'static;
+
+    /// Parse and validate the public key. Keys should be parsed once (e.g. when the webhook is
+    /// created, using [PublicKey]), rather than for each request (see `benches/public_key.rs`).
+    fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<Self::Key, InvalidPublicKey>;
+
+    /// Verify the signature of the message using the parsed public key
+    fn verify(public_key: &Self::Key, message: &[u8], signature: &[u8]) -> Result<(), String>;
 }
 
 /// Trait for webhooks that use asymmetric keys for signatures
@@ -27,18 +35,34 @@ pub trait WebhookPublicKey: Webhook {
     /// Algorithm used for verification
     type ALG: WebhookPublicKeyAlgorithm;
 
-    /// Get the public key for the webhook signature. This is async in case the public key needs
-    /// to be fetched externally. The public key can be cached in Rocket state, accessible via
-    /// `req.rocket().state()`.
-    ///
-    /// Uses the [tokio_util::bytes::Bytes] struct to avoid unnecessary cloning.
+    /// Get the parsed public key for the webhook signature. This is async in case the public key
+    /// needs to be fetched externally.
     fn public_key(
         &self,
-        req: &Request<'_>,
-    ) -> impl Future<Output = Outcome<'_, Bytes, WebhookError>> + Send + Sync;
+        req: &dyn WebhookRequest,
+    ) -> impl Future<Output = Result<PublicKey<Self::ALG>, WebhookError>> + Send;
+
+    /// Get a new public key after the signature couldn't be verified with the `stale` key (e.g.
+    /// if the provider rotated its key). If a key is returned, the signature is verified again
+    /// with it. The default implementation returns `None`.
+    #[allow(unused_variables)]
+    fn refreshed_public_key(
+        &self,
+        req: &dyn WebhookRequest,
+        stale: &PublicKey<Self::ALG>,
+    ) -> impl Future<Output = Result<Option<PublicKey<Self::ALG>>, WebhookError>> + Send {
+        async { Ok(None) }
+    }
 
     /// Get the expected signature from the request
-    fn expected_signature(&self, req: &Request<'_>) -> Outcome<'_, Vec<u8>, WebhookError>;
+    fn expected_signature(&self, req: &dyn WebhookRequest) -> Result<Vec<u8>, WebhookError>;
+
+    /// Get all expected signatures from the request, for webhooks that can send multiple
+    /// signatures. The body is valid if any of them can be verified. The default implementation
+    /// returns the single [WebhookPublicKey::expected_signature].
+    fn expected_signatures(&self, req: &dyn WebhookRequest) -> Result<Vec<Vec<u8>>, WebhookError> {
+        Ok(vec![self.expected_signature(req)?])
+    }
 
     /// Get the message that needs to be verified. Any adjustments can be made to the body here
     /// before calculating the signature (e.g. prefixes or hashes, etc.)
@@ -47,44 +71,60 @@ pub trait WebhookPublicKey: Webhook {
     #[allow(unused_variables)]
     fn message_to_verify(
         &self,
-        req: &Request<'_>,
+        req: &dyn WebhookRequest,
         body: &Bytes,
         time_bounds: (u32, u32),
-    ) -> Outcome<'_, Bytes, WebhookError> {
-        Outcome::Success(body.clone())
+    ) -> Result<Bytes, WebhookError> {
+        Ok(body.clone())
     }
 
     /// Read the raw body and verify with the public key and configured algorithm
     fn validate_with_public_key(
         &self,
-        req: &Request<'_>,
-        mut body: impl AsyncRead + Unpin + Send + Sync,
+        req: &dyn WebhookRequest,
+        mut body: impl AsyncRead + Unpin + Send,
         time_bounds: (u32, u32),
-    ) -> impl Future<Output = Outcome<'_, Vec<u8>, WebhookError>> + Send + Sync
+    ) -> impl Future<Output = Result<ValidatedBody, WebhookError>> + Send
     where
         Self: Sync,
     {
         async move {
-            // Get expected signature from request
-            let expected_signature = try_outcome!(self.expected_signature(req));
+            // Get expected signatures from request
+            let expected_signatures = self.expected_signatures(req)?;
 
             // Get public key
-            let public_key = try_outcome!(self.public_key(req).await);
+            let public_key = self.public_key(req).await?;
 
             // Read body stream
-            let mut raw_body = Vec::with_capacity(body_size(req.headers()).unwrap_or(512));
-            if let Err(e) = body.read_to_end(&mut raw_body).await {
-                return Outcome::Error((Status::BadRequest, WebhookError::Read(e)));
-            }
+            let mut raw_body = Vec::with_capacity(body_size(req).unwrap_or(512));
+            body.read_to_end(&mut raw_body)
+                .await
+                .map_err(WebhookError::Read)?;
             let raw_body = Bytes::from(raw_body);
 
-            // Verify signature with public key
-            let message = try_outcome!(self.message_to_verify(req, &raw_body, time_bounds));
-            if let Err(e) = Self::ALG::verify(&public_key, &message, &expected_signature) {
-                return Outcome::Error((Status::Unauthorized, WebhookError::Signature(e)));
+            // Verify signatures with public key, until one is valid
+            let message = self.message_to_verify(req, &raw_body, time_bounds)?;
+            let verify = |public_key: &PublicKey<Self::ALG>| {
+                let mut verification = Err(String::from("No signatures provided"));
+                for signature in &expected_signatures {
+                    verification = Self::ALG::verify(public_key.key(), &message, signature);
+                    if verification.is_ok() {
+                        break;
+                    }
+                }
+                verification
+            };
+            let mut verification = verify(&public_key);
+
+            // Retry once with a refreshed key. If refreshing fails, the original error is kept.
+            if verification.is_err()
+                && let Ok(Some(refreshed_key)) = self.refreshed_public_key(req, &public_key).await
+            {
+                verification = verify(&refreshed_key);
             }
+            verification.map_err(WebhookError::Signature)?;
 
-            Outcome::Success(raw_body.into())
+            Ok(Vec::from(raw_body).into())
         }
     }
 }
@@ -1,37 +1,215 @@
-/// ECDSA P256 Algorithm
+/// ECDSA P256 Algorithm. Public keys can be SEC1 points, SPKI or JWK.
 #[cfg(feature = "p256")]
 pub mod p256 {
-    use p256::ecdsa::{Signature, VerifyingKey, signature::Verifier};
-    use tokio_util::bytes::Bytes;
+    use p256::{
+        ecdsa::{Signature, VerifyingKey, signature::Verifier},
+        pkcs8::DecodePublicKey,
+    };
 
-    use super::super::WebhookPublicKeyAlgorithm;
+    use super::super::{EncodedPublicKey, InvalidPublicKey, WebhookPublicKeyAlgorithm};
 
+    /// ECDSA P-256 with a DER-encoded (ASN.1) signature
     pub struct EcdsaP256Asn1;
     impl WebhookPublicKeyAlgorithm for EcdsaP256Asn1 {
-        fn verify(public_key: &Bytes, message: &[u8], signature: &[u8]) -> Result<(), String> {
-            let key = VerifyingKey::from_sec1_bytes(&public_key)
-                .map_err(|e| format!("Public key is invalid: {e}"))?;
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            parse_key(public_key)
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
             let signature = Signature::from_der(signature)
                 .map_err(|e| format!("Expected signature is invalid: {e}"))?;
-            key.verify(message, &signature)
-                .map_err(|e| format!("ECDSA P-256 verification failed: {e}"))
+            verify(key, message, &signature)
+        }
+    }
+
+    /// ECDSA P-256 with a fixed-size signature (`r || s`, 64 bytes)
+    pub struct EcdsaP256Fixed;
+    impl WebhookPublicKeyAlgorithm for EcdsaP256Fixed {
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            parse_key(public_key)
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            let signature = Signature::from_slice(signature)
+                .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+            verify(key, message, &signature)
+        }
+    }
+
+    fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+        let key = match public_key {
+            EncodedPublicKey::Der(der) => VerifyingKey::from_sec1_bytes(&der)
+                .or_else(|_| VerifyingKey::from_public_key_der(&der))
+                .map_err(|e| e.to_string()),
+            EncodedPublicKey::Jwk(jwk) => {
+                VerifyingKey::from_sec1_bytes(&jwk.ec_point("P-256")?).map_err(|e| e.to_string())
+            }
+        };
+        key.map_err(|e| InvalidPublicKey(format!("not a valid P-256 key: {e}")))
+    }
+
+    fn verify(key: &VerifyingKey, message: &[u8], signature: &Signature) -> Result<(), String> {
+        key.verify(message, signature)
+            .map_err(|e| format!("ECDSA P-256 verification failed: {e}"))
+    }
+}
+
+/// ECDSA P384 Algorithm. Public keys can be SEC1 points, SPKI or JWK.
+#[cfg(feature = "p384")]
+pub mod p384 {
+    use p384::{
+        ecdsa::{Signature, VerifyingKey, signature::Verifier},
+        pkcs8::DecodePublicKey,
+    };
+
+    use super::super::{EncodedPublicKey, InvalidPublicKey, WebhookPublicKeyAlgorithm};
+
+    /// ECDSA P-384 with a DER-encoded (ASN.1) signature
+    pub struct EcdsaP384Asn1;
+    impl WebhookPublicKeyAlgorithm for EcdsaP384Asn1 {
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            parse_key(public_key)
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            let signature = Signature::from_der(signature)
+                .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+            verify(key, message, &signature)
+        }
+    }
+
+    /// ECDSA P-384 with a fixed-size signature (`r || s`, 96 bytes)
+    pub struct EcdsaP384Fixed;
+    impl WebhookPublicKeyAlgorithm for EcdsaP384Fixed {
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            parse_key(public_key)
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            let signature = Signature::from_slice(signature)
+                .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+            verify(key, message, &signature)
+        }
+    }
+
+    fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+        let key = match public_key {
+            EncodedPublicKey::Der(der) => VerifyingKey::from_sec1_bytes(&der)
+                .or_else(|_| VerifyingKey::from_public_key_der(&der))
+                .map_err(|e| e.to_string()),
+            EncodedPublicKey::Jwk(jwk) => {
+                VerifyingKey::from_sec1_bytes(&jwk.ec_point("P-384")?).map_err(|e| e.to_string())
+            }
+        };
+        key.map_err(|e| InvalidPublicKey(format!("not a valid P-384 key: {e}")))
+    }
+
+    fn verify(key: &VerifyingKey, message: &[u8], signature: &Signature) -> Result<(), String> {
+        key.verify(message, signature)
+            .map_err(|e| format!("ECDSA P-384 verification failed: {e}"))
+    }
+}
+
+/// ECDSA secp256k1 Algorithm. Public keys can be SEC1 points, SPKI or JWK.
+#[cfg(feature = "k256")]
+pub mod k256 {
+    use k256::{
+        ecdsa::{Signature, VerifyingKey, signature::Verifier},
+        pkcs8::DecodePublicKey,
+    };
+
+    use super::super::{EncodedPublicKey, InvalidPublicKey, WebhookPublicKeyAlgorithm};
+
+    /// ECDSA secp256k1 with a DER-encoded (ASN.1) signature
+    pub struct EcdsaSecp256k1Asn1;
+    impl WebhookPublicKeyAlgorithm for EcdsaSecp256k1Asn1 {
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            parse_key(public_key)
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            let signature = Signature::from_der(signature)
+                .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+            verify(key, message, signature)
+        }
+    }
+
+    /// ECDSA secp256k1 with a fixed-size signature (`r || s`, 64 bytes)
+    pub struct EcdsaSecp256k1Fixed;
+    impl WebhookPublicKeyAlgorithm for EcdsaSecp256k1Fixed {
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            parse_key(public_key)
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            let signature = Signature::from_slice(signature)
+                .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+            verify(key, message, signature)
         }
     }
+
+    fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+        let key = match public_key {
+            EncodedPublicKey::Der(der) => VerifyingKey::from_sec1_bytes(&der)
+                .or_else(|_| VerifyingKey::from_public_key_der(&der))
+                .map_err(|e| e.to_string()),
+            EncodedPublicKey::Jwk(jwk) => {
+                VerifyingKey::from_sec1_bytes(&jwk.ec_point("secp256k1")?)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        key.map_err(|e| InvalidPublicKey(format!("not a valid secp256k1 key: {e}")))
+    }
+
+    fn verify(key: &VerifyingKey, message: &[u8], signature: Signature) -> Result<(), String> {
+        // secp256k1 verification only accepts low-S signatures, but senders don't always normalize
+        let signature = signature.normalize_s().unwrap_or(signature);
+        key.verify(message, &signature)
+            .map_err(|e| format!("ECDSA secp256k1 verification failed: {e}"))
+    }
 }
 
-/// ED25519 Algorithm
+/// ED25519 Algorithm. Public keys can be the raw 32 bytes, SPKI or JWK.
 #[cfg(feature = "ed25519")]
 pub mod ed25519 {
-    use ed25519_dalek::{Signature, Verifier, VerifyingKey};
-    use tokio_util::bytes::Bytes;
+    use ed25519_dalek::{Signature, Verifier, VerifyingKey, pkcs8::DecodePublicKey};
 
-    use super::super::WebhookPublicKeyAlgorithm;
+    use super::super::{EncodedPublicKey, InvalidPublicKey, WebhookPublicKeyAlgorithm};
 
+    /// Ed25519 with a 64-byte signature
     pub struct Ed25519;
     impl WebhookPublicKeyAlgorithm for Ed25519 {
-        fn verify(public_key: &Bytes, message: &[u8], signature: &[u8]) -> Result<(), String> {
-            let key = VerifyingKey::try_from(public_key.as_ref())
-                .map_err(|e| format!("Public key is invalid: {e}"))?;
+        type Key = VerifyingKey;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<VerifyingKey, InvalidPublicKey> {
+            let key = match public_key {
+                EncodedPublicKey::Der(der) if der.len() == 32 => {
+                    VerifyingKey::try_from(&*der).map_err(|e| e.to_string())
+                }
+                EncodedPublicKey::Der(der) => {
+                    VerifyingKey::from_public_key_der(&der).map_err(|e| e.to_string())
+                }
+                EncodedPublicKey::Jwk(jwk) => {
+                    jwk.expect_type("OKP", Some("Ed25519"))?;
+                    VerifyingKey::try_from(jwk.param("x")?.as_slice()).map_err(|e| e.to_string())
+                }
+            };
+            key.map_err(|e| InvalidPublicKey(format!("not a valid Ed25519 key: {e}")))
+        }
+
+        fn verify(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> Result<(), String> {
             let signature = Signature::from_slice(signature)
                 .map_err(|e| format!("Expected signature is invalid: {e}"))?;
             key.verify(message, &signature)
@@ -39,3 +217,95 @@ pub mod ed25519 {
         }
     }
 }
+
+/// RSA Algorithms (PKCS#1 v1.5 and PSS). Public keys can be PKCS#1, SPKI or JWK.
+#[cfg(feature = "rsa")]
+pub mod rsa {
+    use rsa::{
+        BigUint, RsaPublicKey,
+        pkcs1::DecodeRsaPublicKey,
+        pkcs1v15,
+        pkcs8::{AssociatedOid, DecodePublicKey},
+        pss,
+        signature::{Verifier, digest::Digest},
+    };
+    use sha1::Sha1;
+    use sha2::Sha256;
+
+    use super::super::{EncodedPublicKey, InvalidPublicKey, WebhookPublicKeyAlgorithm};
+
+    /// RSA PKCS#1 v1.5 with SHA-256 (e.g. `RS256` JSON Web Tokens)
+    pub struct RsaPkcs1v15Sha256;
+    impl WebhookPublicKeyAlgorithm for RsaPkcs1v15Sha256 {
+        type Key = pkcs1v15::VerifyingKey<Sha256>;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<Self::Key, InvalidPublicKey> {
+            parse_key(public_key).map(pkcs1v15::VerifyingKey::new)
+        }
+
+        fn verify(key: &Self::Key, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            verify_pkcs1v15(key, message, signature)
+        }
+    }
+
+    /// Legacy SHA-1 variant, used by some older providers (e.g. AWS SNS signature version 1)
+    pub struct RsaPkcs1v15Sha1;
+    impl WebhookPublicKeyAlgorithm for RsaPkcs1v15Sha1 {
+        type Key = pkcs1v15::VerifyingKey<Sha1>;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<Self::Key, InvalidPublicKey> {
+            parse_key(public_key).map(pkcs1v15::VerifyingKey::new)
+        }
+
+        fn verify(key: &Self::Key, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            verify_pkcs1v15(key, message, signature)
+        }
+    }
+
+    /// RSA-PSS with SHA-256, expecting a salt of the same length as the digest
+    pub struct RsaPssSha256;
+    impl WebhookPublicKeyAlgorithm for RsaPssSha256 {
+        type Key = pss::VerifyingKey<Sha256>;
+
+        fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<Self::Key, InvalidPublicKey> {
+            parse_key(public_key).map(pss::VerifyingKey::new)
+        }
+
+        fn verify(key: &Self::Key, message: &[u8], signature: &[u8]) -> Result<(), String> {
+            let signature = pss::Signature::try_from(signature)
+                .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+            key.verify(message, &signature)
+                .map_err(|e| format!("RSA-PSS verification failed: {e}"))
+        }
+    }
+
+    fn verify_pkcs1v15<D>(
+        key: &pkcs1v15::VerifyingKey<D>,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), String>
+    where
+        D: Digest + AssociatedOid,
+    {
+        let signature = pkcs1v15::Signature::try_from(signature)
+            .map_err(|e| format!("Expected signature is invalid: {e}"))?;
+        key.verify(message, &signature)
+            .map_err(|e| format!("RSA PKCS#1 v1.5 verification failed: {e}"))
+    }
+
+    /// Parse a SPKI, PKCS#1 or JWK public key
+    fn parse_key(public_key: EncodedPublicKey<'_>) -> Result<RsaPublicKey, InvalidPublicKey> {
+        let key = match public_key {
+            EncodedPublicKey::Der(der) => RsaPublicKey::from_public_key_der(&der)
+                .or_else(|_| RsaPublicKey::from_pkcs1_der(&der))
+                .map_err(|e| e.to_string()),
+            EncodedPublicKey::Jwk(jwk) => {
+                jwk.expect_type("RSA", None)?;
+                let n = BigUint::from_bytes_be(&jwk.param("n")?);
+                let e = BigUint::from_bytes_be(&jwk.param("e")?);
+                RsaPublicKey::new(n, e).map_err(|e| e.to_string())
+            }
+        };
+        key.map_err(|e| InvalidPublicKey(format!("not a valid RSA key: {e}")))
+    }
+}
@@ -0,0 +1,210 @@
+use std::{borrow::Cow, error::Error, fmt::Display, sync::Arc};
+
+use base64::{
+    Engine,
+    prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+};
+use rocket::serde::{Deserialize, json::serde_json};
+
+use super::WebhookPublicKeyAlgorithm;
+
+/// Error when a public key can't be decoded, or is not valid for the algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidPublicKey(pub String);
+
+impl Display for InvalidPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Public key is invalid: {}", self.0)
+    }
+}
+
+impl Error for InvalidPublicKey {}
+
+/**
+A public key that has been parsed and validated for the algorithm `A`, so it doesn't need to be
+parsed again for each request. This is cheap to clone.
+
+Keys can be provided in most of the formats handed out by providers:
+- PEM (e.g. a `PUBLIC KEY` block containing SPKI DER, or a `RSA PUBLIC KEY` block)
+- JSON Web Key (JWK)
+- Binary: SPKI DER, or the algorithm's own encoding (e.g. a SEC1 point for ECDSA, the raw
+  32 bytes for Ed25519, or PKCS#1 DER for RSA)
+
+# Example
+```
+use rocket_webhook::webhooks::interface::public_key::{
+    PublicKey, algorithms::ed25519::Ed25519,
+};
+
+let key = PublicKey::<Ed25519>::parse(
+    r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+);
+assert!(key.is_ok());
+
+let key = PublicKey::<Ed25519>::from_hex("not a key");
+assert!(key.is_err());
+```
+*/
+pub struct PublicKey<A: WebhookPublicKeyAlgorithm>(Arc<A::Key>);
+
+impl<A: WebhookPublicKeyAlgorithm> PublicKey<A> {
+    /// Parse a public key in PEM, JWK or binary format
+    pub fn parse(public_key: impl AsRef<[u8]>) -> Result<Self, InvalidPublicKey> {
+        Self::from_encoded(EncodedPublicKey::detect(public_key.as_ref())?)
+    }
+
+    /// Parse a base64-encoded binary public key. Keys in PEM or JWK format are also accepted.
+    pub fn from_base64(public_key: impl AsRef<str>) -> Result<Self, InvalidPublicKey> {
+        let public_key = public_key.as_ref().trim();
+        if is_text_format(public_key) {
+            return Self::parse(public_key);
+        }
+        let decoded = BASE64_STANDARD
+            .decode(public_key)
+            .map_err(|e| InvalidPublicKey(format!("not valid base64: {e}")))?;
+        Self::parse(decoded)
+    }
+
+    /// Parse a hex-encoded binary public key. Keys in PEM or JWK format are also accepted.
+    pub fn from_hex(public_key: impl AsRef<str>) -> Result<Self, InvalidPublicKey> {
+        let public_key = public_key.as_ref().trim();
+        if is_text_format(public_key) {
+            return Self::parse(public_key);
+        }
+        let decoded =
+            hex::decode(public_key).map_err(|e| InvalidPublicKey(format!("not valid hex: {e}")))?;
+        Self::parse(decoded)
+    }
+
+    /// Parse a public key from a JSON Web Key
+    pub fn from_jwk(jwk: &Jwk) -> Result<Self, InvalidPublicKey> {
+        Self::from_encoded(EncodedPublicKey::Jwk(Cow::Borrowed(jwk)))
+    }
+
+    /// Parse a decoded public key
+    pub fn from_encoded(public_key: EncodedPublicKey<'_>) -> Result<Self, InvalidPublicKey> {
+        A::parse_key(public_key).map(|key| Self(Arc::new(key)))
+    }
+
+    /// The parsed key
+    pub fn key(&self) -> &A::Key {
+        &self.0
+    }
+
+    /// Whether both are clones of the same parsed key
+    #[cfg(feature = "jwks")]
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl<A: WebhookPublicKeyAlgorithm> Clone for PublicKey<A> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// A public key decoded from its text format, to be parsed by the algorithm
+pub enum EncodedPublicKey<'a> {
+    /// Binary key: SPKI DER (e.g. from a PEM `PUBLIC KEY` block), or an algorithm-specific
+    /// encoding (e.g. a SEC1 point, the raw Ed25519 key, or PKCS#1 DER)
+    Der(Cow<'a, [u8]>),
+    /// JSON Web Key
+    Jwk(Cow<'a, Jwk>),
+}
+
+impl<'a> EncodedPublicKey<'a> {
+    /// Detect the format of the public key (PEM, JWK, or binary) and decode it
+    pub fn detect(public_key: &'a [u8]) -> Result<Self, InvalidPublicKey> {
+        let text = std::str::from_utf8(public_key).map(str::trim);
+        match text {
+            Ok(pem) if pem.starts_with("-----BEGIN") => Ok(Self::Der(Cow::Owned(decode_pem(pem)?))),
+            Ok(json) if json.starts_with('{') => {
+                let jwk = serde_json::from_str(json)
+                    .map_err(|e| InvalidPublicKey(format!("not a valid JWK: {e}")))?;
+                Ok(Self::Jwk(Cow::Owned(jwk)))
+            }
+            _ => Ok(Self::Der(Cow::Borrowed(public_key))),
+        }
+    }
+}
+
+/// Public parameters of a JSON Web Key ([RFC 7517](https://datatracker.ietf.org/doc/html/rfc7517))
+#[derive(Debug, Clone, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Jwk {
+    /// Key type (e.g. `EC`, `RSA`, `OKP`)
+    pub kty: String,
+    /// Key ID
+    pub kid: Option<String>,
+    /// Algorithm intended for use with the key (e.g. `ES256`)
+    pub alg: Option<String>,
+    /// Curve, for `EC` and `OKP` keys
+    pub crv: Option<String>,
+    /// X coordinate (`EC`), or the public key (`OKP`)
+    pub x: Option<String>,
+    /// Y coordinate (`EC`)
+    pub y: Option<String>,
+    /// Modulus (`RSA`)
+    pub n: Option<String>,
+    /// Exponent (`RSA`)
+    pub e: Option<String>,
+}
+
+impl Jwk {
+    /// Check the key type and curve (if any) of the key
+    pub fn expect_type(&self, kty: &str, crv: Option<&str>) -> Result<(), InvalidPublicKey> {
+        if self.kty != kty || (crv.is_some() && self.crv.as_deref() != crv) {
+            return Err(InvalidPublicKey(format!(
+                "expected JWK with type '{kty}' and curve '{}', got '{}' and '{}'",
+                crv.unwrap_or_default(),
+                self.kty,
+                self.crv.as_deref().unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get a decoded parameter of the key (e.g. `x` or `n`)
+    pub fn param(&self, name: &str) -> Result<Vec<u8>, InvalidPublicKey> {
+        let value = match name {
+            "x" => &self.x,
+            "y" => &self.y,
+            "n" => &self.n,
+            "e" => &self.e,
+            _ => &None,
+        };
+        let value = value
+            .as_deref()
+            .ok_or_else(|| InvalidPublicKey(format!("JWK is missing '{name}'")))?;
+        BASE64_URL_SAFE_NO_PAD
+            .decode(value.trim_end_matches('='))
+            .map_err(|e| InvalidPublicKey(format!("JWK parameter '{name}' is invalid: {e}")))
+    }
+
+    /// Get the uncompressed SEC1 point of an `EC` key with the given curve
+    pub fn ec_point(&self, crv: &str) -> Result<Vec<u8>, InvalidPublicKey> {
+        self.expect_type("EC", Some(crv))?;
+        Ok([vec![0x04], self.param("x")?, self.param("y")?].concat())
+    }
+}
+
+/// Whether the key is in a text format (PEM or JWK), rather than an encoded binary key
+fn is_text_format(public_key: &str) -> bool {
+    public_key.starts_with("-----BEGIN") || public_key.starts_with('{')
+}
+
+/// Decode the contents of the first PEM block
+fn decode_pem(pem: &str) -> Result<Vec<u8>, InvalidPublicKey> {
+    let contents: String = pem
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| !line.starts_with("-----BEGIN"))
+        .skip(1)
+        .take_while(|line| !line.starts_with("-----END"))
+        .filter(|line| !line.contains(':'))
+        .collect();
+    BASE64_STANDARD
+        .decode(contents)
+        .map_err(|e| InvalidPublicKey(format!("PEM is not valid: {e}")))
+}
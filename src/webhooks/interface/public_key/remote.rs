@@ -0,0 +1,263 @@
+//! Public keys fetched from a remote URL
+
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use bon::Builder;
+use rocket::{
+    async_trait,
+    serde::{Deserialize, json::serde_json},
+    tokio::sync::Mutex,
+};
+
+use super::{Jwk, PublicKey, WebhookPublicKeyAlgorithm};
+use crate::WebhookError;
+
+/// Trait for the HTTP client used to fetch public keys. This is implemented for [reqwest::Client]
+/// when the `jwks-reqwest` feature is enabled.
+#[async_trait]
+pub trait KeyFetcher: Send + Sync {
+    /// Send a GET request to the URL and return the response body. Return an error if no
+    /// successful response was received.
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, String>;
+}
+
+#[cfg(feature = "jwks-reqwest")]
+#[async_trait]
+impl KeyFetcher for reqwest::Client {
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, String> {
+        let response = self
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| err.to_string())?;
+        match response.bytes().await {
+            Ok(body) => Ok(body.into()),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+/**
+Public keys that are fetched from a URL, for providers that publish their keys (e.g. as a JSON Web
+Key Set) and rotate them. The URL can return a JWKS (`{"keys": [...]}`), a single JWK, or a key in
+PEM format. Keys that aren't valid for the algorithm `A` are ignored.
+
+The keys are cached, and fetched again once the `ttl` expires. If a request uses a key ID that
+isn't in the cache, or the signature can't be verified with the cached key, the keys are
+fetched again (at most once per `refetch_interval`). Concurrent requests share a single fetch.
+If the keys can't be fetched again, the cached keys are still used, and the fetch is retried
+after the `refetch_interval`.
+
+Use with the [generic public key webhook](crate::webhooks::generic::PublicKeyWebhook), or in
+your own [WebhookPublicKey](super::WebhookPublicKey) implementation.
+
+# Example
+```
+# #[cfg(all(feature = "jwks-reqwest", feature = "p256"))] {
+use std::time::Duration;
+use rocket_webhook::webhooks::{
+    generic::EcdsaP256Webhook, interface::public_key::remote::RemotePublicKeys,
+};
+
+let keys = RemotePublicKeys::builder()
+    .url("https://example.com/.well-known/jwks.json")
+    .fetcher(reqwest::Client::new())
+    .ttl(Duration::from_secs(15 * 60))
+    .build();
+let my_webhook = EcdsaP256Webhook::builder()
+    .remote_keys(keys)
+    .key_id(|req| req.header("Key-Id").map(str::to_owned))
+    .expected_signatures(|req| {
+        req.header("Signature")
+            .and_then(|header| hex::decode(header).ok())
+            .map(|header| vec![header])
+    })
+    .build();
+# }
+```
+*/
+#[derive(Builder)]
+pub struct RemotePublicKeys<A: WebhookPublicKeyAlgorithm> {
+    /// URL of the public key(s)
+    #[builder(into)]
+    url: String,
+    /// HTTP client used to fetch the keys
+    #[builder(with = |fetcher: impl KeyFetcher + 'static| Box::new(fetcher) as Box<dyn KeyFetcher>)]
+    fetcher: Box<dyn KeyFetcher>,
+    /// How long the keys are cached before being fetched again. Defaults to 1 hour.
+    #[builder(default = Duration::from_secs(60 * 60))]
+    ttl: Duration,
+    /// Minimum time between fetches caused by an unknown key ID or a failed verification, so
+    /// invalid requests can't flood the URL. Defaults to 1 minute.
+    #[builder(default = Duration::from_secs(60))]
+    refetch_interval: Duration,
+    #[builder(skip)]
+    cache: RwLock<Option<Arc<KeySet<A>>>>,
+    #[builder(skip)]
+    fetch_lock: Mutex<()>,
+}
+
+/// A key and its ID
+type KeyWithId<A> = (Option<String>, PublicKey<A>);
+
+/// A set of fetched keys
+struct KeySet<A: WebhookPublicKeyAlgorithm> {
+    keys: Vec<KeyWithId<A>>,
+    /// When the keys were last fetched (or a fetch was last attempted)
+    fetched_at: Instant,
+    /// When the keys need to be fetched again
+    expires_at: Instant,
+}
+
+impl<A: WebhookPublicKeyAlgorithm> RemotePublicKeys<A> {
+    /// Get the key with the given ID (or the first key, if no ID is given or the keys don't
+    /// have IDs). The keys are fetched if they aren't cached or have expired, and fetched again
+    /// if the key ID is unknown.
+    pub async fn key(&self, kid: Option<&str>) -> Result<PublicKey<A>, WebhookError> {
+        let cached = self.cached();
+        if let Some(keys) = &cached
+            && Instant::now() < keys.expires_at
+        {
+            if let Some(key) = keys.find(kid) {
+                return Ok(key);
+            }
+            if keys.fetched_at.elapsed() < self.refetch_interval {
+                return Err(unknown_key(kid));
+            }
+        }
+        self.fetch(cached.as_ref())
+            .await?
+            .find(kid)
+            .ok_or_else(|| unknown_key(kid))
+    }
+
+    /// Get a new key after a signature couldn't be verified with the `stale` key (e.g. the
+    /// provider rotated the key without changing its ID). The keys are fetched again if needed.
+    /// Returns `None` if there is no different key.
+    pub async fn refresh(
+        &self,
+        kid: Option<&str>,
+        stale: &PublicKey<A>,
+    ) -> Result<Option<PublicKey<A>>, WebhookError> {
+        self.refresh_where(kid, |key| key.ptr_eq(stale)).await
+    }
+
+    /// Get a new key after a signature couldn't be verified, where `is_stale` matches the key
+    /// that was used (see [RemotePublicKeys::refresh])
+    pub(crate) async fn refresh_where(
+        &self,
+        kid: Option<&str>,
+        is_stale: impl Fn(&PublicKey<A>) -> bool + Send + Sync,
+    ) -> Result<Option<PublicKey<A>>, WebhookError> {
+        let cached = self.cached();
+        if let Some(keys) = &cached {
+            // The keys may have been fetched again since the stale key was returned
+            if let Some(key) = keys.find(kid).filter(|key| !is_stale(key)) {
+                return Ok(Some(key));
+            }
+            if keys.fetched_at.elapsed() < self.refetch_interval {
+                return Ok(None);
+            }
+        }
+        let keys = self.fetch(cached.as_ref()).await?;
+        Ok(keys.find(kid).filter(|key| !is_stale(key)))
+    }
+
+    /// The currently cached keys
+    fn cached(&self) -> Option<Arc<KeySet<A>>> {
+        self.cache.read().expect("lock is not poisoned").clone()
+    }
+
+    /// Fetch the keys and update the cache. If the cache was updated by another request while
+    /// waiting (i.e. it no longer holds the `seen` keys), those keys are used instead. If the
+    /// keys can't be fetched, the cached keys are kept until the next `refetch_interval`.
+    async fn fetch(&self, seen: Option<&Arc<KeySet<A>>>) -> Result<Arc<KeySet<A>>, WebhookError> {
+        let _guard = self.fetch_lock.lock().await;
+        let cached = self.cached();
+        if let Some(keys) = &cached
+            && !seen.is_some_and(|seen| Arc::ptr_eq(seen, keys))
+        {
+            return Ok(keys.clone());
+        }
+
+        let now = Instant::now();
+        let keys = match (self.fetch_keys().await, cached) {
+            (Ok(keys), _) => KeySet {
+                keys,
+                fetched_at: now,
+                expires_at: now + self.ttl,
+            },
+            (Err(_), Some(stale)) => KeySet {
+                keys: stale.keys.clone(),
+                fetched_at: now,
+                expires_at: now + self.refetch_interval,
+            },
+            (Err(err), None) => return Err(err),
+        };
+        let keys = Arc::new(keys);
+        *self.cache.write().expect("lock is not poisoned") = Some(keys.clone());
+        Ok(keys)
+    }
+
+    /// Fetch and parse the keys from the URL
+    async fn fetch_keys(&self) -> Result<Vec<KeyWithId<A>>, WebhookError> {
+        let document = self.fetcher.fetch(&self.url).await.map_err(|e| {
+            WebhookError::Secret(format!(
+                "Failed to fetch public keys from '{}': {e}",
+                self.url
+            ))
+        })?;
+        parse_keys(&document).map_err(|e| {
+            WebhookError::Secret(format!("Public keys from '{}' are invalid: {e}", self.url))
+        })
+    }
+}
+
+impl<A: WebhookPublicKeyAlgorithm> KeySet<A> {
+    /// Find the key with the ID. If the keys don't have IDs, the first key is used.
+    fn find(&self, kid: Option<&str>) -> Option<PublicKey<A>> {
+        let has_ids = self.keys.iter().any(|(id, _)| id.is_some());
+        let key = match kid {
+            Some(kid) if has_ids => self.keys.iter().find(|(id, _)| id.as_deref() == Some(kid)),
+            _ => self.keys.first(),
+        };
+        key.map(|(_, key)| key.clone())
+    }
+}
+
+/// JSON Web Key Set
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+/// Parse the keys from a JWKS, JWK, or a key in PEM/binary format
+fn parse_keys<A: WebhookPublicKeyAlgorithm>(document: &[u8]) -> Result<Vec<KeyWithId<A>>, String> {
+    if let Ok(set) = serde_json::from_slice::<JwkSet>(document) {
+        let keys: Vec<_> = (set.keys.iter())
+            .filter_map(|jwk| Some((jwk.kid.clone(), PublicKey::from_jwk(jwk).ok()?)))
+            .collect();
+        if keys.is_empty() {
+            return Err("no valid keys for the algorithm in JWKS".into());
+        }
+        return Ok(keys);
+    }
+    if let Ok(jwk) = serde_json::from_slice::<Jwk>(document) {
+        let key = PublicKey::from_jwk(&jwk).map_err(|e| e.to_string())?;
+        return Ok(vec![(jwk.kid, key)]);
+    }
+    let key = PublicKey::parse(document).map_err(|e| e.to_string())?;
+    Ok(vec![(None, key)])
+}
+
+fn unknown_key(kid: Option<&str>) -> WebhookError {
+    WebhookError::Signature(format!(
+        "No public key found with ID '{}'",
+        kid.unwrap_or_default()
+    ))
+}
@@ -1,8 +1,169 @@
 //! Internal utilities
 
+use std::{
+    pin::{Pin, pin},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use rocket::{
+    serde::json::{Value, serde_json},
+    tokio::io::{AsyncRead, ReadBuf},
+};
+
+use crate::WebhookRequest;
+
 /// Try reading the body size from the content length header
-pub fn body_size(headers: &rocket::http::HeaderMap) -> Option<usize> {
-    headers
-        .get_one("Content-Length")
+pub fn body_size(req: &dyn WebhookRequest) -> Option<usize> {
+    req.header("Content-Length")
         .and_then(|len| len.parse().ok())
 }
+
+/// Get a top-level string field from a JSON body (e.g. the event type)
+#[allow(dead_code)]
+pub fn json_str_field(body: &[u8], field: &str) -> Option<String> {
+    let value: Value = serde_json::from_slice(body).ok()?;
+    value.get(field)?.as_str().map(str::to_owned)
+}
+
+/// Delivery ID for webhooks without one, made from the signed timestamp and a SHA-256 digest of
+/// the body. Unlike the signature header, this can't be reformatted without failing verification.
+#[cfg(any(
+    feature = "discord",
+    feature = "sendgrid",
+    feature = "slack",
+    feature = "stripe",
+    feature = "zoom"
+))]
+pub fn signed_content_id(timestamp: &str, body: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{timestamp}.{}", hex::encode(Sha256::digest(body)))
+}
+
+#[cfg(any(feature = "sns", feature = "twitch"))]
+/// Parse an RFC 3339 timestamp (e.g. `2023-10-15T08:30:45.123456789Z`) into Unix epoch seconds
+pub fn parse_rfc3339(timestamp: &str) -> Option<u32> {
+    let (date, time) = timestamp.split_once(['T', 't'])?;
+    let mut date_parts = date.splitn(3, '-').map(str::parse::<i64>);
+    let (year, month, day) = (
+        date_parts.next()?.ok()?,
+        date_parts.next()?.ok()?,
+        date_parts.next()?.ok()?,
+    );
+
+    // Split off the UTC offset
+    let (time, offset_secs) = if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        (time, 0)
+    } else {
+        let offset_start = time.rfind(['+', '-'])?;
+        let (time, offset) = time.split_at(offset_start);
+        let (hours, minutes) = offset[1..].split_once(':')?;
+        let offset_secs = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (
+            time,
+            if offset.starts_with('-') {
+                -offset_secs
+            } else {
+                offset_secs
+            },
+        )
+    };
+    let mut time_parts = time.splitn(3, ':');
+    let hours = time_parts.next()?.parse::<i64>().ok()?;
+    let minutes = time_parts.next()?.parse::<i64>().ok()?;
+    let seconds = time_parts.next()?.split('.').next()?.parse::<i64>().ok()?;
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hours > 23
+        || minutes > 59
+        || seconds > 60
+    {
+        return None;
+    }
+
+    // Days since the Unix epoch (http://howardhinnant.github.io/date_algorithms.html#days_from_civil)
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let unix_secs = days * 86400 + hours * 3600 + minutes * 60 + seconds - offset_secs;
+    u32::try_from(unix_secs).ok()
+}
+
+/// Get the current Unix epoch time in seconds
+pub fn unix_time() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32 // Safe to use u32 until 2106
+}
+
+/// Body reader that flags when more than `limit` bytes were read. The underlying
+/// stream should be opened with a limit of at least `limit + 1` bytes, so that a
+/// body that was cut off can be detected.
+pub struct LimitedBody<R> {
+    inner: R,
+    limit: u64,
+    read: u64,
+    exceeded: Arc<AtomicBool>,
+}
+
+impl<R> LimitedBody<R> {
+    /// Wrap the reader, returning a flag that is set once the limit is exceeded
+    pub fn new(inner: R, limit: u64) -> (Self, Arc<AtomicBool>) {
+        let exceeded = Arc::new(AtomicBool::new(false));
+        let body = Self {
+            inner,
+            limit,
+            read: 0,
+            exceeded: exceeded.clone(),
+        };
+        (body, exceeded)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for LimitedBody<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let filled_before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            self.read += (buf.filled().len() - filled_before) as u64;
+            if self.read > self.limit {
+                self.exceeded.store(true, Ordering::Relaxed);
+            }
+        }
+        poll
+    }
+}
+
+/// Run a future to completion on the current thread, parking the thread while the future is pending
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
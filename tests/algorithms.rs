@@ -0,0 +1,216 @@
+//! Tests for the public key algorithms
+
+use rocket_webhook::webhooks::interface::public_key::{
+    PublicKey, WebhookPublicKeyAlgorithm,
+    algorithms::{
+        ed25519::Ed25519,
+        k256::{EcdsaSecp256k1Asn1, EcdsaSecp256k1Fixed},
+        p256::{EcdsaP256Asn1, EcdsaP256Fixed},
+        p384::{EcdsaP384Asn1, EcdsaP384Fixed},
+        rsa::{RsaPkcs1v15Sha1, RsaPkcs1v15Sha256, RsaPssSha256},
+    },
+};
+
+const RSA_MESSAGE: &[u8] = b"Hello RSA";
+const RSA_SPKI_PEM: &str = "-----BEGIN PUBLIC KEY-----
+MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQCdkXpVM3x3KO8t0Ptr53pa99er
+g7pYfOba/r9w6rlrrlGeuWU7v7XCgfzT0/oswNGy6DQ8LBfQfLx9xVuPWyW1ORhr
+oFlQLXOFRHTmQnPdpe9yk+321fOIsHYT/bs9i6cYfwRuSuDrzBYKn6sFSgsPDzE3
+CMhAnPgqylZSnnpW+wIDAQAB
+-----END PUBLIC KEY-----
+";
+const RSA_PKCS1_DER: &str = "308189028181009d917a55337c7728ef2dd0fb6be77a5af7d7ab83ba587ce6dafebf70\
+    eab96bae519eb9653bbfb5c281fcd3d3fa2cc0d1b2e8343c2c17d07cbc7dc55b8f5b25b539186ba059502d73854474e6\
+    4273dda5ef7293edf6d5f388b07613fdbb3d8ba7187f046e4ae0ebcc160a9fab054a0b0f0f313708c8409cf82aca5652\
+    9e7a56fb0203010001";
+const RSA_SHA256_SIGNATURE: &str = "0e8fc740556987f5dc258c39ac57f0dd8afffb69ac73c5eca3ddb376b200d719\
+    d75fd7bd93dbdd8f72a65e29b35c09eb0fdfcd49482ea0f7a614c4791480ad4b67ce19c28af56b59c2705038a32cf441\
+    419f178079c1058d4acf10dc8d8d48cc906d6483b7b4a89c341e4d67f721d7d2c1867f93a1fc40670832f2639f0706a8";
+const RSA_SHA1_SIGNATURE: &str = "7ff4492b6f6a0d7ea3652687ec889406649017821d476da663f8c5911604161b\
+    b17bbce2591c456c30af06e93eae7774d194ee441dcb77b8e9ffcfaf4c9c0530080b7873beef73c6422b2694c09e8160\
+    54d9b9e65b8f9642adf5edc7c4cb497fede161a5e7a7448decfa33b01b10e61936528fabf146beaf0b59e8b31d7a7deb";
+const RSA_PSS_SIGNATURE: &str = "323bd7a2e307598e4d03748ed472ef56d2046a2f0b90299df674cbe6383a55bd\
+    b96c6895d00dcf6da31a0452a43e181af2775d465fe97744cd806838d0fee1c8999c2e12aebd90edd99c613ca7984669\
+    beef3f7676244cdf70327228fc4178d85e1055a4a07dcbefe13e14e295551a2234a00ae2907823fced97b115a417c29e";
+
+const RSA_JWK: &str = concat!(
+    r#"{"kty":"RSA","e":"AQAB","n":"nZF6VTN8dyjvLdD7a-d6WvfXq4O6WHzm2v6_cOq5a65R"#,
+    "nrllO7-1woH809P6LMDRsug0PCwX0Hy8fcVbj1sltTkYa6BZUC1zhUR05kJz3aXvcpPt9tXziLB2E_27PYunGH8Ebkrg68wWCp",
+    r#"-rBUoLDw8xNwjIQJz4KspWUp56Vvs"}"#
+);
+
+/// Verify the signature using the parsed key
+fn verify<A: WebhookPublicKeyAlgorithm>(
+    key: &PublicKey<A>,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), String> {
+    A::verify(key.key(), message, signature)
+}
+
+/// Parse the RSA test key in all supported formats
+fn rsa_keys<A: WebhookPublicKeyAlgorithm>() -> [PublicKey<A>; 4] {
+    [
+        PublicKey::parse(RSA_SPKI_PEM).unwrap(),
+        PublicKey::from_hex(RSA_PKCS1_DER).unwrap(),
+        PublicKey::parse(hex::decode(RSA_PKCS1_DER).unwrap()).unwrap(),
+        PublicKey::parse(RSA_JWK).unwrap(),
+    ]
+}
+
+#[test]
+fn rsa_pkcs1v15() {
+    let sha256_sig = hex::decode(RSA_SHA256_SIGNATURE).unwrap();
+    let sha1_sig = hex::decode(RSA_SHA1_SIGNATURE).unwrap();
+
+    for key in rsa_keys::<RsaPkcs1v15Sha256>() {
+        assert_eq!(verify(&key, RSA_MESSAGE, &sha256_sig), Ok(()));
+        assert!(verify(&key, b"Hello RSB", &sha256_sig).is_err());
+        assert!(verify(&key, RSA_MESSAGE, &sha1_sig).is_err());
+    }
+    for key in rsa_keys::<RsaPkcs1v15Sha1>() {
+        assert_eq!(verify(&key, RSA_MESSAGE, &sha1_sig), Ok(()));
+        assert!(verify(&key, RSA_MESSAGE, &sha256_sig).is_err());
+    }
+
+    let err = PublicKey::<RsaPkcs1v15Sha256>::parse("not a key")
+        .err()
+        .unwrap();
+    assert!(err.to_string().starts_with("Public key is invalid"));
+}
+
+#[test]
+fn rsa_pss() {
+    let pss_sig = hex::decode(RSA_PSS_SIGNATURE).unwrap();
+
+    for key in rsa_keys::<RsaPssSha256>() {
+        assert_eq!(verify(&key, RSA_MESSAGE, &pss_sig), Ok(()));
+        assert!(verify(&key, b"Hello RSB", &pss_sig).is_err());
+    }
+}
+
+const ECDSA_MESSAGE: &[u8] = b"Hello ECDSA";
+
+/// Check the DER and fixed-size signatures, and that they aren't accepted by the wrong variant
+fn check_ecdsa<Asn1, Fixed>(key: &str, der_sig: &str, fixed_sig: &str)
+where
+    Asn1: WebhookPublicKeyAlgorithm,
+    Fixed: WebhookPublicKeyAlgorithm,
+{
+    let asn1_key = PublicKey::<Asn1>::from_hex(key).unwrap();
+    let fixed_key = PublicKey::<Fixed>::from_hex(key).unwrap();
+    let der_sig = hex::decode(der_sig).unwrap();
+    let fixed_sig = hex::decode(fixed_sig).unwrap();
+
+    assert_eq!(verify(&asn1_key, ECDSA_MESSAGE, &der_sig), Ok(()));
+    assert_eq!(verify(&fixed_key, ECDSA_MESSAGE, &fixed_sig), Ok(()));
+
+    assert!(verify(&asn1_key, b"Hello ECDSB", &der_sig).is_err());
+    assert!(verify(&fixed_key, b"Hello ECDSB", &fixed_sig).is_err());
+    assert!(verify(&asn1_key, ECDSA_MESSAGE, &fixed_sig).is_err());
+    assert!(verify(&fixed_key, ECDSA_MESSAGE, &der_sig).is_err());
+}
+
+#[test]
+fn ecdsa_p256() {
+    check_ecdsa::<EcdsaP256Asn1, EcdsaP256Fixed>(
+        "033db96d330fa33bc0a3f57c7f3b702a9bf52f0fa77f44d1c6fe42cd4f924d863d",
+        "3044022078a0a351ff240ab66dd1bd493de5721fcb1b9a0c6d02d7c9f050533971ea019d02202b2106c8d6c3\
+         36e8b7ff59c0a1caa7bd3946bf42d95d3403091fab2b12dbe686",
+        "78a0a351ff240ab66dd1bd493de5721fcb1b9a0c6d02d7c9f050533971ea019d2b2106c8d6c336e8b7ff59c0\
+         a1caa7bd3946bf42d95d3403091fab2b12dbe686",
+    );
+}
+
+#[test]
+fn ecdsa_p384() {
+    check_ecdsa::<EcdsaP384Asn1, EcdsaP384Fixed>(
+        "030f7a9da715508ccfbdd87090166137e39fd08e1d54dceeec3f8516887778871e1221441bebfa5b629e8527\
+         d872b2c1d8",
+        "30640230779aac1eca238b6e005225efe49ea83726804e4b641d902c30c2da7fb84d4e9e72c8a8b0eb8ea8bc\
+         28787f6fbf8729bf0230341510dfcadc6c0316d8f57076068f4cbf2292b16f6bd8b0ebed60fb7d0f97f9f572\
+         6918dc38c141ab02bf006a1814b7",
+        "779aac1eca238b6e005225efe49ea83726804e4b641d902c30c2da7fb84d4e9e72c8a8b0eb8ea8bc28787f6f\
+         bf8729bf341510dfcadc6c0316d8f57076068f4cbf2292b16f6bd8b0ebed60fb7d0f97f9f5726918dc38c141\
+         ab02bf006a1814b7",
+    );
+}
+
+#[test]
+fn ecdsa_secp256k1() {
+    // The fixed-size signature has a high S value, which is normalized before verifying
+    check_ecdsa::<EcdsaSecp256k1Asn1, EcdsaSecp256k1Fixed>(
+        "02b9c15f91391e1ec5b627775841f0abb7a5a0f03ffbac70b436d956eedaded785",
+        "3045022100cd5f1e087c6533725292b98b03c1b34f3e924596de874e982762fae6da8674de0220123e8f8266\
+         9f6ecbe49214f9ad49579b429de5d02a3d9f7139b92717ee30af4f",
+        "cd5f1e087c6533725292b98b03c1b34f3e924596de874e982762fae6da8674deedc1707d996091341b6deb06\
+         52b6a8637810f716850b00ca86193774e20591f2",
+    );
+}
+
+#[test]
+fn ecdsa_key_formats() {
+    let der_sig = hex::decode(
+        "3044022078a0a351ff240ab66dd1bd493de5721fcb1b9a0c6d02d7c9f050533971ea019d02202b2106c8d6c3\
+         36e8b7ff59c0a1caa7bd3946bf42d95d3403091fab2b12dbe686",
+    )
+    .unwrap();
+    let keys = [
+        // SPKI PEM
+        PublicKey::<EcdsaP256Asn1>::parse(
+            "-----BEGIN PUBLIC KEY-----
+            MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEPbltMw+jO8Cj9Xx/O3Aqm/UvD6d/
+            RNHG/kLNT5JNhj2mxQpX4JUEObMz6qPti0gXtKhA8DhmFP5gvPo1/2U91Q==
+            -----END PUBLIC KEY-----",
+        ),
+        // Base64 SPKI DER
+        PublicKey::from_base64(
+            "MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEPbltMw+jO8Cj9Xx/O3Aqm/UvD6d/RNHG/kLNT5JNhj2mxQpX4JU\
+             EObMz6qPti0gXtKhA8DhmFP5gvPo1/2U91Q==",
+        ),
+        // JWK
+        PublicKey::parse(
+            r#"{"kty":"EC","crv":"P-256","x":"PbltMw-jO8Cj9Xx_O3Aqm_UvD6d_RNHG_kLNT5JNhj0",
+                "y":"psUKV-CVBDmzM-qj7YtIF7SoQPA4ZhT-YLz6Nf9lPdU"}"#,
+        ),
+    ];
+    for key in keys {
+        assert_eq!(verify(&key.unwrap(), ECDSA_MESSAGE, &der_sig), Ok(()));
+    }
+
+    // Wrong curve
+    let err = PublicKey::<EcdsaP384Asn1>::parse(
+        r#"{"kty":"EC","crv":"P-256","x":"PbltMw-jO8Cj9Xx_O3Aqm_UvD6d_RNHG_kLNT5JNhj0",
+            "y":"psUKV-CVBDmzM-qj7YtIF7SoQPA4ZhT-YLz6Nf9lPdU"}"#,
+    );
+    assert!(err.is_err());
+}
+
+#[test]
+fn ed25519_key_formats() {
+    // Test key and signature from RFC 8032 (test 1) and RFC 8037
+    let signature = hex::decode(
+        "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46b\
+         d25bf5f0595bbe24655141438e7a100b",
+    )
+    .unwrap();
+    let keys = [
+        PublicKey::<Ed25519>::from_hex(
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+        ),
+        PublicKey::parse(
+            r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#,
+        ),
+        PublicKey::parse(
+            "-----BEGIN PUBLIC KEY-----\n\
+             MCowBQYDK2VwAyEA11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=\n\
+             -----END PUBLIC KEY-----\n",
+        ),
+    ];
+    for key in keys {
+        assert_eq!(verify(&key.unwrap(), b"", &signature), Ok(()));
+    }
+
+    assert!(PublicKey::<Ed25519>::parse(r#"{"kty":"EC","crv":"P-256"}"#).is_err());
+    assert!(PublicKey::<Ed25519>::from_hex("d75a98").is_err());
+}
@@ -9,9 +9,12 @@ use rocket::{
 };
 use rocket_webhook::{
     RocketWebhook, WebhookPayload, WebhookPayloadRaw,
-    webhooks::built_in::{
-        DiscordWebhook, GitHubWebhook, SendGridWebhook, ShopifyWebhook, SlackWebhook,
-        StandardWebhook, StripeWebhook,
+    webhooks::{
+        built_in::{
+            DiscordWebhook, GitHubWebhook, GitLabWebhook, SendGridWebhook, ShopifyWebhook,
+            SlackWebhook, StandardWebhook, StripeWebhook,
+        },
+        interface::hmac::HmacSecret,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -67,6 +70,58 @@ fn github() {
     assert_eq!(response.status(), Status::Unauthorized);
 }
 
+struct GithubRotation;
+
+#[get("/github-rotation", data = "<payload>")]
+async fn github_rotation_route(
+    payload: WebhookPayload<'_, GithubPayload, GitHubWebhook, GithubRotation>,
+) -> String {
+    format!("{:?}", payload.secret_index)
+}
+
+#[test]
+fn github_secret_rotation() {
+    let webhook = RocketWebhook::builder_with_marker()
+        .webhook(GitHubWebhook::with_secrets([
+            HmacSecret::new("new-secret"),
+            HmacSecret::new("test-secret"),
+        ]))
+        .marker(GithubRotation)
+        .build();
+    let expired_webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_secrets([
+            HmacSecret::new("new-secret"),
+            HmacSecret::new("test-secret").expires_at(1),
+        ]))
+        .build();
+
+    let rocket = rocket::build()
+        .manage(webhook)
+        .manage(expired_webhook)
+        .mount("/", routes![github_route, github_rotation_route]);
+
+    let client = Client::tracked(rocket).unwrap();
+    let payload = json!({"action": "opened"});
+    let old_signature = "sha256=6e939b5b3d3e8eba83ff81dde0030a8f2190d965e8bec7a17842863e979c4d7d";
+    let response = client
+        .get("/github-rotation")
+        .header(Header::new("X-Hub-Signature-256", old_signature))
+        .json(&payload)
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("Some(1)"));
+
+    // Old secret has expired
+    let response = client
+        .get("/github")
+        .header(Header::new("X-Hub-Signature-256", old_signature))
+        .json(&payload)
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
 #[get("/slack", data = "<payload>")]
 async fn slack_route(payload: WebhookPayloadRaw<'_, SlackWebhook>) -> Vec<u8> {
     payload.data
@@ -307,3 +362,137 @@ fn standard() {
         })
     );
 }
+
+#[test]
+fn standard_asymmetric() {
+    let public_key = "whpk_A6EHv/POEL4dcN0Y50vAmWfk1jCbpQ1fHdyGZBJVMbg=";
+    let secret = "whsec_x9J8mHVs08bY9qRsE3un7nW8";
+    let hmac_signature = "v1,waXhsxOg6d11zKvCs7dg/PxN9dXETpdbalU1o3J66K4=";
+    let ed25519_signature = "v1a,Wzbx5yuTeywf9VvGnY+s/Z+Kaap/iemZ4uzLoCGVSNfi2IwPyK6YEe2AMn2pmPRKxxyJiFpFg9OAxe+J0CeVBQ==";
+
+    let send = |webhook: StandardWebhook, signature: &str| {
+        let webhook = RocketWebhook::builder()
+            .timestamp_tolerance(IGNORE_TIMESTAMP, 0)
+            .webhook(webhook)
+            .build();
+        let rocket = rocket::build()
+            .manage(webhook)
+            .mount("/", routes![standard_route]);
+        let client = Client::tracked(rocket).unwrap();
+        client
+            .post("/standard")
+            .header(Header::new("Webhook-Id", "msg_CGEWVFV0jBkqRIfP"))
+            .header(Header::new("Webhook-Timestamp", "1759933695"))
+            .header(Header::new("Webhook-Signature", signature.to_owned()))
+            .json(&json!({ "event_type":"ping", "success":true}))
+            .dispatch()
+            .status()
+    };
+
+    // Public key only
+    let public_key_only = || StandardWebhook::with_public_key(public_key).expect("is valid key");
+    assert_eq!(send(public_key_only(), ed25519_signature), Status::Ok);
+    assert_eq!(
+        send(public_key_only(), hmac_signature),
+        Status::Unauthorized
+    );
+
+    // Either scheme during a migration
+    let either = || {
+        StandardWebhook::with_secret(secret)
+            .expect("is base64")
+            .accept_public_key(public_key)
+            .expect("is valid key")
+    };
+    assert_eq!(send(either(), ed25519_signature), Status::Ok);
+    assert_eq!(send(either(), hmac_signature), Status::Ok);
+    let both_signatures = format!("{ed25519_signature} {hmac_signature}");
+    assert_eq!(send(either(), &both_signatures), Status::Ok);
+    let invalid_ed25519 = format!("v1a,{} {hmac_signature}", "A".repeat(88));
+    assert_eq!(send(either(), &invalid_ed25519), Status::Ok);
+    assert_eq!(
+        send(either(), &ed25519_signature.replace("Wzb", "Xzb")),
+        Status::Unauthorized
+    );
+}
+
+#[post("/gitlab", data = "<payload>")]
+async fn gitlab_route(payload: WebhookPayloadRaw<'_, GitLabWebhook>) -> String {
+    let metadata = payload.metadata();
+    format!(
+        "{} from {} ({})",
+        metadata.event.unwrap_or_default(),
+        metadata.instance.unwrap_or_default(),
+        metadata.event_uuid.unwrap_or_default()
+    )
+}
+
+#[test]
+fn gitlab_token() {
+    let webhook = RocketWebhook::builder()
+        .webhook(GitLabWebhook::with_token("gitlab-token"))
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![gitlab_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    let send = |token: Option<&'static str>| {
+        let mut request = client
+            .post("/gitlab")
+            .header(Header::new("X-Gitlab-Event", "Push Hook"))
+            .header(Header::new("X-Gitlab-Instance", "https://gitlab.com"))
+            .header(Header::new(
+                "X-Gitlab-Event-UUID",
+                "13792a34-cac6-4fda-95a8-c58e00a3954e",
+            ))
+            .json(&json!({ "object_kind": "push" }));
+        if let Some(token) = token {
+            request = request.header(Header::new("X-Gitlab-Token", token));
+        }
+        request.dispatch()
+    };
+
+    let response = send(Some("gitlab-token"));
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().as_deref(),
+        Some("Push Hook from https://gitlab.com (13792a34-cac6-4fda-95a8-c58e00a3954e)")
+    );
+    assert_eq!(send(Some("wrong-token")).status(), Status::Unauthorized);
+    assert_eq!(send(None).status(), Status::BadRequest);
+}
+
+#[test]
+fn gitlab_signing_token() {
+    let webhook = RocketWebhook::builder()
+        .timestamp_tolerance(IGNORE_TIMESTAMP, 0)
+        .webhook(
+            GitLabWebhook::with_signing_token("whsec_x9J8mHVs08bY9qRsE3un7nW8").expect("is base64"),
+        )
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![gitlab_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    let send = |signature: &'static str| {
+        client
+            .post("/gitlab")
+            .header(Header::new("X-Gitlab-Event", "Issue Hook"))
+            .header(Header::new("webhook-id", "msg_CGEWVFV0jBkqRIfP"))
+            .header(Header::new("webhook-timestamp", "1759933695"))
+            .header(Header::new("webhook-signature", signature))
+            .json(&json!({ "event_type":"ping", "success":true}))
+            .dispatch()
+    };
+
+    let response = send("v1,waXhsxOg6d11zKvCs7dg/PxN9dXETpdbalU1o3J66K4=");
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().as_deref(),
+        Some("Issue Hook from  ()")
+    );
+    let response = send("v1,vaXhsxOg6d11zKvCs7dg/PxN9dXETpdbalU1o3J66K4=");
+    assert_eq!(response.status(), Status::Unauthorized);
+}
@@ -0,0 +1,225 @@
+//! Tests for automatic responses to verification challenges
+
+use rocket::{
+    Build, Rocket,
+    http::{ContentType, Header, Status},
+    local::blocking::Client,
+    post, routes,
+    serde::json::{Value, json},
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw,
+    challenge::WebhookChallenges,
+    testing::{DiscordTestKey, LocalRequestExt, now},
+    webhooks::built_in::{DiscordWebhook, MetaWebhook, SlackWebhook, TwitchWebhook, ZoomWebhook},
+};
+
+#[post("/slack", data = "<_payload>")]
+async fn slack_route(_payload: WebhookPayloadRaw<'_, SlackWebhook>) -> &'static str {
+    "event"
+}
+
+#[post("/discord", data = "<_payload>")]
+async fn discord_route(_payload: WebhookPayloadRaw<'_, DiscordWebhook>) -> &'static str {
+    "event"
+}
+
+#[post("/zoom", data = "<_payload>")]
+async fn zoom_route(_payload: WebhookPayloadRaw<'_, ZoomWebhook>) -> &'static str {
+    "event"
+}
+
+#[post("/twitch", data = "<_payload>")]
+async fn twitch_route(_payload: WebhookPayloadRaw<'_, TwitchWebhook>) -> &'static str {
+    "event"
+}
+
+#[post("/", data = "<_payload>")]
+async fn meta_route(_payload: WebhookPayloadRaw<'_, MetaWebhook>) -> &'static str {
+    "event"
+}
+
+fn rocket() -> Rocket<Build> {
+    rocket::build()
+        .manage(
+            RocketWebhook::builder()
+                .webhook(SlackWebhook::with_secret("slack-secret"))
+                .build(),
+        )
+        .manage(
+            RocketWebhook::builder()
+                .webhook(DiscordTestKey::new().webhook())
+                .build(),
+        )
+        .manage(
+            RocketWebhook::builder()
+                .webhook(ZoomWebhook::with_secret("zoom-secret"))
+                .build(),
+        )
+        .manage(
+            RocketWebhook::builder()
+                .webhook(TwitchWebhook::with_secret("twitch-secret"))
+                .timestamp_tolerance(u32::MAX, 0)
+                .build(),
+        )
+        .manage(
+            RocketWebhook::builder()
+                .webhook(MetaWebhook::with_secret("meta-secret"))
+                .build(),
+        )
+        .mount(
+            "/",
+            routes![slack_route, discord_route, zoom_route, twitch_route],
+        )
+        .mount("/meta", routes![meta_route])
+        .mount("/meta", [MetaWebhook::verification_route("meta-token")])
+}
+
+const SLACK_CHALLENGE: &str = r#"{"token":"Jhj5dZrVaK7ZwHHjRyZWjbDl","challenge":"3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P","type":"url_verification"}"#;
+
+#[test]
+fn slack_url_verification() {
+    let client = Client::tracked(rocket().attach(WebhookChallenges)).unwrap();
+    let signer = SlackWebhook::with_secret("slack-secret");
+
+    let response = client
+        .post("/slack")
+        .signed(&signer, SLACK_CHALLENGE, now())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::Plain));
+    assert_eq!(
+        response.into_string().as_deref(),
+        Some("3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P")
+    );
+
+    // Real events reach the route
+    let response = client
+        .post("/slack")
+        .signed(&signer, r#"{"type":"event_callback"}"#, now())
+        .dispatch();
+    assert_eq!(response.into_string().as_deref(), Some("event"));
+
+    // Challenges with an invalid signature are not answered
+    let response = client
+        .post("/slack")
+        .signed(&SlackWebhook::with_secret("wrong"), SLACK_CHALLENGE, now())
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn challenges_are_opt_in() {
+    let client = Client::tracked(rocket()).unwrap();
+    let response = client
+        .post("/slack")
+        .signed(
+            &SlackWebhook::with_secret("slack-secret"),
+            SLACK_CHALLENGE,
+            now(),
+        )
+        .dispatch();
+    assert_eq!(response.into_string().as_deref(), Some("event"));
+}
+
+#[test]
+fn discord_ping() {
+    let client = Client::tracked(rocket().attach(WebhookChallenges)).unwrap();
+    let response = client
+        .post("/discord")
+        .signed(&DiscordTestKey::new(), r#"{"id":"123","type":1}"#, now())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    assert_eq!(response.into_json::<Value>(), Some(json!({ "type": 1 })));
+
+    let response = client
+        .post("/discord")
+        .signed(&DiscordTestKey::new(), r#"{"id":"123","type":2}"#, now())
+        .dispatch();
+    assert_eq!(response.into_string().as_deref(), Some("event"));
+}
+
+#[test]
+fn zoom_url_validation() {
+    let client = Client::tracked(rocket().attach(WebhookChallenges)).unwrap();
+    let body = json!({
+        "payload": { "plainToken": "qgg8vlvZRS6UYooatFL8Aw" },
+        "event_ts": 1654503849680u64,
+        "event": "endpoint.url_validation"
+    });
+    let response = client
+        .post("/zoom")
+        .signed(
+            &ZoomWebhook::with_secret("zoom-secret"),
+            body.to_string(),
+            now(),
+        )
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_json::<Value>(),
+        Some(json!({
+            "plainToken": "qgg8vlvZRS6UYooatFL8Aw",
+            "encryptedToken": "b17f0320a1322a509340a1a835b3d9edbc491f0b0790ed2d20d0604d39dfe922"
+        }))
+    );
+}
+
+#[test]
+fn twitch_callback_verification() {
+    let client = Client::tracked(rocket().attach(WebhookChallenges)).unwrap();
+    let response = client
+        .post("/twitch")
+        .header(Header::new(
+            "Twitch-Eventsub-Message-Id",
+            "e76c6bd4-55c9-4987-8304-da1588d8988b",
+        ))
+        .header(Header::new(
+            "Twitch-Eventsub-Message-Timestamp",
+            "2019-11-16T10:11:12.634234626Z",
+        ))
+        .header(Header::new(
+            "Twitch-Eventsub-Message-Signature",
+            "sha256=7e0c2387054519e0141e3cafef0bb6c8de96b9a024a8ea0eb6174d72b0c45c76",
+        ))
+        .header(Header::new(
+            "Twitch-Eventsub-Message-Type",
+            "webhook_callback_verification",
+        ))
+        .body(r#"{"challenge":"pogchamp-kappa-360noscope-vohiyo"}"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().as_deref(),
+        Some("pogchamp-kappa-360noscope-vohiyo")
+    );
+
+    // Notifications reach the route
+    let response = client
+        .post("/twitch")
+        .signed(&TwitchWebhook::with_secret("twitch-secret"), "{}", now())
+        .dispatch();
+    assert_eq!(response.into_string().as_deref(), Some("event"));
+}
+
+#[test]
+fn meta_verification() {
+    let client = Client::tracked(rocket()).unwrap();
+    let response = client
+        .get("/meta?hub.mode=subscribe&hub.challenge=1158201444&hub.verify_token=meta-token")
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("1158201444"));
+
+    let response = client
+        .get("/meta?hub.mode=subscribe&hub.challenge=1158201444&hub.verify_token=wrong")
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response = client
+        .post("/meta")
+        .signed(&MetaWebhook::with_secret("meta-secret"), "{}", now())
+        .dispatch();
+    assert_eq!(response.into_string().as_deref(), Some("event"));
+}
@@ -0,0 +1,294 @@
+//! Tests for the CloudEvents data guards
+
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalRequest},
+    post, routes,
+    serde::{Deserialize, json::serde_json::json},
+};
+use rocket_webhook::{
+    CloudEvent, CloudEventBatch, RocketWebhook, WebhookError, webhooks::generic::TokenWebhook,
+};
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct BlobCreated {
+    url: String,
+}
+
+#[post("/event", data = "<event>")]
+fn event_route(event: CloudEvent<'_, BlobCreated, TokenWebhook>) -> String {
+    format!(
+        "{} {} {} {:?} {:?} {:?} {} {}",
+        event.id,
+        event.source,
+        event.event_type,
+        event.subject,
+        event.time,
+        event.data_content_type,
+        rocket::serde::json::Value::Object(event.extensions),
+        event.data.url
+    )
+}
+
+#[post("/text", data = "<event>")]
+fn text_route(event: CloudEvent<'_, Option<String>, TokenWebhook>) -> String {
+    event.data.unwrap_or_else(|| "no data".into())
+}
+
+#[post("/batch", data = "<batch>")]
+fn batch_route(batch: CloudEventBatch<'_, BlobCreated, TokenWebhook>) -> String {
+    let urls: Vec<_> = batch
+        .events
+        .into_iter()
+        .map(|event| format!("{}={}", event.id, event.data.url))
+        .collect();
+    urls.join(",")
+}
+
+/// Returns the name of the error variant if the event is invalid
+#[post("/check", data = "<event>")]
+fn check_route(event: Result<CloudEvent<'_, BlobCreated, TokenWebhook>, WebhookError>) -> String {
+    match event {
+        Ok(_) => "Ok".into(),
+        Err(err) => format!("{err:?}").split('(').next().unwrap().to_owned(),
+    }
+}
+
+fn client() -> Client {
+    let webhook = RocketWebhook::builder()
+        .webhook(
+            TokenWebhook::builder()
+                .header("X-Webhook-Token")
+                .token("secret")
+                .build(),
+        )
+        .build();
+    let rocket = rocket::build()
+        .mount(
+            "/",
+            routes![event_route, text_route, batch_route, check_route],
+        )
+        .manage(webhook);
+    Client::tracked(rocket).unwrap()
+}
+
+fn binary<'c>(client: &'c Client, uri: &'static str) -> LocalRequest<'c> {
+    client
+        .post(uri)
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(Header::new("ce-specversion", "1.0"))
+        .header(Header::new("ce-id", "A234-1234-1234"))
+        .header(Header::new("ce-source", "/mycontext/subcontext"))
+        .header(Header::new("ce-type", "com.example.blob.created"))
+}
+
+fn structured<'c>(client: &'c Client, uri: &'static str, body: String) -> LocalRequest<'c> {
+    client
+        .post(uri)
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(ContentType::new("application", "cloudevents+json"))
+        .body(body)
+}
+
+fn structured_event(id: &str, url: &str) -> rocket::serde::json::Value {
+    json!({
+        "specversion": "1.0",
+        "id": id,
+        "source": "/mycontext/subcontext",
+        "type": "com.example.blob.created",
+        "subject": "blobs/1",
+        "time": "2025-05-06T16:58:21Z",
+        "datacontenttype": "application/json",
+        "traceparent": "00-abc-def-01",
+        "sequence": 7,
+        "data": {"url": url},
+    })
+}
+
+#[test]
+fn binary_mode() {
+    let client = client();
+
+    let response = binary(&client, "/event")
+        .header(Header::new("ce-subject", "blobs/1"))
+        .header(Header::new("ce-time", "2025-05-06T16:58:21Z"))
+        .header(Header::new("ce-traceparent", "00-abc-def-01"))
+        .header(Header::new("ce-comexampleext", "caf%C3%A9"))
+        .header(ContentType::JSON)
+        .body(r#"{"url":"https://example.com/blob"}"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().unwrap(),
+        r#"A234-1234-1234 /mycontext/subcontext com.example.blob.created Some("blobs/1") Some("2025-05-06T16:58:21Z") Some("application/json") {"comexampleext":"café","traceparent":"00-abc-def-01"} https://example.com/blob"#
+    );
+
+    // text data, and no data
+    let response = binary(&client, "/text")
+        .header(ContentType::Plain)
+        .body("hello")
+        .dispatch();
+    assert_eq!(response.into_string().unwrap(), "hello");
+    let response = binary(&client, "/text").dispatch();
+    assert_eq!(response.into_string().unwrap(), "no data");
+
+    // binary event is a batch of one
+    let response = binary(&client, "/batch")
+        .header(ContentType::JSON)
+        .body(r#"{"url":"https://example.com/blob"}"#)
+        .dispatch();
+    assert_eq!(
+        response.into_string().unwrap(),
+        "A234-1234-1234=https://example.com/blob"
+    );
+
+    // webhook verification still applies
+    let response = client
+        .post("/text")
+        .header(Header::new("ce-specversion", "1.0"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn structured_mode() {
+    let client = client();
+
+    let event = structured_event("B234-1234-1234", "https://example.com/blob");
+    let response = structured(&client, "/event", event.to_string()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().unwrap(),
+        r#"B234-1234-1234 /mycontext/subcontext com.example.blob.created Some("blobs/1") Some("2025-05-06T16:58:21Z") Some("application/json") {"sequence":7,"traceparent":"00-abc-def-01"} https://example.com/blob"#
+    );
+
+    // the content type takes precedence over ce- headers
+    let event = structured_event("C234-1234-1234", "https://example.com/blob");
+    let response = structured(&client, "/event", event.to_string())
+        .header(Header::new("ce-specversion", "1.0"))
+        .header(Header::new("ce-id", "other"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(
+        response
+            .into_string()
+            .unwrap()
+            .starts_with("C234-1234-1234 ")
+    );
+
+    // base64 data
+    let event = json!({
+        "specversion": "1.0",
+        "id": "1",
+        "source": "/source",
+        "type": "com.example.text",
+        "datacontenttype": "text/plain; charset=utf-8",
+        "data_base64": "aGVsbG8=",
+    });
+    let response = structured(&client, "/text", event.to_string()).dispatch();
+    assert_eq!(response.into_string().unwrap(), "hello");
+
+    let batch = json!([
+        structured_event("1", "https://example.com/1"),
+        structured_event("2", "https://example.com/2"),
+    ]);
+    let response = client
+        .post("/batch")
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(ContentType::new("application", "cloudevents-batch+json"))
+        .body(batch.to_string())
+        .dispatch();
+    assert_eq!(
+        response.into_string().unwrap(),
+        "1=https://example.com/1,2=https://example.com/2"
+    );
+    let response = client
+        .post("/event")
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(ContentType::new("application", "cloudevents-batch+json"))
+        .body(batch.to_string())
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn invalid_events() {
+    let client = client();
+    let check = |request: LocalRequest<'_>| request.dispatch().into_string().unwrap();
+
+    let body = r#"{"url":"https://example.com/blob"}"#;
+    assert_eq!(check(binary(&client, "/check").body(body)), "Ok");
+    let missing_id = client
+        .post("/check")
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(Header::new("ce-specversion", "1.0"))
+        .body(body);
+    assert_eq!(check(missing_id), "MissingHeader");
+    let version = client
+        .post("/check")
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(Header::new("ce-specversion", "0.3"));
+    assert_eq!(check(version), "InvalidHeader");
+    let extension = binary(&client, "/check").header(Header::new("ce-my_ext", "value"));
+    assert_eq!(check(extension.body(body)), "InvalidEvent");
+    let data_content_type = binary(&client, "/check")
+        .header(ContentType::JSON)
+        .header(Header::new("ce-datacontenttype", "application/json"));
+    assert_eq!(check(data_content_type.body(body)), "InvalidEvent");
+    let wrong_data = binary(&client, "/check").body(r#"{"uri":"https://example.com"}"#);
+    assert_eq!(check(wrong_data), "Deserialize");
+    let not_text = binary(&client, "/check")
+        .header(ContentType::Binary)
+        .body([0xff, 0xfe]);
+    assert_eq!(check(not_text), "InvalidEvent");
+
+    let invalid = [
+        ("id", json!(null), "InvalidEvent"),
+        ("specversion", json!("0.3"), "InvalidEvent"),
+        ("source", json!(""), "InvalidEvent"),
+        ("type", json!(42), "InvalidEvent"),
+        ("data_base64", json!("aGVsbG8="), "InvalidEvent"),
+        ("Invalid-Name", json!("value"), "InvalidEvent"),
+        ("data", json!({"uri": "https://example.com"}), "Deserialize"),
+    ];
+    for (name, value, error) in invalid {
+        let mut event = structured_event("1", "https://example.com");
+        event[name] = value;
+        let request = structured(&client, "/check", event.to_string());
+        assert_eq!(check(request), error, "{name}");
+    }
+    let request = structured(&client, "/check", "[]".into());
+    assert_eq!(check(request), "InvalidEvent");
+    let request = structured(&client, "/check", "{".into());
+    assert_eq!(check(request), "Deserialize");
+
+    let batch = client
+        .post("/check")
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(ContentType::new("application", "cloudevents-batch+json"))
+        .body("[]");
+    assert_eq!(check(batch), "InvalidEvent");
+    let not_cloud_event = client
+        .post("/check")
+        .header(Header::new("X-Webhook-Token", "secret"))
+        .header(ContentType::JSON)
+        .body(structured_event("1", "https://example.com").to_string());
+    assert_eq!(check(not_cloud_event), "InvalidEvent");
+    let unauthorized = client
+        .post("/check")
+        .header(Header::new("X-Webhook-Token", "wrong"))
+        .header(ContentType::new("application", "cloudevents+json"))
+        .body(structured_event("1", "https://example.com").to_string());
+    assert_eq!(check(unauthorized), "Signature");
+}
+
+#[test]
+fn invalid_event_error() {
+    let error = WebhookError::InvalidEvent("Missing required attribute 'id'".into());
+    assert_eq!(error.status(), Status::BadRequest);
+    assert_eq!(
+        error.to_string(),
+        "Invalid event: Missing required attribute 'id'"
+    );
+}
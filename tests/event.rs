@@ -0,0 +1,161 @@
+//! Tests for typed webhook events
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    post, routes,
+    serde::Deserialize,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookError, WebhookEvent,
+    event::RawEvent,
+    testing::{LocalRequestExt, now},
+    webhook_event,
+    webhooks::built_in::{GitHubWebhook, StripeWebhook},
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct PushEvent {
+    r#ref: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct PullRequestEvent {
+    action: String,
+    number: u64,
+}
+
+webhook_event! {
+    #[derive(Debug)]
+    enum GitHubEvent {
+        "push" => Push(PushEvent),
+        "pull_request" => PullRequest(PullRequestEvent),
+        _ => Other(RawEvent),
+    }
+}
+
+#[post("/github", data = "<payload>")]
+async fn github_route(
+    payload: Result<WebhookEvent<'_, GitHubEvent, GitHubWebhook>, WebhookError>,
+) -> (Status, String) {
+    let event = match payload {
+        Ok(payload) => payload.event,
+        Err(err) => return (err.status(), err.to_string()),
+    };
+    let response = match event {
+        GitHubEvent::Push(push) => format!("push to {}", push.r#ref),
+        GitHubEvent::PullRequest(pr) => format!("pull request #{} {}", pr.number, pr.action),
+        GitHubEvent::Other(RawEvent { event_type, data }) => {
+            format!("other {event_type:?}: {}", String::from_utf8_lossy(&data))
+        }
+    };
+    (Status::Ok, response)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Invoice {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct InvoicePaid {
+    data: InvoiceData,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct InvoiceData {
+    object: Invoice,
+}
+
+webhook_event! {
+    enum StripeEvent {
+        "invoice.paid" => InvoicePaid(InvoicePaid),
+        _ => Other(RawEvent),
+    }
+}
+
+#[post("/stripe", data = "<payload>")]
+async fn stripe_route(payload: WebhookEvent<'_, StripeEvent, StripeWebhook>) -> String {
+    match payload.event {
+        StripeEvent::InvoicePaid(paid) => format!("paid {}", paid.data.object.id),
+        StripeEvent::Other(event) => format!("other {}", event.event_type.unwrap_or_default()),
+    }
+}
+
+fn client() -> Client {
+    let rocket = rocket::build()
+        .manage(
+            RocketWebhook::builder()
+                .webhook(GitHubWebhook::with_secret("github-secret"))
+                .build(),
+        )
+        .manage(
+            RocketWebhook::builder()
+                .webhook(StripeWebhook::with_secret("stripe-secret"))
+                .build(),
+        )
+        .mount("/", routes![github_route, stripe_route]);
+    Client::tracked(rocket).unwrap()
+}
+
+#[test]
+fn github_events() {
+    let client = client();
+    let signer = GitHubWebhook::with_secret("github-secret");
+    let send = |event: &str, body: &str| {
+        let response = client
+            .post("/github")
+            .header(Header::new("X-GitHub-Event", event.to_owned()))
+            .signed(&signer, body, now())
+            .dispatch();
+        (response.status(), response.into_string().unwrap())
+    };
+
+    assert_eq!(
+        send("push", r#"{"ref":"refs/heads/main","before":"abc"}"#),
+        (Status::Ok, "push to refs/heads/main".into())
+    );
+    assert_eq!(
+        send("pull_request", r#"{"action":"opened","number":42}"#),
+        (Status::Ok, "pull request #42 opened".into())
+    );
+    assert_eq!(
+        send("star", r#"{"action":"created"}"#),
+        (
+            Status::Ok,
+            r#"other Some("star"): {"action":"created"}"#.into()
+        )
+    );
+
+    // Known events with an invalid body fail to deserialize
+    let (status, _) = send("push", r#"{"action":"created"}"#);
+    assert_eq!(status, Status::BadRequest);
+}
+
+#[test]
+fn stripe_events() {
+    let client = client();
+    let signer = StripeWebhook::with_secret("stripe-secret");
+    let send = |body: &str| {
+        client
+            .post("/stripe")
+            .signed(&signer, body, now())
+            .dispatch()
+            .into_string()
+            .unwrap()
+    };
+
+    assert_eq!(
+        send(r#"{"type":"invoice.paid","data":{"object":{"id":"in_123"}}}"#),
+        "paid in_123"
+    );
+    assert_eq!(
+        send(r#"{"type":"customer.created","data":{}}"#),
+        "other customer.created"
+    );
+}
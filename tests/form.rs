@@ -0,0 +1,145 @@
+//! Tests for the form data guard
+
+use rocket::{
+    http::{ContentType, Status},
+    local::blocking::Client,
+    post, routes,
+    serde::Deserialize,
+};
+use rocket_webhook::{
+    FormJson, RocketWebhook, WebhookError, WebhookForm,
+    testing::{LocalRequestExt, now},
+    webhooks::built_in::SlackWebhook,
+};
+
+const SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct SlashCommand {
+    command: String,
+    text: String,
+    user_name: String,
+    response_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Interaction {
+    payload: FormJson<InteractionPayload>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct InteractionPayload {
+    r#type: String,
+    actions: Vec<Action>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde")]
+struct Action {
+    action_id: String,
+}
+
+#[post("/command", data = "<payload>")]
+fn command_route(payload: WebhookForm<'_, SlashCommand, SlackWebhook>) -> String {
+    let command = payload.data;
+    format!(
+        "{} {} by {} ({})",
+        command.command, command.text, command.user_name, command.response_url
+    )
+}
+
+#[post("/interaction", data = "<payload>")]
+fn interaction_route(
+    payload: Result<WebhookForm<'_, Interaction, SlackWebhook>, WebhookError>,
+) -> String {
+    match payload {
+        Ok(payload) => {
+            let FormJson(interaction) = payload.data.payload;
+            format!(
+                "{} {}",
+                interaction.r#type, interaction.actions[0].action_id
+            )
+        }
+        Err(err) => err.to_string(),
+    }
+}
+
+fn client() -> Client {
+    let webhook = RocketWebhook::builder()
+        .webhook(SlackWebhook::with_secret(SECRET))
+        .build();
+    let rocket = rocket::build()
+        .mount("/", routes![command_route, interaction_route])
+        .manage(webhook);
+    Client::tracked(rocket).unwrap()
+}
+
+#[test]
+fn form_payload() {
+    let client = client();
+    let webhook = SlackWebhook::with_secret(SECRET);
+
+    let command = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&user_name=roadrunner&command=%2Fdeploy&text=api+v1.2&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2F1234%2F5678";
+    let response = client
+        .post("/command")
+        .header(ContentType::Form)
+        .signed(&webhook, command, now())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().unwrap(),
+        "/deploy api v1.2 by roadrunner (https://hooks.slack.com/commands/1234/5678)"
+    );
+
+    // missing field
+    let response = client
+        .post("/command")
+        .signed(&webhook, "command=%2Fdeploy&text=", now())
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    // invalid signature is rejected before deserializing
+    let response = client
+        .post("/command")
+        .signed(&webhook, command, now())
+        .body(command.replace("deploy", "destroy"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn form_json_field() {
+    let client = client();
+    let webhook = SlackWebhook::with_secret(SECRET);
+
+    let interaction = "payload=%7B%22type%22%3A%22block_actions%22%2C%22actions%22%3A%5B%7B%22action_id%22%3A%22approve%22%7D%5D%7D";
+    let response = client
+        .post("/interaction")
+        .signed(&webhook, interaction, now())
+        .dispatch();
+    assert_eq!(response.into_string().unwrap(), "block_actions approve");
+
+    let invalid_json = "payload=%7B%22type%22%3A";
+    let response = client
+        .post("/interaction")
+        .signed(&webhook, invalid_json, now())
+        .dispatch();
+    let error = response.into_string().unwrap();
+    assert!(
+        error.starts_with("Failed to deserialize webhook form: EOF while parsing"),
+        "{error}"
+    );
+
+    let missing_payload = "type=block_actions";
+    let response = client
+        .post("/interaction")
+        .signed(&webhook, missing_payload, now())
+        .dispatch();
+    assert_eq!(
+        response.into_string().unwrap(),
+        "Failed to deserialize webhook form: missing field `payload`"
+    );
+}
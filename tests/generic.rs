@@ -6,27 +6,58 @@ use rocket::{
     post, routes,
 };
 use rocket_webhook::{
-    RocketWebhook, WebhookError, WebhookPayloadRaw, webhooks::generic::Hmac256Webhook,
+    RocketWebhook, WebhookError, WebhookPayloadRaw,
+    webhooks::{
+        generic::{Ed25519Webhook, Hmac256Webhook, Hmac512Webhook, HmacSha1Webhook, TokenWebhook},
+        interface::public_key::PublicKey,
+    },
 };
+use tokio_util::bytes::BytesMut;
 
 #[post("/hmac", data = "<payload>")]
 async fn hmac_route(payload: WebhookPayloadRaw<'_, Hmac256Webhook>) -> Vec<u8> {
     payload.data
 }
 
+/// Marker for a second, differently configured HMAC webhook
+struct Tenant;
+
+#[post("/tenant", data = "<payload>")]
+async fn tenant_route(payload: WebhookPayloadRaw<'_, Hmac256Webhook, Tenant>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/sha1", data = "<payload>")]
+async fn sha1_route(payload: WebhookPayloadRaw<'_, HmacSha1Webhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/sha512", data = "<payload>")]
+async fn sha512_route(payload: WebhookPayloadRaw<'_, Hmac512Webhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/ed25519", data = "<payload>")]
+async fn ed25519_route(payload: WebhookPayloadRaw<'_, Ed25519Webhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/token", data = "<payload>")]
+async fn token_route(payload: WebhookPayloadRaw<'_, TokenWebhook>) -> String {
+    format!("{:?}", payload.secret_index)
+}
+
 #[test]
 fn hmac() {
     let custom_hmac = Hmac256Webhook::builder()
         .secret("my-custom-hmac-secret")
         .expected_signatures(|req| {
-            req.headers()
-                .get_one("Signature-SHA256")
+            req.header("Signature-SHA256")
                 .and_then(|header| hex::decode(header).ok())
                 .map(|header| vec![header])
         })
         .body_suffix(|req, (min_t, max_t)| {
-            req.headers()
-                .get_one("Timestamp")
+            req.header("Timestamp")
                 .filter(|time| time.parse::<u32>().is_ok_and(|t| t > min_t && t < max_t))
                 .map(|time| time.as_bytes().to_vec())
                 .ok_or_else(|| WebhookError::Timestamp("Missing/invalid timestamp".into()))
@@ -75,3 +106,299 @@ fn hmac() {
         .dispatch();
     assert_eq!(response.status(), Status::BadRequest);
 }
+
+#[test]
+fn hmac_capturing_closures() {
+    let build = |secret: &str, header: String, timestamp_header: String| {
+        Hmac256Webhook::builder()
+            .secret(secret)
+            .expected_signatures(move |req| {
+                req.header(&header)
+                    .and_then(|header| hex::decode(header).ok())
+                    .map(|header| vec![header])
+            })
+            .body_suffix(move |req, _| {
+                req.header(&timestamp_header)
+                    .map(|time| time.as_bytes().to_vec())
+                    .ok_or_else(|| WebhookError::Timestamp("Missing timestamp".into()))
+            })
+            .build()
+    };
+
+    let rocket = rocket::build()
+        .mount("/", routes![hmac_route, tenant_route])
+        .manage(
+            RocketWebhook::builder()
+                .webhook(build(
+                    "my-custom-hmac-secret",
+                    "Signature-SHA256".into(),
+                    "Timestamp".into(),
+                ))
+                .build(),
+        )
+        .manage(
+            RocketWebhook::builder_with_marker()
+                .webhook(build(
+                    "my-custom-hmac-secret",
+                    "X-Tenant-Signature".into(),
+                    "X-Tenant-Timestamp".into(),
+                ))
+                .marker(Tenant)
+                .build(),
+        );
+    let client = Client::tracked(rocket).unwrap();
+
+    let body = "Hello custom HMAC";
+    let timestamp = "1760414077";
+    let signature = "83fbbe9119392db8d86a318ff31bf799d54d8abf91562e06109fedfd57a6df4e";
+
+    let response = client
+        .post("/hmac")
+        .header(Header::new("Signature-SHA256", signature))
+        .header(Header::new("Timestamp", timestamp))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post("/tenant")
+        .header(Header::new("X-Tenant-Signature", signature))
+        .header(Header::new("X-Tenant-Timestamp", timestamp))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = client
+        .post("/tenant")
+        .header(Header::new("Signature-SHA256", signature))
+        .header(Header::new("Timestamp", timestamp))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn hmac_digests() {
+    let sha1 = HmacSha1Webhook::builder()
+        .secret("my-sha1-secret")
+        .expected_signatures(|req| {
+            req.header("X-Hub-Signature")
+                .and_then(|header| header.strip_prefix("sha1="))
+                .and_then(|header| hex::decode(header).ok())
+                .map(|header| vec![header])
+        })
+        .build();
+    let sha512 = Hmac512Webhook::builder()
+        .secret("my-sha512-secret")
+        .expected_signatures(|req| {
+            req.header("Signature-SHA512")
+                .and_then(|header| hex::decode(header).ok())
+                .map(|header| vec![header])
+        })
+        .build();
+
+    let rocket = rocket::build()
+        .mount("/", routes![sha1_route, sha512_route])
+        .manage(RocketWebhook::builder().webhook(sha1).build())
+        .manage(RocketWebhook::builder().webhook(sha512).build());
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client
+        .post("/sha1")
+        .header(Header::new(
+            "X-Hub-Signature",
+            "sha1=105a70f1496e5c76d5b32f393783a9c44c8f0d1a",
+        ))
+        .body("Hello SHA1")
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("Hello SHA1"));
+
+    let response = client
+        .post("/sha1")
+        .header(Header::new(
+            "X-Hub-Signature",
+            "sha1=105a70f1496e5c76d5b32f393783a9c44c8f0d1a",
+        ))
+        .body("Hello SHA2")
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .post("/sha512")
+        .header(Header::new(
+            "Signature-SHA512",
+            "0b2196f85631ecb5a39d975e804b2762316ae37f801ece4722eba7521388332c\
+             9f062c36d98894a4ad89763aaf31f00f9d9ef5a5be4bda401aeae81b79bc1404",
+        ))
+        .body("Hello SHA512")
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("Hello SHA512"));
+}
+
+#[test]
+fn public_key() {
+    let custom_ed25519 = Ed25519Webhook::builder()
+        .public_key(
+            PublicKey::from_hex("03a107bff3ce10be1d70dd18e74bc09967e4d6309ba50d5f1ddc8664125531b8")
+                .unwrap(),
+        )
+        .expected_signatures(|req| {
+            req.header("Signature")
+                .and_then(|header| hex::decode(header).ok())
+                .map(|header| vec![header])
+        })
+        .message_to_verify(|req, body, (min_t, max_t)| {
+            let timestamp = req
+                .header("Timestamp")
+                .filter(|time| time.parse::<u32>().is_ok_and(|t| t > min_t && t < max_t))
+                .ok_or_else(|| WebhookError::Timestamp("Missing/invalid timestamp".into()))?;
+            let mut message = BytesMut::from(timestamp.as_bytes());
+            message.extend_from_slice(b"|");
+            message.extend_from_slice(body);
+            Ok(message.freeze())
+        })
+        .build();
+    let webhook = RocketWebhook::builder()
+        .webhook(custom_ed25519)
+        .timestamp_tolerance(u32::MAX, 0)
+        .build();
+
+    let rocket = rocket::build()
+        .mount("/", routes![ed25519_route])
+        .manage(webhook);
+    let client = Client::tracked(rocket).unwrap();
+
+    let body = "Hello Ed25519";
+    let timestamp = "1760414077";
+    let signature = "f6daa437e7a8e82aa899652261095530c52f954d7676f453664e376dd59bfc88\
+                     56175de26712203999a664ca1a5ea40bf8d951c37e8de924b749b547988dd507";
+
+    let response = client
+        .post("/ed25519")
+        .header(Header::new("Signature", signature))
+        .header(Header::new("Timestamp", timestamp))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some(body));
+
+    // Signature of a different timestamp
+    let response = client
+        .post("/ed25519")
+        .header(Header::new("Signature", signature))
+        .header(Header::new("Timestamp", "1760414078"))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // Missing timestamp
+    let response = client
+        .post("/ed25519")
+        .header(Header::new("Signature", signature))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn token() {
+    let custom_token = TokenWebhook::builder()
+        .header("X-Webhook-Secret")
+        .tokens(["new-token", "old-token"])
+        .build();
+    let webhook = RocketWebhook::builder()
+        .webhook(custom_token)
+        .max_body_size(16)
+        .build();
+
+    let rocket = rocket::build()
+        .mount("/", routes![token_route])
+        .manage(webhook);
+    let client = Client::tracked(rocket).unwrap();
+
+    let send = |token: &'static str, body: &'static str| {
+        client
+            .post("/token")
+            .header(Header::new("X-Webhook-Secret", token))
+            .body(body)
+            .dispatch()
+    };
+
+    let response = send("new-token", "Hello token");
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("Some(0)"));
+
+    let response = send("old-token", "Hello token");
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("Some(1)"));
+
+    assert_eq!(send("new-tokenn", "Hello").status(), Status::Unauthorized);
+    assert_eq!(
+        send("new-token", "Hello token, this is too long").status(),
+        Status::PayloadTooLarge
+    );
+
+    // Missing header
+    let response = client.post("/token").body("Hello token").dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn token_auth_schemes() {
+    let bearer = TokenWebhook::builder().bearer_token("bearer-token").build();
+    let basic = TokenWebhook::builder()
+        .basic_auth_credentials([("user", "new-password"), ("user", "old-password")])
+        .build();
+    let rocket = rocket::build()
+        .mount("/bearer", routes![token_route])
+        .manage(RocketWebhook::builder().webhook(bearer).build());
+    let client = Client::tracked(rocket).unwrap();
+
+    let send = |client: &Client, path: &'static str, authorization: &'static str| {
+        client
+            .post(path)
+            .header(Header::new("Authorization", authorization))
+            .body("Hello token")
+            .dispatch()
+            .status()
+    };
+
+    assert_eq!(
+        send(&client, "/bearer/token", "Bearer bearer-token"),
+        Status::Ok
+    );
+    assert_eq!(
+        send(&client, "/bearer/token", "bearer bearer-token"),
+        Status::Ok
+    );
+    assert_eq!(
+        send(&client, "/bearer/token", "bearer-token"),
+        Status::BadRequest
+    );
+    assert_eq!(
+        send(&client, "/bearer/token", "Bearer wrong-token"),
+        Status::Unauthorized
+    );
+
+    let rocket = rocket::build()
+        .mount("/basic", routes![token_route])
+        .manage(RocketWebhook::builder().webhook(basic).build());
+    let client = Client::tracked(rocket).unwrap();
+
+    // user:old-password
+    assert_eq!(
+        send(&client, "/basic/token", "Basic dXNlcjpvbGQtcGFzc3dvcmQ="),
+        Status::Ok
+    );
+    // user:wrong-password
+    assert_eq!(
+        send(
+            &client,
+            "/basic/token",
+            "Basic dXNlcjp3cm9uZy1wYXNzd29yZA=="
+        ),
+        Status::Unauthorized
+    );
+}
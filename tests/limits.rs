@@ -0,0 +1,97 @@
+//! Tests for body size limits
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    post, routes,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookError, WebhookPayloadRaw, webhooks::built_in::ShopifyWebhook,
+};
+
+#[post("/shopify", data = "<payload>")]
+async fn shopify_route(
+    payload: Result<WebhookPayloadRaw<'_, ShopifyWebhook>, WebhookError>,
+) -> String {
+    match payload {
+        Ok(payload) => String::from_utf8(payload.data).unwrap(),
+        Err(err) => err.to_string(),
+    }
+}
+
+const BODY: &str = "hello shopify";
+const SIGNATURE: &str = "l9ww1bSzk5iGBGdGlyeaPPokoYvxPHgk0w4reAA+jLc=";
+
+fn client(max_body_size: u32, check_content_length: bool) -> Client {
+    let webhook = RocketWebhook::builder()
+        .webhook(ShopifyWebhook::with_secret("test-secret"))
+        .max_body_size(max_body_size)
+        .check_content_length(check_content_length)
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![shopify_route]);
+    Client::tracked(rocket).unwrap()
+}
+
+#[post("/shopify-guard", data = "<payload>")]
+async fn shopify_guard_route(payload: WebhookPayloadRaw<'_, ShopifyWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[test]
+fn within_limit() {
+    let client = client(BODY.len() as u32, true);
+    let response = client
+        .post("/shopify")
+        .header(Header::new("X-Shopify-Hmac-Sha256", SIGNATURE))
+        .header(Header::new("Content-Length", BODY.len().to_string()))
+        .body(BODY)
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some(BODY));
+}
+
+#[test]
+fn too_large() {
+    let client = client(BODY.len() as u32 - 1, false);
+    let response = client
+        .post("/shopify")
+        .header(Header::new("X-Shopify-Hmac-Sha256", SIGNATURE))
+        .body(BODY)
+        .dispatch();
+
+    assert_eq!(
+        response.into_string().as_deref(),
+        Some("Webhook body exceeds the maximum size of 12 bytes")
+    );
+}
+
+#[test]
+fn too_large_status() {
+    let webhook = RocketWebhook::builder()
+        .webhook(ShopifyWebhook::with_secret("test-secret"))
+        .max_body_size(4)
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![shopify_guard_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    // Rejected after reading the body
+    let response = client
+        .post("/shopify-guard")
+        .header(Header::new("X-Shopify-Hmac-Sha256", SIGNATURE))
+        .body(BODY)
+        .dispatch();
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    // Rejected early using the Content-Length header, even without a signature
+    let response = client
+        .post("/shopify-guard")
+        .header(Header::new("Content-Length", BODY.len().to_string()))
+        .body(BODY)
+        .dispatch();
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+}
@@ -1,7 +1,30 @@
-//! Test for multiple webhooks of the same type
+//! Tests for multiple webhooks and accounts of the same type
 
-use rocket::{get, http::Header, local::blocking::Client, routes};
-use rocket_webhook::{RocketWebhook, WebhookPayloadRaw, webhooks::built_in::SlackWebhook};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rocket::{
+    async_trait, get,
+    http::{Header, Status},
+    local::blocking::Client,
+    post, routes,
+    serde::json::json,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookError, WebhookPayloadRaw, WebhookRequest,
+    webhooks::{
+        built_in::{GitHubWebhook, SlackWebhook},
+        interface::hmac::{
+            HmacSecret,
+            resolver::{CachedSecretResolver, SecretResolver},
+        },
+    },
+};
 
 struct SlackAccount1;
 struct SlackAccount2;
@@ -60,3 +83,89 @@ async fn slack1_route(payload: WebhookPayloadRaw<'_, SlackWebhook, SlackAccount1
 async fn slack2_route(payload: WebhookPayloadRaw<'_, SlackWebhook, SlackAccount2>) -> Vec<u8> {
     payload.data
 }
+
+/// Account secrets in Rocket managed state (e.g. a database)
+struct SecretStore;
+
+impl SecretStore {
+    async fn secret(&self, account: &str) -> Result<Option<&'static str>, WebhookError> {
+        match account {
+            "acme" => Ok(Some("test-secret")),
+            "globex" => Ok(Some("other-secret")),
+            "broken" => Err(WebhookError::Secret("database unavailable".into())),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Resolves the secret based on the account in the route
+struct AccountSecrets {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl SecretResolver for AccountSecrets {
+    fn cache_key(&self, req: &dyn WebhookRequest) -> Option<String> {
+        req.with_rocket_request(|req| req.param::<String>(1)?.ok())?
+    }
+
+    async fn resolve(&self, req: &dyn WebhookRequest) -> Result<Vec<HmacSecret>, WebhookError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let Some(account) = self.cache_key(req) else {
+            return Ok(Vec::new());
+        };
+        let store = req
+            .rocket()
+            .and_then(|rocket| rocket.state::<SecretStore>())
+            .expect("secret store is managed");
+        let secret = store.secret(&account).await?;
+        Ok(secret.into_iter().map(HmacSecret::new).collect())
+    }
+}
+
+#[post("/github/<_account>", data = "<payload>")]
+async fn github_account_route(
+    _account: &str,
+    payload: WebhookPayloadRaw<'_, GitHubWebhook>,
+) -> Vec<u8> {
+    payload.data
+}
+
+#[test]
+fn resolved_secrets() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let resolver = AccountSecrets {
+        calls: calls.clone(),
+    };
+    let webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_resolver(CachedSecretResolver::new(
+            resolver,
+            Duration::from_secs(60),
+        )))
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .manage(SecretStore)
+        .mount("/", routes![github_account_route]);
+
+    let client = Client::tracked(rocket).unwrap();
+    let payload = json!({"action": "opened"});
+    let signature = "sha256=6e939b5b3d3e8eba83ff81dde0030a8f2190d965e8bec7a17842863e979c4d7d";
+    let send = |account: &str| {
+        client
+            .post(format!("/github/{account}"))
+            .header(Header::new("X-Hub-Signature-256", signature))
+            .json(&payload)
+            .dispatch()
+            .status()
+    };
+
+    assert_eq!(send("acme"), Status::Ok);
+    assert_eq!(send("acme"), Status::Ok);
+    assert_eq!(calls.load(Ordering::SeqCst), 1, "secrets should be cached");
+
+    assert_eq!(send("globex"), Status::Unauthorized);
+    assert_eq!(send("unknown"), Status::Unauthorized);
+    assert_eq!(send("broken"), Status::InternalServerError);
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
@@ -0,0 +1,169 @@
+//! Tests for signing and dispatching outbound webhooks
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use rocket::{
+    State, async_trait,
+    http::{Header, Status},
+    local::asynchronous::Client,
+    post, routes,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw,
+    outbound::{
+        dispatcher::{WebhookDispatcher, WebhookEndpoint, WebhookTransport},
+        signer::{Hmac256Signer, StandardSigner},
+    },
+    webhooks::{built_in::StandardWebhook, generic::Hmac256Webhook},
+};
+
+/// Sends webhooks to the local Rocket instance
+struct LocalTransport(Client);
+
+#[async_trait]
+impl WebhookTransport for LocalTransport {
+    async fn send(
+        &self,
+        url: &str,
+        headers: &[(String, String)],
+        body: &[u8],
+    ) -> Result<u16, String> {
+        let mut request = self.0.post(url.to_owned()).body(body);
+        for (name, value) in headers {
+            request = request.header(Header::new(name.clone(), value.clone()));
+        }
+        Ok(request.dispatch().await.status().code)
+    }
+}
+
+/// Number of requests that should fail before succeeding
+struct FailFirst(AtomicUsize);
+
+#[post("/standard", data = "<payload>")]
+async fn standard_route(
+    payload: WebhookPayloadRaw<'_, StandardWebhook>,
+    fail_first: &State<FailFirst>,
+) -> Result<Vec<u8>, Status> {
+    let remaining = fail_first.0.load(Ordering::SeqCst);
+    if remaining > 0 {
+        fail_first.0.store(remaining - 1, Ordering::SeqCst);
+        return Err(Status::ServiceUnavailable);
+    }
+    Ok(payload.data)
+}
+
+#[post("/hmac", data = "<payload>")]
+async fn hmac_route(payload: WebhookPayloadRaw<'_, Hmac256Webhook>) -> Vec<u8> {
+    payload.data
+}
+
+const SECRET: &str = "whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw";
+
+async fn dispatcher(fail_first: usize) -> WebhookDispatcher<LocalTransport> {
+    let standard = RocketWebhook::builder()
+        .webhook(StandardWebhook::with_secret(SECRET).unwrap())
+        .build();
+    let hmac = RocketWebhook::builder()
+        .webhook(
+            Hmac256Webhook::builder()
+                .secret("my-custom-hmac-secret")
+                .expected_signatures(|req| {
+                    req.header("Signature-SHA256")
+                        .and_then(|header| hex::decode(header).ok())
+                        .map(|header| vec![header])
+                })
+                .body_suffix(|req, (min_t, max_t)| {
+                    req.header("Timestamp")
+                        .filter(|t| t.parse::<u32>().is_ok_and(|t| t >= min_t && t <= max_t))
+                        .map(|t| t.as_bytes().to_vec())
+                        .ok_or_else(|| {
+                            rocket_webhook::WebhookError::Timestamp("Invalid timestamp".into())
+                        })
+                })
+                .build(),
+        )
+        .build();
+    let rocket = rocket::build()
+        .manage(standard)
+        .manage(hmac)
+        .manage(FailFirst(AtomicUsize::new(fail_first)))
+        .mount("/", routes![standard_route, hmac_route]);
+
+    WebhookDispatcher::builder()
+        .transport(LocalTransport(Client::tracked(rocket).await.unwrap()))
+        .retry_delays([Duration::ZERO, Duration::from_millis(10)])
+        .build()
+}
+
+#[rocket::async_test]
+async fn standard_delivery() {
+    let dispatcher = dispatcher(0).await;
+    let endpoint = WebhookEndpoint::new("/standard", StandardSigner::with_secret(SECRET).unwrap());
+
+    let delivery = dispatcher
+        .dispatch(&endpoint, "msg_1", br#"{"type":"ping"}"#)
+        .await;
+    assert!(delivery.is_success());
+    assert_eq!(delivery.id, "msg_1");
+    assert_eq!(delivery.attempts.len(), 1);
+    assert_eq!(delivery.attempts[0].status, Some(200));
+}
+
+#[rocket::async_test]
+async fn retries() {
+    let dispatcher = dispatcher(2).await;
+    let endpoint = WebhookEndpoint::new("/standard", StandardSigner::with_secret(SECRET).unwrap());
+
+    let delivery = dispatcher.dispatch(&endpoint, "msg_2", b"retried").await;
+    assert!(delivery.is_success());
+    let attempts: Vec<_> = delivery
+        .attempts
+        .iter()
+        .map(|attempt| (attempt.attempt, attempt.status))
+        .collect();
+    assert_eq!(attempts, [(1, Some(503)), (2, Some(503)), (3, Some(200))]);
+}
+
+#[rocket::async_test]
+async fn wrong_secret() {
+    let dispatcher = dispatcher(0).await;
+    let endpoint = WebhookEndpoint::new(
+        "/standard",
+        StandardSigner::with_secret("whsec_d3Jvbmctc2VjcmV0").unwrap(),
+    );
+
+    let delivery = dispatcher.dispatch(&endpoint, "msg_3", b"rejected").await;
+    assert!(!delivery.is_success());
+    assert_eq!(delivery.attempts.len(), 3);
+    assert!(
+        delivery
+            .attempts
+            .iter()
+            .all(|attempt| attempt.status == Some(401))
+    );
+}
+
+#[rocket::async_test]
+async fn generic_hmac_delivery() {
+    let dispatcher = dispatcher(0).await;
+    // closures can capture per-endpoint config
+    let signature_header = String::from("Signature-SHA256");
+    let signer = Hmac256Signer::builder()
+        .secret("my-custom-hmac-secret")
+        .headers(move |_id, timestamp, signature| {
+            vec![
+                (signature_header.clone(), hex::encode(signature)),
+                ("Timestamp".into(), timestamp.to_string()),
+            ]
+        })
+        .body_suffix(|_id, timestamp| timestamp.to_string().into_bytes())
+        .build();
+    let endpoint = WebhookEndpoint::new("/hmac", signer);
+
+    let delivery = dispatcher.dispatch(&endpoint, "msg_4", b"Hello").await;
+    assert!(delivery.is_success());
+    assert_eq!(delivery.attempts.len(), 1);
+}
@@ -0,0 +1,295 @@
+//! Tests for public keys fetched from a URL
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use rocket::{
+    State, async_trait, get,
+    http::{Header, Status},
+    local::asynchronous::Client,
+    post, routes,
+    tokio::{self, time::sleep},
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw,
+    webhooks::{
+        generic::EcdsaP256Webhook,
+        interface::public_key::{
+            algorithms::p256::EcdsaP256Asn1,
+            remote::{KeyFetcher, RemotePublicKeys},
+        },
+    },
+};
+
+const MESSAGE: &str = "Hello JWKS";
+const KEY_1: (&str, &str) = (
+    "yyRGzLbLo5mopXVoY1T6I0Oz2gSSEfaj8zOs61rYa7o",
+    "2I5PEddilaVQaJDYNJpoLt3dlcajJmHKCBreiO5PGmk",
+);
+const SIG_1: &str = "304402206ab7440d2f460073cb7b33dc1242669843fe153cc4b9ad7c88672f3824f45c630220\
+    2b54b7e4fa6dc3f7f92bf66f2286266caad12b75c9ba7fcf38529ce84b5defbf";
+const KEY_2: (&str, &str) = (
+    "m9bDIxPEqELtZp-GuZmusacGpf8wMeWtR-WmTYa104o",
+    "PRNBBCcZuG_MY6F73BWBO0AujWXZcOhUQK_bnLGRam8",
+);
+const SIG_2: &str = "304502210094a8dfc2a4429eb8f1ee9fede8a25c54799fe537d1aaf7ef22b4ee7b5f756ff502\
+    207321f6df4616135223a6f9e16cf4dfb929265c400e2b006a2440922b313a6ac4";
+const KEY_3: (&str, &str) = (
+    "_FTj_7ImX0MvV9D7d10lh0ELo6fVoKAzVrE6dVNwg-M",
+    "uSACkrNuzKC0F-AtLo7_u6GSm4LfhY7AkxnclDUmKZE",
+);
+const SIG_3: &str = "30440220344753917dde4c02a114a5efb4faca13fe1e7651b92350cf06ff797bfe4b04d80220\
+    269d2ff55f417eae600c3289055c5a7ae2345dff895212a3157d103eb2678493";
+
+/// Build a JWKS with the key IDs and P-256 keys
+fn jwks(keys: &[(&str, (&str, &str))]) -> String {
+    let keys: Vec<_> = (keys.iter())
+        .map(|(kid, (x, y))| {
+            format!(r#"{{"kty":"EC","crv":"P-256","kid":"{kid}","x":"{x}","y":"{y}"}}"#)
+        })
+        .collect();
+    format!(r#"{{"keys":[{}]}}"#, keys.join(","))
+}
+
+/// Local stand-in for the provider's key server
+#[derive(Default)]
+struct KeyServer {
+    document: Mutex<String>,
+    fetches: AtomicUsize,
+}
+
+impl KeyServer {
+    fn serve(&self, document: String) {
+        *self.document.lock().unwrap() = document;
+    }
+
+    fn fetches(&self) -> usize {
+        self.fetches.load(Ordering::SeqCst)
+    }
+}
+
+#[get("/jwks")]
+async fn jwks_route(server: &State<Arc<KeyServer>>) -> Result<String, Status> {
+    server.fetches.fetch_add(1, Ordering::SeqCst);
+    sleep(Duration::from_millis(20)).await;
+    match server.document.lock().unwrap().clone() {
+        document if document.is_empty() => Err(Status::NotFound),
+        document => Ok(document),
+    }
+}
+
+/// Fetches keys from the local key server
+struct LocalFetcher(Client);
+
+#[async_trait]
+impl KeyFetcher for LocalFetcher {
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, String> {
+        let response = self.0.get(url.to_owned()).dispatch().await;
+        if response.status() != Status::Ok {
+            return Err(format!("Received status {}", response.status()));
+        }
+        Ok(response.into_bytes().await.unwrap_or_default())
+    }
+}
+
+async fn key_server() -> (Arc<KeyServer>, LocalFetcher) {
+    let server = Arc::new(KeyServer::default());
+    let rocket = rocket::build()
+        .manage(server.clone())
+        .mount("/", routes![jwks_route]);
+    let client = Client::untracked(rocket).await.unwrap();
+    (server, LocalFetcher(client))
+}
+
+#[post("/webhook", data = "<payload>")]
+async fn webhook_route(payload: WebhookPayloadRaw<'_, EcdsaP256Webhook>) -> Vec<u8> {
+    payload.data
+}
+
+async fn webhook_client(keys: RemotePublicKeys<EcdsaP256Asn1>) -> Client {
+    let webhook = EcdsaP256Webhook::builder()
+        .remote_keys(keys)
+        .key_id(|req| req.header("Key-Id").map(str::to_owned))
+        .expected_signatures(|req| {
+            req.header("Signature")
+                .and_then(|header| hex::decode(header).ok())
+                .map(|header| vec![header])
+        })
+        .build();
+    let rocket = rocket::build()
+        .manage(RocketWebhook::builder().webhook(webhook).build())
+        .mount("/", routes![webhook_route]);
+    Client::untracked(rocket).await.unwrap()
+}
+
+async fn send(client: &Client, kid: &str, signature: &str) -> Status {
+    client
+        .post("/webhook")
+        .header(Header::new("Key-Id", kid.to_owned()))
+        .header(Header::new("Signature", signature.to_owned()))
+        .body(MESSAGE)
+        .dispatch()
+        .await
+        .status()
+}
+
+#[rocket::async_test]
+async fn caches_and_selects_by_key_id() {
+    let (server, fetcher) = key_server().await;
+    server.serve(jwks(&[("k1", KEY_1), ("k2", KEY_2)]));
+    let keys = RemotePublicKeys::builder()
+        .url("/jwks")
+        .fetcher(fetcher)
+        .build();
+    let client = webhook_client(keys).await;
+
+    assert_eq!(send(&client, "k1", SIG_1).await, Status::Ok);
+    assert_eq!(send(&client, "k2", SIG_2).await, Status::Ok);
+    assert_eq!(send(&client, "k2", SIG_1).await, Status::Unauthorized);
+    assert_eq!(server.fetches(), 1);
+
+    // Unknown key IDs are only refetched once per refetch interval
+    assert_eq!(send(&client, "k3", SIG_3).await, Status::Unauthorized);
+    assert_eq!(server.fetches(), 1);
+}
+
+#[rocket::async_test]
+async fn refetches_unknown_and_rotated_keys() {
+    let (server, fetcher) = key_server().await;
+    server.serve(jwks(&[("k1", KEY_1)]));
+    let keys = RemotePublicKeys::builder()
+        .url("/jwks")
+        .fetcher(fetcher)
+        .refetch_interval(Duration::ZERO)
+        .build();
+    let client = webhook_client(keys).await;
+
+    assert_eq!(send(&client, "k1", SIG_1).await, Status::Ok);
+    assert_eq!(server.fetches(), 1);
+
+    // New key ID
+    server.serve(jwks(&[("k1", KEY_1), ("k2", KEY_2)]));
+    assert_eq!(send(&client, "k2", SIG_2).await, Status::Ok);
+    assert_eq!(server.fetches(), 2);
+
+    // Key rotated without changing the ID
+    server.serve(jwks(&[("k1", KEY_3)]));
+    assert_eq!(send(&client, "k1", SIG_3).await, Status::Ok);
+    assert_eq!(server.fetches(), 3);
+    assert_eq!(send(&client, "k1", SIG_3).await, Status::Ok);
+    assert_eq!(server.fetches(), 3);
+
+    // Invalid signature is only refetched once
+    assert_eq!(send(&client, "k1", SIG_1).await, Status::Unauthorized);
+    assert_eq!(server.fetches(), 4);
+}
+
+#[rocket::async_test]
+async fn expires_cached_keys() {
+    let (server, fetcher) = key_server().await;
+    server.serve(jwks(&[("k1", KEY_1)]));
+    let keys = RemotePublicKeys::<EcdsaP256Asn1>::builder()
+        .url("/jwks")
+        .fetcher(fetcher)
+        .ttl(Duration::from_millis(100))
+        .build();
+
+    assert!(keys.key(Some("k1")).await.is_ok());
+    assert!(keys.key(Some("k1")).await.is_ok());
+    assert_eq!(server.fetches(), 1);
+
+    sleep(Duration::from_millis(150)).await;
+    assert!(keys.key(Some("k1")).await.is_ok());
+    assert_eq!(server.fetches(), 2);
+}
+
+#[rocket::async_test]
+async fn keeps_stale_keys_when_fetch_fails() {
+    let (server, fetcher) = key_server().await;
+    server.serve(jwks(&[("k1", KEY_1)]));
+    let keys = RemotePublicKeys::builder()
+        .url("/jwks")
+        .fetcher(fetcher)
+        .ttl(Duration::from_millis(100))
+        .refetch_interval(Duration::from_millis(200))
+        .build();
+    let client = webhook_client(keys).await;
+    assert_eq!(send(&client, "k1", SIG_1).await, Status::Ok);
+
+    // The expired keys are still used, and the fetch is retried after the refetch interval
+    server.serve(String::new());
+    sleep(Duration::from_millis(150)).await;
+    assert_eq!(send(&client, "k1", SIG_1).await, Status::Ok);
+    assert_eq!(send(&client, "k1", SIG_1).await, Status::Ok);
+    assert_eq!(server.fetches(), 2);
+
+    server.serve(jwks(&[("k1", KEY_2)]));
+    sleep(Duration::from_millis(250)).await;
+    assert_eq!(send(&client, "k1", SIG_2).await, Status::Ok);
+    assert_eq!(server.fetches(), 3);
+}
+
+#[rocket::async_test]
+async fn deduplicates_concurrent_fetches() {
+    let (server, fetcher) = key_server().await;
+    server.serve(jwks(&[("k1", KEY_1), ("k2", KEY_2)]));
+    let keys = RemotePublicKeys::<EcdsaP256Asn1>::builder()
+        .url("/jwks")
+        .fetcher(fetcher)
+        .build();
+
+    let (key_1, key_2, key_3) =
+        tokio::join!(keys.key(Some("k1")), keys.key(Some("k2")), keys.key(None));
+    assert!(key_1.is_ok() && key_2.is_ok() && key_3.is_ok());
+    assert_eq!(server.fetches(), 1);
+}
+
+#[rocket::async_test]
+async fn key_formats_and_errors() {
+    let (server, fetcher) = key_server().await;
+    let keys = RemotePublicKeys::<EcdsaP256Asn1>::builder()
+        .url("/jwks")
+        .fetcher(fetcher)
+        .ttl(Duration::ZERO)
+        .build();
+
+    // Fetch failure
+    let err = keys.key(None).await.err().unwrap();
+    assert_eq!(err.status(), Status::InternalServerError);
+
+    // JWKS without valid keys for the algorithm
+    server.serve(
+        r#"{"keys":[{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}]}"#
+            .into(),
+    );
+    let err = keys.key(None).await.err().unwrap();
+    assert!(err.to_string().contains("no valid keys"), "{err}");
+
+    // Keys of other types are skipped
+    let (x, y) = KEY_1;
+    let ec_key = format!(r#"{{"kty":"EC","crv":"P-256","kid":"k1","x":"{x}","y":"{y}"}}"#);
+    server.serve(format!(
+        r#"{{"keys":[{{"kty":"RSA","kid":"rsa","n":"AQAB","e":"AQAB"}},{ec_key}]}}"#
+    ));
+    assert!(keys.key(Some("k1")).await.is_ok());
+    assert!(keys.key(Some("rsa")).await.is_err());
+
+    // Single JWK
+    server.serve(ec_key);
+    assert!(keys.key(Some("k1")).await.is_ok());
+
+    // PEM (without a key ID)
+    server.serve(
+        "-----BEGIN PUBLIC KEY-----
+        MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEPbltMw+jO8Cj9Xx/O3Aqm/UvD6d/
+        RNHG/kLNT5JNhj2mxQpX4JUEObMz6qPti0gXtKhA8DhmFP5gvPo1/2U91Q==
+        -----END PUBLIC KEY-----"
+            .into(),
+    );
+    assert!(keys.key(Some("any")).await.is_ok());
+}
@@ -0,0 +1,288 @@
+//! Tests for replay protection
+
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+    post, routes,
+    serde::json::json,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw,
+    replay::{MemoryReplayStore, ReplayStore},
+    testing::{TestSigner, now},
+    webhooks::{
+        built_in::{GitHubWebhook, GitLabWebhook, ShopifyWebhook, StripeWebhook},
+        generic::TokenWebhook,
+    },
+};
+
+#[post("/github", data = "<payload>")]
+async fn github_route(payload: WebhookPayloadRaw<'_, GitHubWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/token", data = "<payload>")]
+async fn token_route(payload: WebhookPayloadRaw<'_, TokenWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/gitlab", data = "<payload>")]
+async fn gitlab_route(payload: WebhookPayloadRaw<'_, GitLabWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/shopify", data = "<payload>")]
+async fn shopify_route(payload: WebhookPayloadRaw<'_, ShopifyWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/stripe", data = "<payload>")]
+async fn stripe_route(payload: WebhookPayloadRaw<'_, StripeWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+const SIGNATURE: &str = "sha256=6e939b5b3d3e8eba83ff81dde0030a8f2190d965e8bec7a17842863e979c4d7d";
+
+#[test]
+fn duplicate_delivery() {
+    let webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_secret("test-secret"))
+        .replay_store(MemoryReplayStore::new())
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![github_route]);
+    let client = Client::tracked(rocket).unwrap();
+    let payload = json!({"action": "opened"});
+
+    let send = |delivery_id: &'static str| {
+        client
+            .post("/github")
+            .header(Header::new("X-Hub-Signature-256", SIGNATURE))
+            .header(Header::new("X-GitHub-Delivery", delivery_id))
+            .json(&payload)
+            .dispatch()
+            .status()
+    };
+
+    assert_eq!(send("delivery-1"), Status::Ok);
+    assert_eq!(send("delivery-1"), Status::Conflict);
+    // the delivery ID isn't signed, so the same request with a different ID is accepted
+    assert_eq!(send("delivery-2"), Status::Ok);
+}
+
+#[test]
+fn unsigned_delivery_id() {
+    let webhook = RocketWebhook::builder()
+        .webhook(ShopifyWebhook::with_secret("shopify-secret"))
+        .replay_store(MemoryReplayStore::new())
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![shopify_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    let body = r#"{"id":820982911946154508}"#;
+    let headers =
+        ShopifyWebhook::with_secret("shopify-secret").signed_headers(body.as_bytes(), now());
+    let send = |webhook_id: Option<&'static str>| {
+        let mut request = client.post("/shopify").body(body);
+        for (name, value) in &headers {
+            let value = match webhook_id {
+                Some(id) if name == "X-Shopify-Webhook-Id" => id.to_owned(),
+                _ => value.clone(),
+            };
+            request = request.header(Header::new(name.clone(), value));
+        }
+        request.dispatch().status()
+    };
+
+    // Shopify's retries are rejected
+    assert_eq!(send(None), Status::Ok);
+    assert_eq!(send(None), Status::Conflict);
+    // but the X-Shopify-Webhook-Id header isn't signed, so it doesn't protect against replays
+    assert_eq!(send(Some("other-id")), Status::Ok);
+}
+
+#[test]
+fn invalid_delivery_not_recorded() {
+    let webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_secret("test-secret"))
+        .replay_store(MemoryReplayStore::new())
+        .replay_status(Status::Ok)
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![github_route]);
+    let client = Client::tracked(rocket).unwrap();
+    let payload = json!({"action": "opened"});
+
+    // Invalid signature shouldn't record the delivery ID
+    let wrong_signature = "sha256=6e939b5b3d3e8eba83ff81dde0030a8f2190d965e8bec7a17842863e979c4d7e";
+    let response = client
+        .post("/github")
+        .header(Header::new("X-Hub-Signature-256", wrong_signature))
+        .header(Header::new("X-GitHub-Delivery", "delivery-1"))
+        .json(&payload)
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response = client
+        .post("/github")
+        .header(Header::new("X-Hub-Signature-256", SIGNATURE))
+        .header(Header::new("X-GitHub-Delivery", "delivery-1"))
+        .json(&payload)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().as_deref(),
+        Some(r#"{"action":"opened"}"#)
+    );
+
+    // Duplicate responds with configured status, without calling the route handler
+    let response = client
+        .post("/github")
+        .header(Header::new("X-Hub-Signature-256", SIGNATURE))
+        .header(Header::new("X-GitHub-Delivery", "delivery-1"))
+        .json(&payload)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_ne!(
+        response.into_string().as_deref(),
+        Some(r#"{"action":"opened"}"#)
+    );
+}
+
+#[test]
+fn generic_delivery_id() {
+    let id_header = String::from("X-Delivery-Id");
+    let token = TokenWebhook::builder()
+        .header("X-Webhook-Token")
+        .token("secret")
+        .delivery_id(move |req| req.header(&id_header).map(str::to_owned))
+        .build();
+    let webhook = RocketWebhook::builder()
+        .webhook(token)
+        .replay_store(MemoryReplayStore::new())
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![token_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    let send = |delivery_id: &'static str| {
+        client
+            .post("/token")
+            .header(Header::new("X-Webhook-Token", "secret"))
+            .header(Header::new("X-Delivery-Id", delivery_id))
+            .body("Hello token")
+            .dispatch()
+            .status()
+    };
+
+    assert_eq!(send("delivery-1"), Status::Ok);
+    assert_eq!(send("delivery-1"), Status::Conflict);
+    assert_eq!(send("delivery-2"), Status::Ok);
+}
+
+#[test]
+fn reformatted_signature_header() {
+    let webhook = RocketWebhook::builder()
+        .webhook(StripeWebhook::with_secret("whsec_test"))
+        .replay_store(MemoryReplayStore::new())
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![stripe_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    let body = r#"{"type":"charge.succeeded"}"#;
+    let timestamp = now();
+    let (name, value) = StripeWebhook::with_secret("whsec_test")
+        .signed_headers(body.as_bytes(), timestamp)
+        .remove(0);
+    let send = |value: String| {
+        client
+            .post("/stripe")
+            .header(Header::new(name.clone(), value))
+            .body(body)
+            .dispatch()
+            .status()
+    };
+
+    assert_eq!(send(value.clone()), Status::Ok);
+    // the signature still verifies, but the delivery is the same
+    let signature = value.split_once(",v1=").unwrap().1;
+    let reformatted = [
+        value
+            .to_uppercase()
+            .replacen("T=", "t=", 1)
+            .replacen("V1=", "v1=", 1),
+        format!("v1={signature},t={timestamp}"),
+        format!("{value},v1={}", "0".repeat(64)),
+        format!("{value},v0={signature}"),
+    ];
+    for value in reformatted {
+        assert_eq!(send(value.clone()), Status::Conflict, "{value}");
+    }
+
+    // a different body at the same timestamp is a new delivery
+    let other_body = r#"{"type":"charge.failed"}"#;
+    let (name, value) = StripeWebhook::with_secret("whsec_test")
+        .signed_headers(other_body.as_bytes(), timestamp)
+        .remove(0);
+    let response = client
+        .post("/stripe")
+        .header(Header::new(name, value))
+        .body(other_body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[test]
+fn gitlab_signed_delivery_id() {
+    const SECRET: &str = "whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw";
+    let webhook = RocketWebhook::builder()
+        .webhook(GitLabWebhook::with_signing_token(SECRET).unwrap())
+        .replay_store(MemoryReplayStore::new())
+        .build();
+    let rocket = rocket::build()
+        .manage(webhook)
+        .mount("/", routes![gitlab_route]);
+    let client = Client::tracked(rocket).unwrap();
+
+    // the unsigned Idempotency-Key header is ignored when the delivery is signed
+    let body = r#"{"object_kind":"push"}"#;
+    let headers = GitLabWebhook::with_signing_token(SECRET)
+        .unwrap()
+        .signed_headers(body.as_bytes(), now());
+    let send = |idempotency_key: &'static str| {
+        let mut request = client
+            .post("/gitlab")
+            .header(Header::new("Idempotency-Key", idempotency_key))
+            .body(body);
+        for (name, value) in &headers {
+            request = request.header(Header::new(name.clone(), value.clone()));
+        }
+        request.dispatch().status()
+    };
+    assert_eq!(send("key-1"), Status::Ok);
+    assert_eq!(send("key-2"), Status::Conflict);
+}
+
+#[rocket::async_test]
+async fn memory_store_evicts_expired() {
+    let store = MemoryReplayStore::new();
+    let now = now();
+
+    assert!(store.insert("old", now - 1).await);
+    assert_eq!(store.len(), 1);
+    assert!(store.insert("new", now + 60).await);
+    assert_eq!(store.len(), 1);
+    assert!(!store.insert("new", now + 60).await);
+
+    // an expired ID can be inserted again
+    assert!(store.insert("old", now + 60).await);
+    assert!(!store.insert("old", now + 60).await);
+    assert_eq!(store.len(), 2);
+}
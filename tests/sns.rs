@@ -0,0 +1,237 @@
+//! Tests for the AWS SNS webhook
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use rocket::{
+    async_trait,
+    http::{Header, Status},
+    local::blocking::Client,
+    post, routes,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookError, WebhookPayload,
+    replay::MemoryReplayStore,
+    webhooks::{
+        built_in::{SnsMessage, SnsMessageType, SnsWebhook},
+        interface::public_key::remote::KeyFetcher,
+    },
+};
+
+const CERT_URL: &str = "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem";
+const TOPIC_ARN: &str = "arn:aws:sns:us-east-1:123456789012:my-topic";
+const CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBrDCCARWgAwIBAgIBATANBgkqhkiG9w0BAQsFADAcMRowGAYDVQQDDBFzbnMu
+YW1hem9uYXdzLmNvbTAeFw0yNTAxMDEwMDAwMDBaFw0zNDEyMzAwMDAwMDBaMBwx
+GjAYBgNVBAMMEXNucy5hbWF6b25hd3MuY29tMIGfMA0GCSqGSIb3DQEBAQUAA4GN
+ADCBiQKBgQC8yaYqrF1rJ+/oyi4NQ7R/m1rU1txXqjKqDdYHUQJP3KlbNE5TqA0s
+hzP9KA69Ike6Oz3GeQBDbZiMzjeDxt3tH8CELInhgh9lISesHX4vBGgJR2o5kHh5
+tI9TmBVsJEsP7fo+KGwVyhqhlrW0wao256rwkYM+frt2HGNcXv20NQIDAQABMA0G
+CSqGSIb3DQEBCwUAA4GBAB0ADBD8kdZu9Hlf4CpUBietycG/pnL3aEiEkE6XkOH9
+8hdf+FKiudKoIgjYIeJdin9p56L7apJmVgZwd0KH1cEJUPfVhOo0V7RlOxU9YPld
+aF+lrZiksSN2vNq1Z/G4Nde/0Z5GWljmLWWouIYHwqEXQga0kynRuFWlJwWH8Z5r
+-----END CERTIFICATE-----
+";
+
+/// Notification with a subject, signature version 1
+const NOTIFICATION_V1: &str = r#"{"Type": "Notification", "MessageId": "22b80b92-fdea-4c2c-8f9d-bdfb0c7bf324", "TopicArn": "arn:aws:sns:us-east-1:123456789012:my-topic", "Subject": "My First Message", "Message": "Hello world!", "Timestamp": "2025-05-06T16:58:21.017Z", "SignatureVersion": "1", "SigningCertURL": "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem", "UnsubscribeURL": "https://sns.us-east-1.amazonaws.com/?Action=Unsubscribe", "Signature": "W+gsMjjWZygC31ddqf+IehD+UZ42h32zO7IM6OcRCNOx761js5e8ITtcnuiCH3E2DLkudBnfWSD9Ul1cf+HP39VN1SCjM50Ud/qlQYwaDrNuYpXJsN7syT/vqzs2UZra22d2olhWUSlgl6hhBNH4h7zbouQNcVo/Y/uxgw0+9ew="}"#;
+/// Notification without a subject, signature version 2
+const NOTIFICATION_V2: &str = r#"{"Type": "Notification", "MessageId": "da41e39f-ea4d-435a-b922-c6aae3915ebe", "TopicArn": "arn:aws:sns:us-east-1:123456789012:my-topic", "Message": "{\"order\":42}", "Timestamp": "2025-05-06T17:02:10.123Z", "SignatureVersion": "2", "SigningCertURL": "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem", "UnsubscribeURL": "https://sns.us-east-1.amazonaws.com/?Action=Unsubscribe", "Signature": "TXhNbjkpEcvr8H+4Hm4rkVzS0YzPDHXEnIGWcr/evrVSeVMyDaPi4Y9gGxdr86ugugoZI05ktwsrqCw6NYvpEc/YryXjrrssNuLl6oI1BH7DXqi6x0/OvcFgt9cSd8QaPcAkJBd/+V47eb4UHbnWnnKFkxJdrMW4jtEK7V3s8xs="}"#;
+/// Subscription confirmation, signature version 2
+const CONFIRMATION: &str = r#"{"Type": "SubscriptionConfirmation", "MessageId": "165545c9-2a5c-472c-8df2-7ff2be2b3b1b", "Token": "2336412f37", "TopicArn": "arn:aws:sns:us-east-1:123456789012:my-topic", "Message": "You have chosen to subscribe to the topic.\nTo confirm the subscription, visit the SubscribeURL included in this message.", "SubscribeURL": "https://sns.us-east-1.amazonaws.com/?Action=ConfirmSubscription&Token=2336412f37", "Timestamp": "2025-05-06T16:50:00.000Z", "SignatureVersion": "2", "SigningCertURL": "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem", "Signature": "jrtpdx7WIrLC3Yh6PKZdQVPhA60j6WF1xZYTchlblOxPRdBddpuLn4j+8pmhZzUuGzsJMY6DikIQNmIm59MPHMeZCLZ/4uWVTISNLVno0+I+SxFpXohckUf9yKJrXAu3QMAfMJm1k7BfwaiWwj9sWMpOQHjdxgUhzV0SRuAfugk="}"#;
+
+/// Serves the test certificate, and counts the fetches
+#[derive(Clone, Default)]
+struct CertFetcher(Arc<AtomicUsize>);
+
+#[async_trait]
+impl KeyFetcher for CertFetcher {
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>, String> {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        match url {
+            CERT_URL => Ok(CERT.into()),
+            _ => Err("Not found".into()),
+        }
+    }
+}
+
+#[post("/sns", data = "<payload>")]
+fn sns_route(payload: WebhookPayload<'_, SnsMessage, SnsWebhook>) -> String {
+    let message = payload.data;
+    match message.message_type {
+        SnsMessageType::Notification => message.message,
+        SnsMessageType::SubscriptionConfirmation => message.subscribe_url.unwrap(),
+        SnsMessageType::UnsubscribeConfirmation => String::new(),
+    }
+}
+
+/// Webhook that accepts the fixed timestamps of the test messages
+fn rocket_webhook(sns: SnsWebhook) -> RocketWebhook<SnsWebhook> {
+    RocketWebhook::builder()
+        .webhook(sns)
+        .timestamp_tolerance(u32::MAX, 15)
+        .build()
+}
+
+#[test]
+fn sns() {
+    let fetcher = CertFetcher::default();
+    let webhook = rocket_webhook(SnsWebhook::new(fetcher.clone()).with_topics([TOPIC_ARN]));
+    let rocket = rocket::build()
+        .mount("/", routes![sns_route])
+        .manage(webhook);
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.post("/sns").body(NOTIFICATION_V1).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().unwrap(), "Hello world!");
+
+    let response = client.post("/sns").body(NOTIFICATION_V2).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().unwrap(), r#"{"order":42}"#);
+
+    let response = client.post("/sns").body(CONFIRMATION).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().unwrap(),
+        "https://sns.us-east-1.amazonaws.com/?Action=ConfirmSubscription&Token=2336412f37"
+    );
+
+    // certificate is cached
+    assert_eq!(fetcher.0.load(Ordering::Relaxed), 1);
+
+    let tampered = NOTIFICATION_V1.replace("Hello world!", "Hello world?");
+    let response = client.post("/sns").body(tampered).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // signature of a v2 message doesn't verify with SHA1
+    let downgraded =
+        NOTIFICATION_V2.replace(r#""SignatureVersion": "2""#, r#""SignatureVersion": "1""#);
+    let response = client.post("/sns").body(downgraded).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+}
+
+#[test]
+fn sns_message_checks() {
+    let fetcher = CertFetcher::default();
+    let webhook = rocket_webhook(
+        SnsWebhook::new(fetcher.clone())
+            .with_topics(["arn:aws:sns:us-east-1:123456789012:other-topic"]),
+    );
+    let no_headers: [(&str, &str); 0] = [];
+
+    let err = webhook
+        .verify(&no_headers, NOTIFICATION_V1.as_bytes())
+        .unwrap_err();
+    assert!(
+        matches!(&err, WebhookError::Signature(e) if e.contains("Topic is not allowed")),
+        "{err:?}"
+    );
+
+    let webhook = rocket_webhook(SnsWebhook::new(fetcher.clone()));
+    let unsupported_version =
+        NOTIFICATION_V2.replace(r#""SignatureVersion": "2""#, r#""SignatureVersion": "3""#);
+    let err = webhook
+        .verify(&no_headers, unsupported_version.as_bytes())
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::Signature(_)), "{err:?}");
+
+    let missing_token = CONFIRMATION.replace(r#""Token": "2336412f37", "#, "");
+    let err = webhook
+        .verify(&no_headers, missing_token.as_bytes())
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::InvalidEvent(_)), "{err:?}");
+
+    let not_sns = r#"{"Type": "Notification"}"#;
+    let err = webhook.verify(&no_headers, not_sns.as_bytes()).unwrap_err();
+    assert!(matches!(err, WebhookError::Deserialize(_)), "{err:?}");
+
+    // the signed timestamp is checked before fetching the certificate
+    let fetcher = CertFetcher::default();
+    let webhook = RocketWebhook::builder()
+        .webhook(SnsWebhook::new(fetcher.clone()))
+        .build();
+    let err = webhook
+        .verify(&no_headers, NOTIFICATION_V1.as_bytes())
+        .unwrap_err();
+    assert!(
+        matches!(&err, WebhookError::Timestamp(t) if t == "2025-05-06T16:58:21.017Z"),
+        "{err:?}"
+    );
+    assert_eq!(fetcher.0.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn sns_replay() {
+    let webhook = RocketWebhook::builder()
+        .webhook(SnsWebhook::new(CertFetcher::default()))
+        .timestamp_tolerance(u32::MAX, 15)
+        .replay_store(MemoryReplayStore::new())
+        .build();
+    let rocket = rocket::build()
+        .mount("/", routes![sns_route])
+        .manage(webhook);
+    let client = Client::tracked(rocket).unwrap();
+
+    let send = |message_id_header: &'static str| {
+        client
+            .post("/sns")
+            .header(Header::new("x-amz-sns-message-id", message_id_header))
+            .body(NOTIFICATION_V1)
+            .dispatch()
+            .status()
+    };
+
+    // the message ID is taken from the signed body, not the header
+    assert_eq!(send("22b80b92-fdea-4c2c-8f9d-bdfb0c7bf324"), Status::Ok);
+    assert_eq!(
+        send("22b80b92-fdea-4c2c-8f9d-bdfb0c7bf324"),
+        Status::Conflict
+    );
+    assert_eq!(send("other-id"), Status::Conflict);
+}
+
+#[test]
+fn sns_cert_url() {
+    let fetcher = CertFetcher::default();
+    let webhook = rocket_webhook(SnsWebhook::new(fetcher.clone()));
+    let no_headers: [(&str, &str); 0] = [];
+
+    let disallowed_urls = [
+        "http://sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem",
+        "https://sns.us-east-1.amazonaws.com.evil.com/SimpleNotificationService-test.pem",
+        "https://sns.us-east-1.amazonaws.com:8443/SimpleNotificationService-test.pem",
+        "https://user@sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem",
+        "https://evil.com/sns.us-east-1.amazonaws.com/SimpleNotificationService-test.pem",
+        "https://sns.evil.com.amazonaws.com/SimpleNotificationService-test.pem",
+        "https://sns.us-east-1.amazonaws.com/SimpleNotificationService-test.txt",
+    ];
+    for url in disallowed_urls {
+        let body = NOTIFICATION_V1.replace(CERT_URL, url);
+        let err = webhook.verify(&no_headers, body.as_bytes()).unwrap_err();
+        assert!(matches!(err, WebhookError::Signature(_)), "{url}: {err:?}");
+    }
+    assert_eq!(fetcher.0.load(Ordering::Relaxed), 0);
+
+    // allowed URL that fails to fetch
+    let missing_cert = CERT_URL.replace("test", "missing");
+    let body = NOTIFICATION_V1.replace(CERT_URL, &missing_cert);
+    let err = webhook.verify(&no_headers, body.as_bytes()).unwrap_err();
+    assert!(matches!(err, WebhookError::Secret(_)), "{err:?}");
+    assert_eq!(fetcher.0.load(Ordering::Relaxed), 1);
+
+    // failed fetch is cached
+    let err = webhook.verify(&no_headers, body.as_bytes()).unwrap_err();
+    assert!(matches!(err, WebhookError::Secret(_)), "{err:?}");
+    assert_eq!(fetcher.0.load(Ordering::Relaxed), 1);
+
+    // custom certificate hosts
+    let webhook =
+        rocket_webhook(SnsWebhook::new(fetcher.clone()).with_cert_hosts(["certs.example.com"]));
+    let err = webhook
+        .verify(&no_headers, NOTIFICATION_V1.as_bytes())
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::Signature(_)), "{err:?}");
+}
@@ -0,0 +1,196 @@
+//! Tests for the signed test request helpers
+
+use rocket::{
+    Build, Rocket,
+    http::Status,
+    local::{asynchronous, blocking::Client},
+    post, routes,
+};
+use rocket_webhook::{
+    RocketWebhook, WebhookPayloadRaw,
+    testing::{DiscordTestKey, LocalRequestExt, SendGridTestKey, TestSigner, now},
+    webhooks::{
+        Webhook,
+        built_in::{
+            DiscordWebhook, GitHubWebhook, GitLabWebhook, MetaWebhook, SendGridWebhook,
+            ShopifyWebhook, SlackWebhook, StandardWebhook, StripeWebhook, TwitchWebhook,
+            ZoomWebhook,
+        },
+    },
+};
+
+#[post("/github", data = "<payload>")]
+async fn github_route(payload: WebhookPayloadRaw<'_, GitHubWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/slack", data = "<payload>")]
+async fn slack_route(payload: WebhookPayloadRaw<'_, SlackWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/stripe", data = "<payload>")]
+async fn stripe_route(payload: WebhookPayloadRaw<'_, StripeWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/shopify", data = "<payload>")]
+async fn shopify_route(payload: WebhookPayloadRaw<'_, ShopifyWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/standard", data = "<payload>")]
+async fn standard_route(payload: WebhookPayloadRaw<'_, StandardWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/gitlab", data = "<payload>")]
+async fn gitlab_route(payload: WebhookPayloadRaw<'_, GitLabWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/zoom", data = "<payload>")]
+async fn zoom_route(payload: WebhookPayloadRaw<'_, ZoomWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/twitch", data = "<payload>")]
+async fn twitch_route(payload: WebhookPayloadRaw<'_, TwitchWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/meta", data = "<payload>")]
+async fn meta_route(payload: WebhookPayloadRaw<'_, MetaWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/discord", data = "<payload>")]
+async fn discord_route(payload: WebhookPayloadRaw<'_, DiscordWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+#[post("/sendgrid", data = "<payload>")]
+async fn sendgrid_route(payload: WebhookPayloadRaw<'_, SendGridWebhook>) -> Vec<u8> {
+    payload.data
+}
+
+const STANDARD_SECRET: &str = "whsec_MfKQ9r8GKYqrTwjUPD8ILPZIo2LaLaSw";
+
+fn manage<W: Webhook + Send + Sync + 'static>(rocket: Rocket<Build>, webhook: W) -> Rocket<Build> {
+    rocket.manage(RocketWebhook::builder().webhook(webhook).build())
+}
+
+fn rocket() -> Rocket<Build> {
+    let rocket = rocket::build().mount(
+        "/",
+        routes![
+            github_route,
+            slack_route,
+            stripe_route,
+            shopify_route,
+            standard_route,
+            gitlab_route,
+            zoom_route,
+            twitch_route,
+            meta_route,
+            discord_route,
+            sendgrid_route
+        ],
+    );
+    let rocket = manage(rocket, GitHubWebhook::with_secret("github-secret"));
+    let rocket = manage(rocket, SlackWebhook::with_secret("slack-secret"));
+    let rocket = manage(rocket, StripeWebhook::with_secret("stripe-secret"));
+    let rocket = manage(rocket, ShopifyWebhook::with_secret("shopify-secret"));
+    let rocket = manage(
+        rocket,
+        StandardWebhook::with_secret(STANDARD_SECRET).unwrap(),
+    );
+    let rocket = manage(
+        rocket,
+        GitLabWebhook::with_signing_token(STANDARD_SECRET).unwrap(),
+    );
+    let rocket = manage(rocket, ZoomWebhook::with_secret("zoom-secret"));
+    let rocket = manage(rocket, TwitchWebhook::with_secret("twitch-secret"));
+    let rocket = manage(rocket, MetaWebhook::with_secret("meta-secret"));
+    let rocket = manage(rocket, DiscordTestKey::new().webhook());
+    manage(rocket, SendGridTestKey::new().webhook())
+}
+
+fn check(client: &Client, path: &str, signer: &impl TestSigner) {
+    let body = r#"{"hello":"world"}"#;
+    let response = client.post(path).signed(signer, body, now()).dispatch();
+    assert_eq!(response.status(), Status::Ok, "{path}");
+    assert_eq!(response.into_string().as_deref(), Some(body), "{path}");
+
+    // Timestamps outside the tolerance are rejected (GitHub, Shopify and Meta don't send timestamps)
+    if !matches!(path, "/github" | "/shopify" | "/meta") {
+        let response = client
+            .post(path)
+            .signed(signer, body, now() - 3600)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest, "{path}");
+    }
+
+    // Requests signed for a different webhook are rejected
+    let response = client
+        .post(path)
+        .signed(&GitHubWebhook::with_secret("wrong-secret"), body, now())
+        .dispatch();
+    assert_ne!(response.status(), Status::Ok, "{path}");
+}
+
+#[test]
+fn signed_requests() {
+    let client = Client::tracked(rocket()).unwrap();
+    check(
+        &client,
+        "/github",
+        &GitHubWebhook::with_secret("github-secret"),
+    );
+    check(
+        &client,
+        "/slack",
+        &SlackWebhook::with_secret("slack-secret"),
+    );
+    check(
+        &client,
+        "/stripe",
+        &StripeWebhook::with_secret("stripe-secret"),
+    );
+    check(
+        &client,
+        "/shopify",
+        &ShopifyWebhook::with_secret("shopify-secret"),
+    );
+    check(
+        &client,
+        "/standard",
+        &StandardWebhook::with_secret(STANDARD_SECRET).unwrap(),
+    );
+    check(
+        &client,
+        "/gitlab",
+        &GitLabWebhook::with_signing_token(STANDARD_SECRET).unwrap(),
+    );
+    check(&client, "/zoom", &ZoomWebhook::with_secret("zoom-secret"));
+    check(
+        &client,
+        "/twitch",
+        &TwitchWebhook::with_secret("twitch-secret"),
+    );
+    check(&client, "/meta", &MetaWebhook::with_secret("meta-secret"));
+    check(&client, "/discord", &DiscordTestKey::new());
+    check(&client, "/sendgrid", &SendGridTestKey::new());
+}
+
+#[rocket::async_test]
+async fn signed_async_request() {
+    let client = asynchronous::Client::tracked(rocket()).await.unwrap();
+    let signer = StripeWebhook::with_secret("stripe-secret");
+    let response = client
+        .post("/stripe")
+        .signed(&signer, "hello", now())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Ok);
+}
@@ -0,0 +1,88 @@
+//! Tests for validating webhooks outside of Rocket
+
+use std::collections::HashMap;
+
+use rocket::http::Status;
+use rocket_webhook::{
+    RocketWebhook, WebhookError, WebhookRequest,
+    replay::MemoryReplayStore,
+    webhooks::built_in::{GitHubWebhook, StripeWebhook},
+};
+
+const GITHUB_BODY: &[u8] = br#"{"action":"opened"}"#;
+const GITHUB_SIGNATURE: &str =
+    "sha256=6e939b5b3d3e8eba83ff81dde0030a8f2190d965e8bec7a17842863e979c4d7d";
+
+#[test]
+fn verify_headers() {
+    let webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_secret("test-secret"))
+        .build();
+
+    let headers = [("x-hub-signature-256", GITHUB_SIGNATURE)];
+    let validated = webhook.verify(&headers, GITHUB_BODY).unwrap();
+    assert_eq!(validated.data, GITHUB_BODY);
+    assert_eq!(validated.secret_index, Some(0));
+
+    let headers = HashMap::from([("X-Hub-Signature-256", GITHUB_SIGNATURE)]);
+    let err = webhook.verify(&headers, b"tampered").unwrap_err();
+    assert!(matches!(err, WebhookError::Signature(_)));
+    assert_eq!(webhook.error_status(&err), Status::Unauthorized);
+
+    let err = webhook
+        .verify(&Vec::<(&str, &str)>::new(), GITHUB_BODY)
+        .unwrap_err();
+    assert!(matches!(err, WebhookError::MissingHeader(_)));
+    assert_eq!(webhook.error_status(&err), Status::BadRequest);
+}
+
+#[test]
+fn no_rocket_request() {
+    let headers: &dyn WebhookRequest = &[("X-GitHub-Event", "push")];
+    assert!(headers.rocket().is_none());
+    assert_eq!(
+        headers.with_rocket_request(|req| req.uri().to_string()),
+        None
+    );
+}
+
+#[test]
+fn verify_limits_and_replays() {
+    let webhook = RocketWebhook::builder()
+        .webhook(GitHubWebhook::with_secret("test-secret"))
+        .replay_store(MemoryReplayStore::new())
+        .max_body_size(GITHUB_BODY.len() as u32)
+        .build();
+
+    let headers = [
+        ("X-Hub-Signature-256", GITHUB_SIGNATURE),
+        ("X-GitHub-Delivery", "delivery-1"),
+    ];
+    assert!(webhook.verify(&headers, GITHUB_BODY).is_ok());
+
+    let err = webhook.verify(&headers, GITHUB_BODY).unwrap_err();
+    assert!(matches!(err, WebhookError::Duplicate(ref id) if id == "delivery-1"));
+    assert_eq!(webhook.error_status(&err), Status::Conflict);
+
+    let too_large = [GITHUB_BODY, b" "].concat();
+    let err = webhook.verify(&headers, &too_large).unwrap_err();
+    assert!(matches!(err, WebhookError::PayloadTooLarge(_)));
+    assert_eq!(webhook.error_status(&err), Status::PayloadTooLarge);
+}
+
+#[rocket::async_test]
+async fn verify_stream() {
+    let webhook = RocketWebhook::builder()
+        .webhook(StripeWebhook::with_secret("test-secret"))
+        .timestamp_tolerance(u32::MAX, 0)
+        .build();
+
+    let body = br#"{"id":"evt_12345","object":"event"}"#;
+    let headers = vec![(
+        "Stripe-Signature".to_string(),
+        "t=1492774577,v1=d08311034a9d558256d1ca3700a3a7f9b22f7ec03e52cca53c5632dcea29b8e7"
+            .to_string(),
+    )];
+    let validated = webhook.verify_stream(&headers, &body[..]).await.unwrap();
+    assert_eq!(validated.data, body);
+}